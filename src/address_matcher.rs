@@ -0,0 +1,146 @@
+//! The `AddressMatcher` struct is defined here which matches the outputs of
+//! block transactions against a large set of addresses locally. Watching
+//! thousands of addresses this way only requires fetching every block once
+//! instead of querying the blockchain index for each address.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use crate::BlockHeight;
+use ergo_lib::chain::transaction::Transaction;
+use ergo_lib::ergo_chain_types::{blake2b256_hash, Digest32};
+use ergo_lib::ergotree_ir::chain::address::AddressEncoder;
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// A compact set of the ErgoTree hashes of watched addresses
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddressMatcher {
+    tree_hashes: HashSet<Digest32>,
+}
+
+impl AddressMatcher {
+    /// Create a new empty `AddressMatcher`
+    pub fn new() -> AddressMatcher {
+        AddressMatcher::default()
+    }
+
+    /// Create an `AddressMatcher` watching all of the given addresses
+    pub fn from_addresses(addresses: &[String]) -> Result<AddressMatcher> {
+        let mut matcher = AddressMatcher::new();
+        for address in addresses {
+            matcher.add_address(address)?;
+        }
+        Ok(matcher)
+    }
+
+    /// Start watching the given address
+    pub fn add_address(&mut self, address: &str) -> Result<()> {
+        let tree = AddressEncoder::unchecked_parse_address_from_str(address)
+            .and_then(|a| Ok(a.script()?))
+            .map_err(|e| NodeError::Other(e.to_string()))?;
+        self.add_tree(&tree)
+    }
+
+    /// Start watching boxes protected by the given ErgoTree
+    pub fn add_tree(&mut self, tree: &ErgoTree) -> Result<()> {
+        self.tree_hashes.insert(tree_hash(tree)?);
+        Ok(())
+    }
+
+    /// Number of watched ErgoTrees
+    pub fn len(&self) -> usize {
+        self.tree_hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree_hashes.is_empty()
+    }
+
+    /// Checks whether the ErgoTree belongs to a watched address
+    pub fn matches(&self, tree: &ErgoTree) -> bool {
+        tree_hash(tree).is_ok_and(|hash| self.tree_hashes.contains(&hash))
+    }
+
+    /// Returns the outputs of the transactions which belong to a watched address
+    pub fn matching_outputs(&self, txs: &[Transaction]) -> Vec<ErgoBox> {
+        txs.iter()
+            .flat_map(|tx| tx.outputs.iter())
+            .filter(|output| self.matches(&output.ergo_tree))
+            .cloned()
+            .collect()
+    }
+}
+
+impl NodeInterface {
+    /// Returns the outputs of the best chain block at the given height which
+    /// belong to an address of the matcher
+    pub fn matching_outputs_at_height(
+        &self,
+        matcher: &AddressMatcher,
+        height: BlockHeight,
+    ) -> Result<Vec<ErgoBox>> {
+        let header_id = self.block_id_at_height(height)?;
+        Ok(matcher.matching_outputs(&self.block_transactions(&header_id)?))
+    }
+
+    /// Blocks the current thread, matching the outputs of every block from
+    /// `from_height` onwards against the matcher once the block has
+    /// `min_confirmations` confirmations. The height and matched outputs of
+    /// every block with matches are passed to `callback`. Watching stops
+    /// when the callback returns `false` or an error is encountered.
+    pub fn watch_addresses<F>(
+        &self,
+        matcher: &AddressMatcher,
+        from_height: BlockHeight,
+        min_confirmations: u64,
+        poll_interval: Duration,
+        mut callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(BlockHeight, Vec<ErgoBox>) -> bool,
+    {
+        let mut next_height = from_height;
+        loop {
+            let confirmed_height =
+                (self.current_block_height()? + 1).saturating_sub(min_confirmations.max(1));
+            while next_height <= confirmed_height {
+                let outputs = self.matching_outputs_at_height(matcher, next_height)?;
+                if !outputs.is_empty() && !callback(next_height, outputs) {
+                    return Ok(());
+                }
+                next_height += 1;
+            }
+            std::thread::sleep(self.poll_interval(poll_interval));
+        }
+    }
+}
+
+/// Hashes the serialized ErgoTree
+fn tree_hash(tree: &ErgoTree) -> Result<Digest32> {
+    tree.sigma_serialize_bytes()
+        .map(|bytes| blake2b256_hash(&bytes))
+        .map_err(|e| NodeError::Other(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::TEST_ADDRESS;
+
+    #[test]
+    fn test_address_matcher() {
+        let watched = "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA";
+        let other = TEST_ADDRESS;
+        let matcher = AddressMatcher::from_addresses(&[watched.to_string()]).unwrap();
+        let tree = |address| {
+            AddressEncoder::unchecked_parse_address_from_str(address)
+                .unwrap()
+                .script()
+                .unwrap()
+        };
+        assert_eq!(matcher.len(), 1);
+        assert!(matcher.matches(&tree(watched)));
+        assert!(!matcher.matches(&tree(other)));
+    }
+}
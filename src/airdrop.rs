@@ -0,0 +1,207 @@
+//! Helpers for distributing a token to many recipients at once, splitting
+//! the payouts into several transactions where needed.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use crate::{NanoErg, TokenID};
+use ergo_lib::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+use ergo_lib::ergo_chain_types::Digest32;
+use ergo_lib::ergotree_ir::chain::address::{Address, AddressEncoder};
+use ergo_lib::ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use ergo_lib::ergotree_ir::chain::token::{Token, TokenId};
+use ergo_lib::wallet::box_selector::{BoxSelector as _, BoxSelectorError, SimpleBoxSelector};
+use ergo_lib::wallet::tx_builder::{TxBuilder, SUGGESTED_TX_FEE};
+use std::convert::{TryFrom, TryInto};
+
+/// Maximum number of recipients paid by a single airdrop transaction
+pub const MAX_AIRDROP_OUTPUTS: usize = 100;
+/// Maximum size in bytes of an airdrop transaction, matching the default
+/// `maxTransactionSize` accepted by nodes
+pub const MAX_AIRDROP_TX_SIZE: usize = 98_304;
+/// Rough size of a single input proof, used to estimate the size of the
+/// signed transaction
+const PROOF_SIZE_ESTIMATE: usize = 100;
+
+/// An unsigned airdrop transaction together with the boxes it spends, which
+/// are required to sign it via `sign_transaction` or an external signer.
+#[derive(Debug, Clone)]
+pub struct AirdropTx {
+    pub unsigned_tx: UnsignedTransaction,
+    pub boxes_to_spend: Vec<ErgoBox>,
+}
+
+impl AirdropTx {
+    /// Estimated size in bytes of the transaction once signed
+    pub fn estimated_size(&self) -> Result<usize> {
+        let bytes = self
+            .unsigned_tx
+            .bytes_to_sign()
+            .map_err(|e| NodeError::Other(e.to_string()))?;
+        Ok(bytes.len() + self.boxes_to_spend.len() * PROOF_SIZE_ESTIMATE)
+    }
+}
+
+impl NodeInterface {
+    /// Builds unsigned transactions paying each recipient the given amount
+    /// of `token_id` in a box holding `per_output_min_ergs` nanoErgs.
+    /// Recipients are split into transactions of at most
+    /// `MAX_AIRDROP_OUTPUTS` outputs which stay within `MAX_AIRDROP_TX_SIZE`.
+    /// Each transaction spends the change of the previous one, thus the
+    /// transactions must be signed and submitted in the returned order.
+    pub fn build_airdrop_tx(
+        &self,
+        token_id: &TokenID,
+        recipients: &[(Address, u64)],
+        per_output_min_ergs: NanoErg,
+    ) -> Result<Vec<AirdropTx>> {
+        let to_error = |e: &dyn std::fmt::Display| NodeError::Other(e.to_string());
+        let token_id: TokenId = Digest32::try_from(token_id.clone())
+            .map_err(|e| to_error(&e))?
+            .into();
+        let change_address =
+            AddressEncoder::unchecked_parse_address_from_str(&self.resolve_change_address()?)
+                .map_err(|e| to_error(&e))?;
+        let change_tree = change_address.script().map_err(|e| to_error(&e))?;
+        let height = self.current_block_height()? as u32;
+
+        let mut available_boxes = self.unspent_boxes_sorted()?;
+        let mut airdrop_txs = vec![];
+        let mut remaining = recipients;
+        while !remaining.is_empty() {
+            let mut chunk_size = remaining.len().min(MAX_AIRDROP_OUTPUTS);
+            let airdrop_tx = loop {
+                let airdrop_tx = build_airdrop_chunk(
+                    &available_boxes,
+                    token_id,
+                    &remaining[..chunk_size],
+                    per_output_min_ergs,
+                    height,
+                    &change_address,
+                )?;
+                if chunk_size == 1 || airdrop_tx.estimated_size()? <= MAX_AIRDROP_TX_SIZE {
+                    break airdrop_tx;
+                }
+                chunk_size /= 2;
+            };
+
+            // Replace the spent boxes with the change of the transaction so
+            // the next transaction can be chained onto it
+            available_boxes.retain(|b| !airdrop_tx.boxes_to_spend.contains(b));
+            let tx_id = airdrop_tx.unsigned_tx.id();
+            let outputs = airdrop_tx.unsigned_tx.output_candidates.iter().enumerate();
+            for (index, output) in outputs.skip(chunk_size) {
+                if output.ergo_tree == change_tree {
+                    let change_box = ErgoBox::from_box_candidate(output, tx_id, index as u16)
+                        .map_err(|e| to_error(&e))?;
+                    available_boxes.insert(0, change_box);
+                }
+            }
+            remaining = &remaining[chunk_size..];
+            airdrop_txs.push(airdrop_tx);
+        }
+        Ok(airdrop_txs)
+    }
+}
+
+/// Builds a single airdrop transaction paying the given recipients, selecting
+/// inputs out of `available_boxes`
+fn build_airdrop_chunk(
+    available_boxes: &[ErgoBox],
+    token_id: TokenId,
+    recipients: &[(Address, u64)],
+    per_output_min_ergs: NanoErg,
+    height: u32,
+    change_address: &Address,
+) -> Result<AirdropTx> {
+    let to_error = |e: &dyn std::fmt::Display| NodeError::Other(e.to_string());
+    let output_value = BoxValue::new(per_output_min_ergs).map_err(|e| to_error(&e))?;
+    let mut outputs = vec![];
+    for (address, amount) in recipients {
+        let tree = address.script().map_err(|e| to_error(&e))?;
+        let mut builder = ErgoBoxCandidateBuilder::new(output_value, tree, height);
+        builder.add_token(Token {
+            token_id,
+            amount: (*amount).try_into().map_err(|e| to_error(&e))?,
+        });
+        outputs.push(builder.build().map_err(|e| to_error(&e))?);
+    }
+
+    let fee = SUGGESTED_TX_FEE();
+    let total_value = per_output_min_ergs * recipients.len() as u64 + *fee.as_u64();
+    let total_tokens: u64 = recipients.iter().map(|(_, amount)| amount).sum();
+    let target_tokens = vec![Token {
+        token_id,
+        amount: total_tokens.try_into().map_err(|e| to_error(&e))?,
+    }];
+    let selection = SimpleBoxSelector::new()
+        .select(
+            available_boxes.to_vec(),
+            BoxValue::new(total_value).map_err(|e| to_error(&e))?,
+            &target_tokens,
+        )
+        .map_err(|e| match e {
+            BoxSelectorError::NotEnoughCoins(_) => NodeError::InsufficientErgsBalance(),
+            e => to_error(&e),
+        })?;
+    let boxes_to_spend = selection.boxes.as_vec().clone();
+    let unsigned_tx = TxBuilder::new(selection, outputs, height, fee, change_address.clone())
+        .build()
+        .map_err(|e| to_error(&e))?;
+    Ok(AirdropTx {
+        unsigned_tx,
+        boxes_to_spend,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{test_address, test_box_with};
+    use ergo_lib::ergotree_ir::chain::ergo_box::{BoxTokens, NonMandatoryRegisters};
+
+    #[test]
+    fn test_build_airdrop_chunk() {
+        let address = test_address();
+        let token_id: TokenId = Digest32::zero().into();
+        let tokens = BoxTokens::from_vec(vec![Token {
+            token_id,
+            amount: 1_000u64.try_into().unwrap(),
+        }])
+        .unwrap();
+        let wallet_box = test_box_with(
+            10_000_000_000,
+            &address.script().unwrap(),
+            Some(tokens),
+            NonMandatoryRegisters::empty(),
+            1,
+            0,
+        );
+        let recipients = vec![(address.clone(), 10), (address.clone(), 20)];
+
+        let airdrop_tx = build_airdrop_chunk(
+            &[wallet_box],
+            token_id,
+            &recipients,
+            1_000_000,
+            10,
+            &address,
+        )
+        .unwrap();
+        let outputs = &airdrop_tx.unsigned_tx.output_candidates;
+        // two payouts, the miner fee and the change
+        assert_eq!(outputs.len(), 4);
+        assert_eq!(*outputs.get(1).unwrap().value.as_u64(), 1_000_000);
+        assert_eq!(airdrop_tx.boxes_to_spend.len(), 1);
+
+        let too_many = vec![(address.clone(), 2_000)];
+        assert!(build_airdrop_chunk(
+            &airdrop_tx.boxes_to_spend,
+            token_id,
+            &too_many,
+            1_000_000,
+            10,
+            &address
+        )
+        .is_err());
+    }
+}
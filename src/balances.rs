@@ -0,0 +1,98 @@
+//! Typed balances of the node wallet including the tokens it holds.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use crate::{BlockHeight, NanoErg, TokenID};
+use serde::Deserialize;
+
+/// The balance of a single token held by the node wallet
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenBalance {
+    pub token_id: TokenID,
+    pub amount: u64,
+    /// Name of the token if its metadata could be resolved
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Number of decimals of the token if its metadata could be resolved
+    #[serde(default)]
+    pub decimals: Option<u32>,
+}
+
+/// The nanoErg and token balances of the node wallet
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct WalletBalances {
+    /// Height of the wallet at which the balances were computed
+    pub height: BlockHeight,
+    pub balance: NanoErg,
+    #[serde(rename = "assets")]
+    pub tokens: Vec<TokenBalance>,
+}
+
+impl WalletBalances {
+    /// The amount of the given token held, or 0 if it is not held
+    pub fn token_amount(&self, token_id: &TokenID) -> u64 {
+        self.tokens
+            .iter()
+            .filter(|t| t.token_id == *token_id)
+            .map(|t| t.amount)
+            .sum()
+    }
+}
+
+impl NodeInterface {
+    /// Get the confirmed nanoErg balance and the full list of tokens held
+    /// in the node wallet. Token names and decimals are resolved via the
+    /// node when available.
+    pub fn wallet_balances(&self) -> Result<WalletBalances> {
+        self.wallet_balances_from("/wallet/balances")
+    }
+
+    /// Get the nanoErg and token balances of the node wallet including
+    /// unconfirmed transactions in the mempool. Pending deposits are the
+    /// difference to the confirmed `wallet_balances`.
+    pub fn wallet_balances_unconfirmed(&self) -> Result<WalletBalances> {
+        self.wallet_balances_from("/wallet/balances/withUnconfirmed")
+    }
+
+    /// Acquires the balances from the given wallet balances endpoint
+    fn wallet_balances_from(&self, endpoint: &str) -> Result<WalletBalances> {
+        let res_json = self.get_json(endpoint)?;
+        if res_json["balance"].is_null() {
+            return Err(NodeError::NodeSyncing);
+        }
+        let mut balances: WalletBalances = serde_json::from_value(res_json.clone())
+            .map_err(|_| NodeError::FailedParsingNodeResponse(res_json.to_string()))?;
+
+        for token in balances.tokens.iter_mut() {
+            if let Ok((name, decimals)) = self.token_name_and_decimals(&token.token_id) {
+                token.name = name;
+                token.decimals = decimals;
+            }
+        }
+        Ok(balances)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsing_wallet_balances() {
+        let node_response_json_str = r#"{
+          "height": 1024,
+          "balance": 2500000000,
+          "assets": [
+            {"tokenId": "0cd8c9f416e5b1ca9f986a7f10a84191dfb85941619e49e53c0dc30ebf83324b", "amount": 1000}
+          ]
+        }"#;
+        let balances: WalletBalances = serde_json::from_str(node_response_json_str).unwrap();
+        assert_eq!(balances.balance, 2500000000);
+        assert_eq!(
+            balances.token_amount(
+                &"0cd8c9f416e5b1ca9f986a7f10a84191dfb85941619e49e53c0dc30ebf83324b".to_string()
+            ),
+            1000
+        );
+        assert_eq!(balances.tokens[0].name, None);
+    }
+}
@@ -0,0 +1,271 @@
+//! Typed access to the extra-index (`/blockchain`) endpoints of the node,
+//! which allow querying arbitrary addresses, boxes and transactions without
+//! relying on the node wallet or a public explorer. Requires the extra
+//! indexer to be enabled on the node.
+use crate::balances::TokenBalance;
+use crate::node_interface::{encode_param, NodeInterface, Result};
+use crate::{BlockHeight, NanoErg};
+use ergo_lib::chain::transaction::TxId;
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use serde_json::{json, Value};
+
+/// A box as returned by the indexer, i.e. the `ErgoBox` together with the
+/// metadata the indexer keeps about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedBox {
+    pub ergo_box: ErgoBox,
+    /// The address protecting the box
+    pub address: String,
+    pub inclusion_height: BlockHeight,
+    /// Position of the box among all boxes ever indexed
+    pub global_index: u64,
+    /// Id of the transaction spending the box, if it is spent
+    pub spent_transaction_id: Option<TxId>,
+    /// Height of the block including the spending transaction, if the
+    /// box is spent and the node reports it
+    pub spending_height: Option<BlockHeight>,
+}
+
+impl IndexedBox {
+    /// Checks whether the box has been spent
+    pub fn is_spent(&self) -> bool {
+        self.spent_transaction_id.is_some()
+    }
+}
+
+/// The indexer metadata of a box, parsed separately from the `ErgoBox`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexedBoxMetadata {
+    address: String,
+    inclusion_height: BlockHeight,
+    #[serde(default)]
+    global_index: u64,
+    #[serde(default)]
+    spent_transaction_id: Option<TxId>,
+    #[serde(default)]
+    spending_height: Option<BlockHeight>,
+}
+
+impl<'de> Deserialize<'de> for IndexedBox {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let json = Value::deserialize(deserializer)?;
+        let metadata: IndexedBoxMetadata =
+            serde_json::from_value(json.clone()).map_err(D::Error::custom)?;
+        let ergo_box = serde_json::from_value(json).map_err(D::Error::custom)?;
+        Ok(IndexedBox {
+            ergo_box,
+            address: metadata.address,
+            inclusion_height: metadata.inclusion_height,
+            global_index: metadata.global_index,
+            spent_transaction_id: metadata.spent_transaction_id,
+            spending_height: metadata.spending_height,
+        })
+    }
+}
+
+/// A transaction as returned by the indexer
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedTransaction {
+    pub id: TxId,
+    pub block_id: String,
+    pub inclusion_height: BlockHeight,
+    /// Timestamp (in milliseconds) of the block including the transaction
+    pub timestamp: u64,
+    /// Position of the transaction within its block
+    pub index: u32,
+    /// Position of the transaction among all transactions ever indexed
+    #[serde(default)]
+    pub global_index: u64,
+    #[serde(rename = "numConfirmations")]
+    pub confirmations: u64,
+    pub inputs: Vec<IndexedBox>,
+    pub outputs: Vec<IndexedBox>,
+    /// Size of the transaction in bytes
+    #[serde(default)]
+    pub size: u32,
+}
+
+/// A page of results of a paginated indexer query
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Total number of results across all pages
+    pub total: u64,
+}
+
+/// The nanoErg and token balance of an address
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Balance {
+    pub nano_ergs: NanoErg,
+    #[serde(default)]
+    pub tokens: Vec<TokenBalance>,
+}
+
+impl Balance {
+    /// The amount of the given token held, or 0 if it is not held
+    pub fn token_amount(&self, token_id: &str) -> u64 {
+        self.tokens
+            .iter()
+            .filter(|t| t.token_id == token_id)
+            .map(|t| t.amount)
+            .sum()
+    }
+}
+
+/// The balance of an address as returned by `/blockchain/balance`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct AddressBalance {
+    /// Balance held in boxes included in the blockchain
+    pub confirmed: Balance,
+    /// Balance according to the unconfirmed transactions in the mempool
+    #[serde(default)]
+    pub unconfirmed: Balance,
+}
+
+impl NodeInterface {
+    /// Get the confirmed and unconfirmed nanoErg and token balance of an
+    /// arbitrary address
+    pub fn address_balance(&self, address: &str) -> Result<AddressBalance> {
+        self.require_indexer()?;
+        self.check_address_network(address)?;
+        self.post_typed("/blockchain/balance", &json!(address))
+    }
+
+    /// Get a page of the unspent boxes protected by an arbitrary address,
+    /// skipping the first `offset` boxes and returning at most `limit`
+    pub fn unspent_boxes_by_address(
+        &self,
+        address: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<ErgoBox>> {
+        self.require_indexer()?;
+        self.check_address_network(address)?;
+        let endpoint = format!("/blockchain/box/unspent/byAddress?offset={offset}&limit={limit}");
+        self.post_typed(&endpoint, &json!(address))
+    }
+
+    /// Get a page of all boxes (spent and unspent) ever protected by an
+    /// arbitrary address, oldest first, skipping the first `offset` boxes and
+    /// returning at most `limit`
+    pub fn boxes_by_address(
+        &self,
+        address: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Page<IndexedBox>> {
+        self.require_indexer()?;
+        self.check_address_network(address)?;
+        let endpoint =
+            format!("/blockchain/box/byAddress?offset={offset}&limit={limit}&sortDirection=asc");
+        self.post_typed(&endpoint, &json!(address))
+    }
+
+    /// Get a page of the transactions involving an arbitrary address,
+    /// skipping the first `offset` transactions and returning at most `limit`
+    pub fn transactions_by_address(
+        &self,
+        address: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Page<IndexedTransaction>> {
+        self.require_indexer()?;
+        self.check_address_network(address)?;
+        let endpoint = format!("/blockchain/transaction/byAddress?offset={offset}&limit={limit}");
+        self.post_typed(&endpoint, &json!(address))
+    }
+
+    /// Get an indexed transaction (confirmed, spent or not) by its id
+    pub fn indexed_transaction_by_id(&self, tx_id: &TxId) -> Result<IndexedTransaction> {
+        self.require_indexer()?;
+        let id = String::from(*tx_id);
+        self.get_typed(&format!(
+            "/blockchain/transaction/byId/{}",
+            encode_param(&id)
+        ))
+    }
+
+    /// Get an indexed box (spent or unspent) by its id
+    pub fn indexed_box_by_id(&self, box_id: &String) -> Result<IndexedBox> {
+        self.require_indexer()?;
+        self.get_typed(&format!("/blockchain/box/byId/{}", encode_param(box_id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indexed_box_json(spent_transaction_id: Option<&str>) -> Value {
+        json!({
+            "boxId": "45482d60f7372105d2a8091c88ac486d33c8b4b3fddd8d7d0c5df470174f5640",
+            "value": 2000000000,
+            "ergoTree": "0008cd02764ea2b0b9b06b5730a4257bba71fd7797eb1ec12bc3ae6025a01d7fba53830e",
+            "assets": [],
+            "additionalRegisters": {},
+            "creationHeight": 1000000,
+            "transactionId": "0000000000000000000000000000000000000000000000000000000000000000",
+            "index": 2,
+            "blockId": "ab".repeat(32),
+            "inclusionHeight": 1000001,
+            "globalIndex": 42,
+            "address": "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA",
+            "spentTransactionId": spent_transaction_id,
+            "mainChain": true
+        })
+    }
+
+    #[test]
+    fn test_parse_address_balance() {
+        let token_id = "08b59b14e4fdd60e5952314adbaa8b4e00bc0f0b676872a5224d3bf8591074cd";
+        let balance_json = json!({
+            "confirmed": {
+                "nanoErgs": 2000000000,
+                "tokens": [{"tokenId": token_id, "amount": 5, "decimals": 2, "name": "Test"}]
+            },
+            "unconfirmed": {"nanoErgs": 0, "tokens": []}
+        });
+        let balance: AddressBalance = serde_json::from_value(balance_json).unwrap();
+        assert_eq!(balance.confirmed.nano_ergs, 2000000000);
+        assert_eq!(balance.confirmed.token_amount(token_id), 5);
+        assert_eq!(balance.confirmed.tokens[0].decimals, Some(2));
+        assert_eq!(balance.unconfirmed, Balance::default());
+    }
+
+    #[test]
+    fn test_parse_indexed_transaction() {
+        let spending_tx_id = "cd".repeat(32);
+        let tx_json = json!({
+            "id": spending_tx_id,
+            "blockId": "ef".repeat(32),
+            "inclusionHeight": 1000100,
+            "timestamp": 1700000000000u64,
+            "index": 3,
+            "globalIndex": 1234,
+            "numConfirmations": 5,
+            "inputs": [indexed_box_json(Some(&spending_tx_id))],
+            "dataInputs": [],
+            "outputs": [indexed_box_json(None)],
+            "size": 200
+        });
+        let page: Page<IndexedTransaction> =
+            serde_json::from_value(json!({"items": [tx_json], "total": 7})).unwrap();
+        assert_eq!(page.total, 7);
+        let tx = page.items[0].clone();
+        assert_eq!(String::from(tx.id), spending_tx_id);
+        assert_eq!(tx.index, 3);
+        assert_eq!(tx.confirmations, 5);
+        assert!(tx.inputs[0].is_spent());
+        assert_eq!(tx.inputs[0].spent_transaction_id, Some(tx.id));
+        let output = &tx.outputs[0];
+        assert!(!output.is_spent());
+        assert_eq!(output.inclusion_height, 1000001);
+        assert_eq!(output.global_index, 42);
+        assert_eq!(*output.ergo_box.value.as_u64(), 2000000000);
+    }
+}
@@ -0,0 +1,125 @@
+//! Typed access to the block endpoints of the node.
+use crate::node_interface::{encode_param, NodeError, NodeInterface, Result};
+use crate::BlockHeight;
+use ergo_lib::chain::transaction::Transaction;
+use ergo_lib::ergo_chain_types::Header;
+
+/// Number of confirmations required by `block_seed_at` before the header of
+/// a block is used as a seed, making it costly for miners to influence it
+pub const MIN_SEED_CONFIRMATIONS: u64 = 10;
+
+/// A public randomness seed derived from the header of a best chain block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockSeed {
+    pub height: BlockHeight,
+    pub header_id: String,
+    /// The header id bytes, which commit to the PoW solution of the block
+    pub seed: [u8; 32],
+}
+
+/// A full block with its header and transactions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FullBlock {
+    pub header: Header,
+    pub transactions: Vec<Transaction>,
+}
+
+impl NodeInterface {
+    /// Get the ids of the block headers at the given height. The first id
+    /// belongs to the block on the best chain.
+    pub fn block_ids_at_height(&self, height: BlockHeight) -> Result<Vec<String>> {
+        self.get_typed(&format!("/blocks/at/{height}"))
+    }
+
+    /// Get the id of the header of the best chain block at the given height
+    pub fn block_id_at_height(&self, height: BlockHeight) -> Result<String> {
+        self.block_ids_at_height(height)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| NodeError::Other(format!("No block found at height {height}")))
+    }
+
+    /// Get the `count` most recent headers of the best chain, ordered from
+    /// the oldest to the latest header
+    pub fn last_headers(&self, count: u64) -> Result<Vec<Header>> {
+        self.get_typed(&format!("/blocks/lastHeaders/{count}"))
+    }
+
+    /// Get the headers of the best chain from `from_height` to `to_height`
+    /// (both inclusive), ordered by height
+    pub fn chain_slice(
+        &self,
+        from_height: BlockHeight,
+        to_height: BlockHeight,
+    ) -> Result<Vec<Header>> {
+        // `fromHeight` is exclusive in the node's chain slice endpoint
+        let endpoint = format!(
+            "/blocks/chainSlice?fromHeight={}&toHeight={to_height}",
+            from_height.saturating_sub(1)
+        );
+        let res_json = self.get_json(&endpoint)?;
+        let headers: Vec<Header> = serde_json::from_value(res_json.clone())
+            .map_err(|_| NodeError::FailedParsingNodeResponse(res_json.to_string()))?;
+        Ok(headers
+            .into_iter()
+            .filter(|h| (from_height..=to_height).contains(&(h.height as BlockHeight)))
+            .collect())
+    }
+
+    /// Get a randomness seed from the header of the best chain block at the
+    /// given height, failing if the block has less than
+    /// `MIN_SEED_CONFIRMATIONS` confirmations
+    pub fn block_seed_at(&self, height: BlockHeight) -> Result<BlockSeed> {
+        self.block_seed_at_depth(height, MIN_SEED_CONFIRMATIONS)
+    }
+
+    /// Get a randomness seed from the header of the best chain block at the
+    /// given height, failing if the block has less than `min_confirmations`
+    /// confirmations
+    pub fn block_seed_at_depth(
+        &self,
+        height: BlockHeight,
+        min_confirmations: u64,
+    ) -> Result<BlockSeed> {
+        let current_height = self.current_block_height()?;
+        let confirmations = (current_height + 1).saturating_sub(height);
+        if confirmations < min_confirmations {
+            return Err(NodeError::Other(format!(
+                "Block at height {height} has {confirmations} confirmations, {min_confirmations} required"
+            )));
+        }
+        let header = self.block_header(&self.block_id_at_height(height)?)?;
+        Ok(BlockSeed {
+            height,
+            header_id: String::from(header.id.0),
+            seed: header.id.0 .0,
+        })
+    }
+
+    /// Get the header of the block with the given header id
+    pub fn block_header(&self, header_id: &str) -> Result<Header> {
+        self.get_typed(&format!("/blocks/{}/header", encode_param(header_id)))
+    }
+
+    /// Get the transactions of the block with the given header id
+    pub fn block_transactions(&self, header_id: &str) -> Result<Vec<Transaction>> {
+        let res_json =
+            self.get_json(&format!("/blocks/{}/transactions", encode_param(header_id)))?;
+        serde_json::from_value(res_json["transactions"].clone())
+            .map_err(|_| NodeError::FailedParsingNodeResponse(res_json.to_string()))
+    }
+
+    /// Get the full block with the given header id
+    pub fn full_block(&self, header_id: &str) -> Result<FullBlock> {
+        let res_json = self.get_json(&format!("/blocks/{}", encode_param(header_id)))?;
+        let parse_error = || NodeError::FailedParsingNodeResponse(res_json.to_string());
+        Ok(FullBlock {
+            header: serde_json::from_value(res_json["header"].clone())
+                .map_err(|_| parse_error())?,
+            transactions: serde_json::from_value(
+                res_json["blockTransactions"]["transactions"].clone(),
+            )
+            .map_err(|_| parse_error())?,
+        })
+    }
+}
@@ -0,0 +1,115 @@
+//! The `BoxLeaseRegistry` struct is defined here which allows multiple workers
+//! sharing a `NodeInterface` to avoid selecting the same unspent boxes.
+use crate::node_interface::{select_until_total, NodeInterface, Result};
+use crate::NanoErg;
+use ergo_lib::ergotree_ir::chain::ergo_box::{BoxId, ErgoBox};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// An advisory in-process registry of leased box ids. Leased boxes are
+/// skipped by the box selection helpers of `NodeInterface` until the lease
+/// is released or expires. Clones of a registry share the same leases.
+#[derive(Debug, Clone, Default)]
+pub struct BoxLeaseRegistry {
+    leases: Arc<Mutex<HashMap<BoxId, Instant>>>,
+}
+
+impl BoxLeaseRegistry {
+    /// Create a new empty `BoxLeaseRegistry`
+    pub fn new() -> BoxLeaseRegistry {
+        BoxLeaseRegistry::default()
+    }
+
+    /// Lease the given box ids for `duration`
+    pub fn lease(&self, box_ids: &[BoxId], duration: Duration) {
+        let expiry = Instant::now() + duration;
+        let mut leases = self.active_leases();
+        for id in box_ids {
+            leases.insert(*id, expiry);
+        }
+    }
+
+    /// Release the leases of the given box ids
+    pub fn release(&self, box_ids: &[BoxId]) {
+        let mut leases = self.active_leases();
+        for id in box_ids {
+            leases.remove(id);
+        }
+    }
+
+    /// Checks whether the given box id is currently leased
+    pub fn is_leased(&self, box_id: &BoxId) -> bool {
+        self.active_leases().contains_key(box_id)
+    }
+
+    /// Returns the box ids which are currently leased
+    pub fn leased_box_ids(&self) -> Vec<BoxId> {
+        self.active_leases().keys().copied().collect()
+    }
+
+    /// Filters out all boxes which are currently leased
+    pub fn filter_unleased(&self, boxes: &[ErgoBox]) -> Vec<ErgoBox> {
+        let leases = self.active_leases();
+        boxes
+            .iter()
+            .filter(|b| !leases.contains_key(&b.box_id()))
+            .cloned()
+            .collect()
+    }
+
+    /// Acquires the lease map with all expired leases removed
+    fn active_leases(&self) -> MutexGuard<'_, HashMap<BoxId, Instant>> {
+        let mut leases = self
+            .leases
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        leases.retain(|_, expiry| *expiry > now);
+        leases
+    }
+}
+
+/// Box lease related methods
+impl NodeInterface {
+    /// Returns a sorted list of unleased unspent boxes which cover at least
+    /// the provided value `total` of nanoErgs and leases them for `duration`.
+    /// Selection and leasing happen atomically with respect to other
+    /// workers sharing the same `BoxLeaseRegistry`.
+    pub fn lease_unspent_boxes_with_min_total(
+        &self,
+        total: NanoErg,
+        duration: Duration,
+    ) -> Result<Vec<ErgoBox>> {
+        let boxes = self.unspent_boxes_sorted()?;
+        let mut leases = self.box_leases.active_leases();
+        let unleased: Vec<ErgoBox> = boxes
+            .into_iter()
+            .filter(|b| !leases.contains_key(&b.box_id()))
+            .collect();
+        // The lease map is held, so select without filtering it again
+        let selected = select_until_total(total, &unleased)?;
+        let expiry = Instant::now() + duration;
+        for b in &selected {
+            leases.insert(b.box_id(), expiry);
+        }
+        Ok(selected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lease_release_and_expiry() {
+        let registry = BoxLeaseRegistry::new();
+        let id = BoxId::zero();
+        registry.lease(&[id], Duration::from_secs(60));
+        assert!(registry.clone().is_leased(&id));
+        registry.release(&[id]);
+        assert!(!registry.is_leased(&id));
+        registry.lease(&[id], Duration::from_secs(0));
+        assert!(registry.leased_box_ids().is_empty());
+    }
+}
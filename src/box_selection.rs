@@ -0,0 +1,124 @@
+//! The `BoxSelector` trait is defined here which allows customizing the
+//! order (and filtering) of wallet boxes used when selecting inputs.
+use crate::node_interface::{NodeInterface, Result};
+use crate::NanoErg;
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+
+/// A strategy for selecting unspent boxes. Boxes are consumed in the order
+/// returned by `order_boxes` until the required total is reached, thus
+/// boxes which are filtered out are never selected.
+pub trait BoxSelector {
+    /// Orders the candidate boxes by preference, optionally filtering some out
+    fn order_boxes(&self, boxes: Vec<ErgoBox>) -> Vec<ErgoBox>;
+}
+
+/// Selects the boxes holding the most nanoErgs first
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LargestFirst;
+
+/// Selects the boxes with the lowest creation height first
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OldestFirst;
+
+/// Selects the boxes holding the fewest nanoErgs first
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SmallestFirst;
+
+impl BoxSelector for LargestFirst {
+    fn order_boxes(&self, mut boxes: Vec<ErgoBox>) -> Vec<ErgoBox> {
+        boxes.sort_by_key(|b| std::cmp::Reverse(*b.value.as_u64()));
+        boxes
+    }
+}
+
+impl BoxSelector for OldestFirst {
+    fn order_boxes(&self, mut boxes: Vec<ErgoBox>) -> Vec<ErgoBox> {
+        boxes.sort_by_key(|b| b.creation_height);
+        boxes
+    }
+}
+
+impl BoxSelector for SmallestFirst {
+    fn order_boxes(&self, mut boxes: Vec<ErgoBox>) -> Vec<ErgoBox> {
+        boxes.sort_by_key(|b| *b.value.as_u64());
+        boxes
+    }
+}
+
+/// Any closure ordering boxes can be used as a `BoxSelector`
+impl<F> BoxSelector for F
+where
+    F: Fn(Vec<ErgoBox>) -> Vec<ErgoBox>,
+{
+    fn order_boxes(&self, boxes: Vec<ErgoBox>) -> Vec<ErgoBox> {
+        self(boxes)
+    }
+}
+
+impl NodeInterface {
+    /// Returns a list of unspent boxes which cover at least the provided
+    /// value `total` of nanoErgs, using the given `BoxSelector`.
+    pub fn unspent_boxes_with_selector<S: BoxSelector + ?Sized>(
+        &self,
+        total: NanoErg,
+        selector: &S,
+    ) -> Result<Vec<ErgoBox>> {
+        let boxes = selector.order_boxes(self.unspent_boxes()?);
+        self.consume_boxes_until_total(total, &boxes)
+    }
+
+    /// Acquires unspent boxes which cover `total` amount of nanoErgs using
+    /// the given `BoxSelector` and serializes them
+    pub fn serialized_unspent_boxes_with_selector<S: BoxSelector + ?Sized>(
+        &self,
+        total: NanoErg,
+        selector: &S,
+    ) -> Result<Vec<String>> {
+        let boxes = self.unspent_boxes_with_selector(total, selector)?;
+        let mut serialized_boxes = vec![];
+        for b in boxes {
+            serialized_boxes.push(self.serialized_box_from_id(&b.box_id().into())?);
+        }
+        Ok(serialized_boxes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{test_box, test_tree};
+
+    fn ergo_box(value: NanoErg, creation_height: u32) -> ErgoBox {
+        test_box(value, &test_tree(), creation_height, 0)
+    }
+
+    #[test]
+    fn test_box_selector_ordering() {
+        let boxes = vec![
+            ergo_box(2_000_000, 3),
+            ergo_box(1_000_000, 2),
+            ergo_box(3_000_000, 1),
+        ];
+        let values =
+            |boxes: Vec<ErgoBox>| -> Vec<u64> { boxes.iter().map(|b| *b.value.as_u64()).collect() };
+        assert_eq!(
+            values(LargestFirst.order_boxes(boxes.clone())),
+            vec![3_000_000, 2_000_000, 1_000_000]
+        );
+        assert_eq!(
+            values(SmallestFirst.order_boxes(boxes.clone())),
+            vec![1_000_000, 2_000_000, 3_000_000]
+        );
+        assert_eq!(
+            values(OldestFirst.order_boxes(boxes.clone())),
+            vec![3_000_000, 1_000_000, 2_000_000]
+        );
+        let no_small_boxes = |boxes: Vec<ErgoBox>| -> Vec<ErgoBox> {
+            boxes
+                .into_iter()
+                .filter(|b| *b.value.as_u64() > 1_000_000)
+                .collect()
+        };
+        assert_eq!(values(no_small_boxes.order_boxes(boxes)).len(), 2);
+    }
+}
@@ -0,0 +1,214 @@
+//! Detection of the optional features which are enabled on the connected node.
+//! Detection is performed once per `NodeInterface` (and its clones) and cached.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use crate::BlockHeight;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Optional node features which are disabled in a default node config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeFeature {
+    /// The extra index serving the `/blockchain` endpoints
+    Indexer,
+    /// The `/transactions/unconfirmed` mempool endpoints
+    Mempool,
+    /// The `/nipopow` proof endpoints
+    Nipopow,
+    /// The `/mining` endpoints
+    Mining,
+}
+
+impl fmt::Display for NodeFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            NodeFeature::Indexer => "extra indexer (`extraIndex = true`)",
+            NodeFeature::Mempool => "mempool API",
+            NodeFeature::Nipopow => "NiPoPoW proofs",
+            NodeFeature::Mining => "mining (`mining = true`)",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// The optional features which the connected node has enabled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeCapabilities {
+    pub indexer: bool,
+    pub mempool: bool,
+    pub nipopow: bool,
+    pub mining: bool,
+}
+
+impl NodeCapabilities {
+    /// Checks whether the given feature is enabled
+    pub fn supports(&self, feature: NodeFeature) -> bool {
+        match feature {
+            NodeFeature::Indexer => self.indexer,
+            NodeFeature::Mempool => self.mempool,
+            NodeFeature::Nipopow => self.nipopow,
+            NodeFeature::Mining => self.mining,
+        }
+    }
+}
+
+/// A cache of the detected `NodeCapabilities`, shared between clones
+/// of a `NodeInterface`.
+#[derive(Debug, Clone, Default)]
+pub struct CapabilitiesCache {
+    capabilities: Arc<Mutex<Option<NodeCapabilities>>>,
+}
+
+impl CapabilitiesCache {
+    /// Clears the cached capabilities so they are detected again on next use
+    pub fn clear(&self) {
+        *self
+            .capabilities
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+}
+
+/// The progress of the extra indexer relative to the node's full height
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexerLag {
+    pub indexed_height: BlockHeight,
+    pub full_height: BlockHeight,
+}
+
+impl IndexerLag {
+    /// Number of blocks which the indexer has not processed yet
+    pub fn blocks_behind(&self) -> BlockHeight {
+        self.full_height.saturating_sub(self.indexed_height)
+    }
+}
+
+impl fmt::Display for IndexerLag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "indexed height {} is {} blocks behind full height {}",
+            self.indexed_height,
+            self.blocks_behind(),
+            self.full_height
+        )
+    }
+}
+
+/// How indexer-backed queries react to the indexer lagging behind the node
+#[derive(Clone, Default)]
+pub enum IndexerLagPolicy {
+    /// Query the indexer regardless of its progress
+    #[default]
+    Ignore,
+    /// Fail with `NodeError::IndexerBehind` if the indexer is more
+    /// than `max_lag` blocks behind
+    Fail { max_lag: BlockHeight },
+    /// Call `callback` and continue with the query if the indexer is more
+    /// than `max_lag` blocks behind
+    Warn {
+        max_lag: BlockHeight,
+        callback: Arc<dyn Fn(IndexerLag) + Send + Sync>,
+    },
+}
+
+impl fmt::Debug for IndexerLagPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexerLagPolicy::Ignore => write!(f, "Ignore"),
+            IndexerLagPolicy::Fail { max_lag } => write!(f, "Fail {{ max_lag: {max_lag} }}"),
+            IndexerLagPolicy::Warn { max_lag, .. } => write!(f, "Warn {{ max_lag: {max_lag} }}"),
+        }
+    }
+}
+
+/// Capability detection methods
+impl NodeInterface {
+    /// Returns the optional features enabled on the node. Detection is
+    /// performed on first use and cached afterwards.
+    pub fn capabilities(&self) -> Result<NodeCapabilities> {
+        let cached = *self
+            .capabilities_cache
+            .capabilities
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(capabilities) = cached {
+            return Ok(capabilities);
+        }
+        // The cache is not locked while detecting, as the requests may
+        // clear it (e.g. on a reconnect)
+        // Ensure the node is reachable so an offline node is not cached
+        // as having every feature disabled
+        self.send_get_req("/info")?;
+        let capabilities = NodeCapabilities {
+            indexer: self.endpoint_available("/blockchain/indexedHeight"),
+            mempool: self.endpoint_available("/transactions/unconfirmed/size"),
+            nipopow: self.endpoint_available("/nipopow/popowHeaderByHeight/1"),
+            mining: self.endpoint_available("/mining/rewardAddress"),
+        };
+        *self
+            .capabilities_cache
+            .capabilities
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(capabilities);
+        Ok(capabilities)
+    }
+
+    /// Returns an error if the given feature is not enabled on the node
+    pub fn require_feature(&self, feature: NodeFeature) -> Result<()> {
+        if self.capabilities()?.supports(feature) {
+            Ok(())
+        } else {
+            Err(NodeError::FeatureDisabledOnNode(feature))
+        }
+    }
+
+    /// Set the policy applied when indexer-backed queries are made while
+    /// the extra indexer lags behind the node
+    pub fn with_indexer_lag_policy(mut self, policy: IndexerLagPolicy) -> Self {
+        self.indexer_lag_policy = policy;
+        self
+    }
+
+    /// Get the indexed height and full height of the extra indexer
+    pub fn indexer_lag(&self) -> Result<IndexerLag> {
+        let res_json = self.get_json("/blockchain/indexedHeight")?;
+        let height = |key: &str| {
+            res_json[key]
+                .as_u64()
+                .ok_or_else(|| NodeError::FailedParsingNodeResponse(res_json.to_string()))
+        };
+        Ok(IndexerLag {
+            indexed_height: height("indexedHeight")?,
+            full_height: height("fullHeight")?,
+        })
+    }
+
+    /// Returns an error if the extra indexer is disabled, or if it lags
+    /// behind the node and the `IndexerLagPolicy` is set to fail.
+    pub fn require_indexer(&self) -> Result<()> {
+        self.require_feature(NodeFeature::Indexer)?;
+        let (max_lag, callback) = match &self.indexer_lag_policy {
+            IndexerLagPolicy::Ignore => return Ok(()),
+            IndexerLagPolicy::Fail { max_lag } => (*max_lag, None),
+            IndexerLagPolicy::Warn { max_lag, callback } => (*max_lag, Some(callback)),
+        };
+        let lag = self.indexer_lag()?;
+        if lag.blocks_behind() <= max_lag {
+            return Ok(());
+        }
+        match callback {
+            Some(callback) => {
+                callback(lag);
+                Ok(())
+            }
+            None => Err(NodeError::IndexerBehind(lag)),
+        }
+    }
+
+    /// Checks whether a GET request to the endpoint succeeds
+    pub(crate) fn endpoint_available(&self, endpoint: &str) -> bool {
+        self.send_get_req(endpoint)
+            .map(|res| res.status().is_success())
+            .unwrap_or(false)
+    }
+}
@@ -0,0 +1,153 @@
+//! The `CoinControl` struct is defined here which allows a developer to
+//! manually control which UTXOs from the node wallet get spent.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use crate::NanoErg;
+use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+use ergo_lib::chain::transaction::Transaction;
+use ergo_lib::ergotree_ir::chain::ergo_box::{BoxId, ErgoBox};
+
+/// A `CoinControl` holds the box ids which must be spent (pinned) and the
+/// box ids which must never be spent (frozen) when selecting inputs.
+#[derive(Debug, Clone, Default)]
+pub struct CoinControl {
+    pub pinned: Vec<BoxId>,
+    pub frozen: Vec<BoxId>,
+}
+
+impl CoinControl {
+    /// Create a new empty `CoinControl`
+    pub fn new() -> CoinControl {
+        CoinControl::default()
+    }
+
+    /// Pin the given box id so that it is always used as an input
+    pub fn pin(mut self, box_id: BoxId) -> CoinControl {
+        if !self.pinned.contains(&box_id) {
+            self.pinned.push(box_id);
+        }
+        self
+    }
+
+    /// Freeze the given box id so that it is never used as an input
+    pub fn freeze(mut self, box_id: BoxId) -> CoinControl {
+        if !self.frozen.contains(&box_id) {
+            self.frozen.push(box_id);
+        }
+        self
+    }
+
+    /// Checks whether the given box id is frozen
+    pub fn is_frozen(&self, box_id: &BoxId) -> bool {
+        self.frozen.contains(box_id)
+    }
+
+    /// Given the unspent boxes of a wallet (in order of preference), select
+    /// boxes which cover at least `total` nanoErgs. Pinned boxes are always
+    /// selected first, frozen boxes are skipped.
+    pub fn select_boxes(&self, total: NanoErg, boxes: &[ErgoBox]) -> Result<Vec<ErgoBox>> {
+        if let Some(id) = self.pinned.iter().find(|id| self.is_frozen(id)) {
+            return Err(NodeError::CoinControlViolation(format!(
+                "Box {} is both pinned and frozen.",
+                String::from(*id)
+            )));
+        }
+
+        let mut selected = vec![];
+        for id in &self.pinned {
+            let pinned_box = boxes.iter().find(|b| b.box_id() == *id).ok_or_else(|| {
+                NodeError::CoinControlViolation(format!(
+                    "Pinned box {} is not an unspent wallet box.",
+                    String::from(*id)
+                ))
+            })?;
+            selected.push(pinned_box.clone());
+        }
+
+        let mut count: NanoErg = selected.iter().map(|b| *b.value.as_u64()).sum();
+        for b in boxes {
+            if count >= total {
+                break;
+            }
+            let id = b.box_id();
+            if self.is_frozen(&id) || self.pinned.contains(&id) {
+                continue;
+            }
+            count += b.value.as_u64();
+            selected.push(b.clone());
+        }
+        if count < total {
+            return Err(NodeError::InsufficientErgsBalance());
+        }
+        Ok(selected)
+    }
+
+    /// Checks that an `UnsignedTransaction` spends every pinned box and
+    /// does not spend any frozen box.
+    pub fn check_transaction(&self, unsigned_tx: &UnsignedTransaction) -> Result<()> {
+        let input_ids: Vec<BoxId> = unsigned_tx.inputs.iter().map(|i| i.box_id).collect();
+        if let Some(id) = input_ids.iter().find(|id| self.is_frozen(id)) {
+            return Err(NodeError::CoinControlViolation(format!(
+                "Transaction spends frozen box {}.",
+                String::from(*id)
+            )));
+        }
+        if let Some(id) = self.pinned.iter().find(|id| !input_ids.contains(id)) {
+            return Err(NodeError::CoinControlViolation(format!(
+                "Transaction does not spend pinned box {}.",
+                String::from(*id)
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Coin-control related methods
+impl NodeInterface {
+    /// Returns a sorted list of unspent boxes which cover at least the
+    /// provided value `total` of nanoErgs while respecting the pinned and
+    /// frozen boxes of the provided `CoinControl`.
+    pub fn unspent_boxes_with_coin_control(
+        &self,
+        total: NanoErg,
+        coin_control: &CoinControl,
+    ) -> Result<Vec<ErgoBox>> {
+        coin_control.select_boxes(total, &self.unspent_boxes_sorted()?)
+    }
+
+    /// Acquires unspent boxes which cover `total` amount of nanoErgs
+    /// using the provided `CoinControl` and serializes the boxes so they
+    /// are ready to be used as `rawInputs` in a transaction request.
+    pub fn serialized_unspent_boxes_with_coin_control(
+        &self,
+        total: NanoErg,
+        coin_control: &CoinControl,
+    ) -> Result<Vec<String>> {
+        let boxes = self.unspent_boxes_with_coin_control(total, coin_control)?;
+        let mut serialized_boxes = vec![];
+        for b in boxes {
+            serialized_boxes.push(self.serialize_box(&b)?);
+        }
+        Ok(serialized_boxes)
+    }
+
+    /// Sign an `UnsignedTransaction` after checking it against the provided
+    /// `CoinControl`. The input boxes are acquired from the node wallet.
+    pub fn sign_transaction_with_coin_control(
+        &self,
+        unsigned_tx: &UnsignedTransaction,
+        coin_control: &CoinControl,
+        data_input_boxes: Option<Vec<ErgoBox>>,
+    ) -> Result<Transaction> {
+        coin_control.check_transaction(unsigned_tx)?;
+        let wallet_boxes = self.unspent_boxes()?;
+        let mut boxes_to_spend = vec![];
+        for input in unsigned_tx.inputs.iter() {
+            let input_box = wallet_boxes
+                .iter()
+                .find(|b| b.box_id() == input.box_id)
+                .ok_or(NodeError::NoBoxesFound)?;
+            boxes_to_spend.push(input_box.clone());
+        }
+        self.sign_transaction(unsigned_tx, Some(boxes_to_spend), data_input_boxes)
+    }
+}
@@ -0,0 +1,223 @@
+//! Helpers for deploying P2S contracts through the node wallet.
+use crate::node_interface::{is_mainnet_address, NodeError, NodeInterface, Result};
+use crate::requests::string_field;
+use crate::tx_request::{PaymentRequest, TokenAmount, TxRequestBuilder, TxRequestItem};
+use crate::{NanoErg, P2SAddressString};
+use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+use ergo_lib::chain::transaction::DataInput;
+use ergo_lib::ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+use ergo_lib::ergotree_ir::chain::address::{Address, AddressEncoder, NetworkPrefix};
+use ergo_lib::ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergo_lib::ergotree_ir::chain::ergo_box::{BoxId, BoxTokens, ErgoBox, ErgoBoxCandidate};
+use ergo_lib::ergotree_ir::chain::token::Token;
+use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+use ergo_lib::ergotree_ir::mir::constant::Constant;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use ergo_lib::wallet::box_selector::{
+    sum_tokens_from_boxes, sum_value, BoxSelection, ErgoBoxAssetsData,
+};
+use ergo_lib::wallet::tx_builder::TxBuilder;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+
+/// An unsigned transaction spending a contract box together with all boxes
+/// required to sign it via `sign_transaction` or an external signer.
+#[derive(Debug, Clone)]
+pub struct ContractSpend {
+    pub unsigned_tx: UnsignedTransaction,
+    pub boxes_to_spend: Vec<ErgoBox>,
+    pub data_input_boxes: Vec<ErgoBox>,
+}
+
+/// The source of a contract to be deployed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContractSource {
+    /// ErgoScript source code which is compiled by the node
+    ErgoScript(String),
+    /// A hex-encoded serialized ErgoTree
+    ErgoTree(String),
+}
+
+impl NodeInterface {
+    /// Deploys a contract by compiling it (if provided as ErgoScript), creating
+    /// a box protected by it holding `value` nanoErgs, `tokens` and `registers`
+    /// via the node wallet, and submitting the transaction.
+    /// Returns the box id of the created contract box.
+    pub fn deploy_contract(
+        &self,
+        contract: &ContractSource,
+        value: NanoErg,
+        tokens: Vec<TokenAmount>,
+        registers: BTreeMap<String, String>,
+    ) -> Result<String> {
+        let address = match contract {
+            ContractSource::ErgoScript(source) => self.compile_p2s(source)?,
+            ContractSource::ErgoTree(tree_hex) => {
+                self.ergo_tree_to_wallet_network_address(tree_hex)?
+            }
+        };
+        let tree_hex = AddressEncoder::unchecked_parse_address_from_str(&address)
+            .and_then(|a| Ok(a.script()?))
+            .map_err(|e| NodeError::Other(e.to_string()))?
+            .to_base16_bytes()
+            .map_err(|e| NodeError::Other(e.to_string()))?;
+
+        let tx_request =
+            TxRequestBuilder::new().with_request(TxRequestItem::Payment(PaymentRequest {
+                address,
+                value,
+                assets: tokens,
+                registers,
+            }));
+        let signed_tx = self.generate_json_transaction(&tx_request.build()?)?;
+        let box_id = signed_tx["outputs"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|o| o["ergoTree"].as_str() == Some(tree_hex.as_str()))
+            .and_then(|o| o["boxId"].as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| NodeError::FailedParsingNodeResponse(signed_tx.to_string()))?;
+        self.submit_json_transaction(&signed_tx.to_string())?;
+        Ok(box_id)
+    }
+
+    /// Assembles an unsigned transaction spending `contract_box` into the
+    /// intended `outputs`. The context extension `values` are set on the
+    /// contract input, the `data_input_ids` boxes are fetched from the node, and
+    /// wallet boxes are added to cover the outputs and `fee`, with the change
+    /// going to the address chosen by the `ChangeAddressPolicy`.
+    pub fn build_contract_spend(
+        &self,
+        contract_box: &ErgoBox,
+        outputs: Vec<ErgoBoxCandidate>,
+        values: &[(u8, Constant)],
+        data_input_ids: &[BoxId],
+        fee: NanoErg,
+    ) -> Result<ContractSpend> {
+        let to_error = |e: &dyn std::fmt::Display| NodeError::Other(e.to_string());
+        let fee_value = BoxValue::new(fee).map_err(|e| to_error(&e))?;
+        let needed_value = sum_value(&outputs) + fee;
+        let needed_tokens = sum_tokens_from_boxes(&outputs).map_err(|e| to_error(&e))?;
+
+        // Add wallet boxes until the outputs are covered and the change is spendable
+        let mut boxes_to_spend = vec![contract_box.clone()];
+        let mut wallet_boxes = self.unspent_boxes_sorted()?.into_iter();
+        loop {
+            let input_value = sum_value(&boxes_to_spend);
+            let input_tokens = sum_tokens_from_boxes(&boxes_to_spend).map_err(|e| to_error(&e))?;
+            let tokens_covered = needed_tokens
+                .iter()
+                .all(|(id, amount)| input_tokens.get(id).is_some_and(|a| a >= amount));
+            let has_change_tokens = input_tokens.len() > needed_tokens.len()
+                || needed_tokens
+                    .iter()
+                    .any(|(id, amount)| input_tokens.get(id) != Some(amount));
+            let change = input_value.saturating_sub(needed_value);
+            let change_spendable =
+                (change == 0 && !has_change_tokens) || change >= *BoxValue::SAFE_USER_MIN.as_u64();
+            if input_value >= needed_value && tokens_covered && change_spendable {
+                break;
+            }
+            match wallet_boxes.next() {
+                Some(b) if b.box_id() != contract_box.box_id() => boxes_to_spend.push(b),
+                Some(_) => continue,
+                None => return Err(NodeError::InsufficientErgsBalance()),
+            }
+        }
+
+        let change_value = sum_value(&boxes_to_spend) - needed_value;
+        let mut change_tokens: Vec<Token> = vec![];
+        for (id, amount) in sum_tokens_from_boxes(&boxes_to_spend).map_err(|e| to_error(&e))? {
+            let spent = needed_tokens.get(&id).map(|a| *a.as_u64()).unwrap_or(0);
+            if *amount.as_u64() > spent {
+                let left = (*amount.as_u64() - spent)
+                    .try_into()
+                    .map_err(|e| to_error(&e))?;
+                change_tokens.push(Token {
+                    token_id: id,
+                    amount: left,
+                });
+            }
+        }
+        let change_boxes = if change_value == 0 {
+            vec![]
+        } else {
+            vec![ErgoBoxAssetsData {
+                value: BoxValue::new(change_value).map_err(|e| to_error(&e))?,
+                tokens: BoxTokens::opt_empty_vec(change_tokens).map_err(|e| to_error(&e))?,
+            }]
+        };
+
+        let change_address =
+            AddressEncoder::unchecked_parse_address_from_str(&self.resolve_change_address()?)
+                .map_err(|e| to_error(&e))?;
+        let data_input_boxes = self.data_input_boxes_from_ids(data_input_ids)?;
+        let selection = BoxSelection {
+            boxes: boxes_to_spend
+                .clone()
+                .try_into()
+                .map_err(|e| to_error(&e))?,
+            change_boxes,
+        };
+        let mut tx_builder = TxBuilder::new(
+            selection,
+            outputs,
+            self.current_block_height()? as u32,
+            fee_value,
+            change_address,
+        );
+        tx_builder.set_data_inputs(
+            data_input_ids
+                .iter()
+                .map(|id| DataInput { box_id: *id })
+                .collect(),
+        );
+        let mut extension = ContextExtension::empty();
+        for (id, value) in values {
+            extension.values.insert(*id, value.clone());
+        }
+        tx_builder.set_context_extension(contract_box.box_id(), extension);
+        let unsigned_tx = tx_builder.build().map_err(|e| to_error(&e))?;
+
+        Ok(ContractSpend {
+            unsigned_tx,
+            boxes_to_spend,
+            data_input_boxes,
+        })
+    }
+
+    /// Compile ErgoScript source into a P2S address using the node
+    pub fn compile_p2s(&self, source: &str) -> Result<P2SAddressString> {
+        self.compile_source("/script/p2sAddress", source)
+    }
+
+    /// Compile ErgoScript source into a P2SH address using the node
+    pub fn compile_p2sh(&self, source: &str) -> Result<P2SAddressString> {
+        self.compile_source("/script/p2shAddress", source)
+    }
+
+    fn compile_source(&self, endpoint: &str, source: &str) -> Result<P2SAddressString> {
+        let body = json!({
+            "source": source,
+        });
+        let res_json = self.use_json_endpoint_and_check_errors(endpoint, &body.to_string())?;
+        string_field(&res_json, "address")
+    }
+
+    /// Encode a hex-encoded ErgoTree as an address of the same network as the node wallet
+    fn ergo_tree_to_wallet_network_address(&self, tree_hex: &str) -> Result<P2SAddressString> {
+        let tree_bytes = base16::decode(tree_hex).map_err(|e| NodeError::Other(e.to_string()))?;
+        let tree = ErgoTree::sigma_parse_bytes(&tree_bytes)
+            .map_err(|e| NodeError::Other(e.to_string()))?;
+        let address =
+            Address::recreate_from_ergo_tree(&tree).map_err(|e| NodeError::Other(e.to_string()))?;
+        let network = match (self.network, self.wallet_addresses()?.first()) {
+            (Some(network), _) => network.prefix(),
+            (None, Some(a)) if is_mainnet_address(a) => NetworkPrefix::Mainnet,
+            _ => NetworkPrefix::Testnet,
+        };
+        Ok(AddressEncoder::new(network).address_to_str(&address))
+    }
+}
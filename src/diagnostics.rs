@@ -0,0 +1,241 @@
+//! A diagnostic report of a `NodeInterface` and the node it is connected to,
+//! meant to be pasted into bug reports. Secrets such as the api key and the
+//! node host are redacted.
+use crate::capabilities::NodeCapabilities;
+use crate::node_interface::{NodeInterface, Result};
+use crate::BlockHeight;
+use reqwest::Url;
+use std::fmt;
+
+/// Details about the node as reported by `/info`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeInfoSummary {
+    pub app_version: String,
+    pub network: String,
+    pub full_height: Option<BlockHeight>,
+    pub headers_height: Option<BlockHeight>,
+    pub peers_count: u64,
+}
+
+impl NodeInfoSummary {
+    /// Whether the node has applied all blocks of the headers it knows
+    pub fn is_synced(&self) -> bool {
+        match (self.full_height, self.headers_height) {
+            (Some(full), Some(headers)) => full + 1 >= headers,
+            _ => false,
+        }
+    }
+}
+
+/// A redacted diagnostic report. Parts which could not be acquired from the
+/// node hold the error encountered instead.
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    pub crate_version: &'static str,
+    /// The node url with the host redacted
+    pub node_url: String,
+    /// The url of the separate wallet node with the host redacted, if set
+    pub wallet_node_url: Option<String>,
+    pub configured_network: Option<String>,
+    pub read_only: bool,
+    pub politeness: bool,
+    pub node_info: std::result::Result<NodeInfoSummary, String>,
+    pub capabilities: std::result::Result<NodeCapabilities, String>,
+    /// Whether the wallet is initialized and unlocked
+    pub wallet: std::result::Result<(bool, bool), String>,
+    /// Endpoints with failed requests as `(endpoint, errors, requests, last error)`
+    pub error_stats: Vec<(String, u64, u64, Option<String>)>,
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "ergo-node-interface: {}", self.crate_version)?;
+        writeln!(f, "node url: {}", self.node_url)?;
+        if let Some(wallet_node_url) = &self.wallet_node_url {
+            writeln!(f, "wallet node url: {wallet_node_url}")?;
+        }
+        let network = self.configured_network.as_deref().unwrap_or("not set");
+        writeln!(f, "configured network: {network}")?;
+        writeln!(f, "read only: {}", self.read_only)?;
+        writeln!(f, "politeness: {}", self.politeness)?;
+        match &self.node_info {
+            Ok(info) => {
+                writeln!(f, "node version: {}", info.app_version)?;
+                writeln!(f, "node network: {}", info.network)?;
+                let height =
+                    |h: Option<BlockHeight>| h.map_or("none".to_string(), |h| h.to_string());
+                writeln!(
+                    f,
+                    "sync: full height {}, headers height {} ({})",
+                    height(info.full_height),
+                    height(info.headers_height),
+                    if info.is_synced() {
+                        "synced"
+                    } else {
+                        "syncing"
+                    }
+                )?;
+                writeln!(f, "peers: {}", info.peers_count)?;
+            }
+            Err(e) => writeln!(f, "node info: unavailable ({e})")?,
+        }
+        match &self.capabilities {
+            Ok(c) => writeln!(
+                f,
+                "capabilities: indexer={} mempool={} nipopow={} mining={}",
+                c.indexer, c.mempool, c.nipopow, c.mining
+            )?,
+            Err(e) => writeln!(f, "capabilities: unavailable ({e})")?,
+        }
+        match &self.wallet {
+            Ok((initialized, unlocked)) => {
+                writeln!(f, "wallet: initialized={initialized} unlocked={unlocked}")?
+            }
+            Err(e) => writeln!(f, "wallet: unavailable ({e})")?,
+        }
+        if self.error_stats.is_empty() {
+            writeln!(f, "request errors: none")?;
+        }
+        for (endpoint, errors, requests, last_error) in &self.error_stats {
+            let last_error = last_error.as_deref().unwrap_or("-");
+            writeln!(
+                f,
+                "request errors: {endpoint} {errors}/{requests} (last: {last_error})"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl NodeInterface {
+    /// Produces a redacted `Diagnostics` report of this `NodeInterface` and
+    /// the node it is connected to. Failures to reach the node are included
+    /// in the report rather than returned.
+    pub fn diagnostics(&self) -> Diagnostics {
+        let redact = |e: String| self.redact(&e);
+        let mut error_stats: Vec<(String, u64, u64, Option<String>)> = self
+            .error_stats()
+            .into_iter()
+            .filter(|(_, stats)| stats.errors > 0)
+            .map(|(endpoint, stats)| {
+                let last_error = stats.last_error.map(redact);
+                (endpoint, stats.errors, stats.requests, last_error)
+            })
+            .collect();
+        error_stats.sort();
+
+        Diagnostics {
+            crate_version: env!("CARGO_PKG_VERSION"),
+            node_url: redacted_url(&self.url),
+            wallet_node_url: self.wallet_route.as_ref().map(|r| redacted_url(&r.url)),
+            configured_network: self.network.map(|n| n.to_string()),
+            read_only: self.read_only,
+            politeness: self.politeness.is_some(),
+            node_info: self.node_info_summary().map_err(|e| redact(e.to_string())),
+            capabilities: self.capabilities().map_err(|e| redact(e.to_string())),
+            wallet: self
+                .wallet_status()
+                .map(|s| (s.initialized, s.unlocked))
+                .map_err(|e| redact(e.to_string())),
+            error_stats,
+        }
+    }
+
+    /// Redacts the api keys and the urls of the node and wallet node within
+    /// the text, e.g. an error message which includes the requested url
+    fn redact(&self, text: &str) -> String {
+        let mut routes = vec![(&self.url, self.api_key.as_str())];
+        if let Some(route) = &self.wallet_route {
+            routes.push((&route.url, route.api_key.as_str()));
+        }
+        let mut text = text.to_string();
+        for (url, api_key) in routes {
+            text = text.replace(url.as_str(), &format!("{}/", redacted_url(url)));
+            if let Some(host) = url.host_str() {
+                text = redact_secret(&text, host);
+            }
+            text = redact_secret(&text, api_key);
+        }
+        text
+    }
+
+    /// Get a summary of the node details reported by `/info`
+    fn node_info_summary(&self) -> Result<NodeInfoSummary> {
+        let info = self.get_json("/info")?;
+        Ok(NodeInfoSummary {
+            app_version: info["appVersion"].as_str().unwrap_or("unknown").to_string(),
+            network: info["network"].as_str().unwrap_or("unknown").to_string(),
+            full_height: info["fullHeight"].as_u64(),
+            headers_height: info["headersHeight"].as_u64(),
+            peers_count: info["peersCount"].as_u64().unwrap_or_default(),
+        })
+    }
+}
+
+/// Returns the url with its host and path redacted, keeping the scheme and port
+fn redacted_url(url: &Url) -> String {
+    match url.port_or_known_default() {
+        Some(port) => format!("{}://<redacted>:{port}", url.scheme()),
+        None => format!("{}://<redacted>", url.scheme()),
+    }
+}
+
+/// Replaces every occurrence of the secret within the text
+fn redact_secret(text: &str, secret: &str) -> String {
+    if secret.is_empty() {
+        text.to_string()
+    } else {
+        text.replace(secret, "<redacted>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redaction() {
+        let url = Url::parse("https://my-node.example.com:9443/ergo/").unwrap();
+        assert_eq!(redacted_url(&url), "https://<redacted>:9443");
+        assert_eq!(
+            redact_secret("invalid api key hunter2", "hunter2"),
+            "invalid api key <redacted>"
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_without_node() {
+        let node = NodeInterface::from_url_str("secret-key", "http://127.0.0.1:1/").unwrap();
+        let report = node.diagnostics().to_string();
+        assert!(report.contains("node info: unavailable"));
+        assert!(!report.contains("secret-key"));
+        assert!(!report.contains("127.0.0.1"));
+    }
+
+    #[test]
+    fn test_diagnostics_after_failed_requests() {
+        let node = NodeInterface::from_url_str("secret-key", "http://127.0.0.1:1/ergo/")
+            .unwrap()
+            .with_wallet_node("wallet-key", "http://127.0.0.2:1/")
+            .unwrap();
+        assert!(node.current_block_height().is_err());
+        assert!(node.wallet_status().is_err());
+        let diagnostics = node.diagnostics();
+        assert_eq!(diagnostics.error_stats.len(), 2);
+        assert!(diagnostics
+            .error_stats
+            .iter()
+            .all(|stats| stats.3.is_some()));
+        let report = diagnostics.to_string();
+        assert!(report.contains("wallet node url: http://<redacted>:1"));
+        for secret in [
+            "secret-key",
+            "wallet-key",
+            "127.0.0.1",
+            "127.0.0.2",
+            "/ergo",
+        ] {
+            assert!(!report.contains(secret), "{} leaked: {}", secret, report);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Local computation of the Ergo emission schedule, following the node's
+//! `EmissionRules` for mainnet, and typed access to the emission endpoints.
+use crate::node_interface::{NodeInterface, Result};
+use crate::{BlockHeight, NanoErg, P2PKAddressString, P2SAddressString};
+use serde::Deserialize;
+
+/// Number of nanoErgs in a single Erg
+const COINS_IN_ONE_ERG: NanoErg = 1_000_000_000;
+/// Number of blocks during which the emission is fixed
+pub const FIXED_RATE_PERIOD: BlockHeight = 525_600;
+/// Number of blocks in a single emission epoch after the fixed rate period
+pub const EPOCH_LENGTH: BlockHeight = 64_800;
+/// Emission per block during the fixed rate period
+pub const FIXED_RATE: NanoErg = 75 * COINS_IN_ONE_ERG;
+/// Reduction of the emission per block with every epoch
+pub const ONE_EPOCH_REDUCTION: NanoErg = 3 * COINS_IN_ONE_ERG;
+/// Reward of the foundation per block during the fixed rate period
+pub const FOUNDERS_INITIAL_REWARD: NanoErg = 75 * COINS_IN_ONE_ERG / 10;
+
+/// Returns the emission epoch at the given height. Epoch `0` is the
+/// fixed rate period.
+pub fn emission_epoch(height: BlockHeight) -> u64 {
+    if height < FIXED_RATE_PERIOD {
+        0
+    } else {
+        1 + (height - FIXED_RATE_PERIOD) / EPOCH_LENGTH
+    }
+}
+
+/// Returns the total amount of nanoErgs emitted by the block at the given height
+pub fn emission_at_height(height: BlockHeight) -> NanoErg {
+    FIXED_RATE.saturating_sub(ONE_EPOCH_REDUCTION * emission_epoch(height))
+}
+
+/// Returns the amount of nanoErgs going to the miner of the block at the given height
+pub fn miner_reward_at_height(height: BlockHeight) -> NanoErg {
+    if height < FIXED_RATE_PERIOD + 2 * EPOCH_LENGTH {
+        FIXED_RATE - FOUNDERS_INITIAL_REWARD
+    } else {
+        emission_at_height(height)
+    }
+}
+
+/// Returns the amount of nanoErgs going to the foundation from the block at the given height
+pub fn foundation_reward_at_height(height: BlockHeight) -> NanoErg {
+    emission_at_height(height) - miner_reward_at_height(height)
+}
+
+/// Returns the height at which the emission is next reduced, or `None`
+/// if the emission has already ended.
+pub fn next_reward_reduction_height(height: BlockHeight) -> Option<BlockHeight> {
+    if emission_at_height(height) == 0 {
+        None
+    } else {
+        Some(FIXED_RATE_PERIOD + emission_epoch(height) * EPOCH_LENGTH)
+    }
+}
+
+/// Emission info at a given height as returned by `/emission/at/{height}`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmissionInfo {
+    pub height: BlockHeight,
+    /// Reward of the miner of the block at this height
+    pub miner_reward: NanoErg,
+    /// Total amount of nanoErgs issued up to this height
+    pub total_coins_issued: NanoErg,
+    /// Amount of nanoErgs remaining to be issued after this height
+    pub total_remain_coins: NanoErg,
+    /// Amount of nanoErgs sent to the re-emission contract at this height
+    #[serde(default)]
+    pub reemitted: NanoErg,
+}
+
+/// Addresses of the emission related contracts as returned by `/emission/scripts`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmissionScripts {
+    pub emission: P2SAddressString,
+    pub reemission: P2SAddressString,
+    pub pay2_reemission: P2SAddressString,
+}
+
+impl NodeInterface {
+    /// Get the emission info reported by the node at the given height
+    pub fn emission_at(&self, height: BlockHeight) -> Result<EmissionInfo> {
+        self.get_typed(&format!("/emission/at/{height}"))
+    }
+
+    /// Get the addresses of the emission and re-emission contracts
+    pub fn emission_scripts(&self) -> Result<EmissionScripts> {
+        self.get_typed("/emission/scripts")
+    }
+
+    /// Get the address which receives the block rewards of the node.
+    /// Equivalent to `mining_reward_address`.
+    pub fn reward_address(&self) -> Result<P2PKAddressString> {
+        self.mining_reward_address()
+    }
+
+    /// Checks that the locally computed miner reward at the given height
+    /// matches the value reported by the node's `/emission/at` endpoint.
+    pub fn check_emission_schedule(&self, height: BlockHeight) -> Result<bool> {
+        Ok(self.emission_at(height)?.miner_reward == miner_reward_at_height(height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emission_schedule() {
+        assert_eq!(emission_at_height(1), FIXED_RATE);
+        assert_eq!(miner_reward_at_height(1), 67_500_000_000);
+        assert_eq!(foundation_reward_at_height(1), 7_500_000_000);
+        assert_eq!(next_reward_reduction_height(1), Some(FIXED_RATE_PERIOD));
+
+        assert_eq!(emission_epoch(FIXED_RATE_PERIOD), 1);
+        assert_eq!(emission_at_height(FIXED_RATE_PERIOD), 72 * COINS_IN_ONE_ERG);
+        assert_eq!(
+            foundation_reward_at_height(FIXED_RATE_PERIOD),
+            4_500_000_000
+        );
+        assert_eq!(
+            next_reward_reduction_height(FIXED_RATE_PERIOD),
+            Some(FIXED_RATE_PERIOD + EPOCH_LENGTH)
+        );
+
+        let last_epoch_height = FIXED_RATE_PERIOD + 24 * EPOCH_LENGTH;
+        assert_eq!(emission_at_height(last_epoch_height), 0);
+        assert_eq!(next_reward_reduction_height(last_epoch_height), None);
+    }
+
+    #[test]
+    fn test_parse_emission_info() {
+        let json = r#"{"height": 1, "minerReward": 67500000000,
+            "totalCoinsIssued": 75000000000, "totalRemainCoins": 97739925000000000,
+            "reemitted": 0}"#;
+        let info: EmissionInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(info.miner_reward, miner_reward_at_height(1));
+        assert_eq!(info.total_coins_issued, emission_at_height(1));
+    }
+}
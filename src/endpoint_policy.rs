@@ -0,0 +1,73 @@
+//! The `EndpointPolicy` enum is defined here which restricts the endpoint
+//! groups (the first path segment, e.g. `wallet` or `mining`) which a
+//! `NodeInterface` may call. The policy is enforced at the request layer.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+
+/// Restricts which endpoint groups a `NodeInterface` may call
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum EndpointPolicy {
+    /// All endpoints may be called
+    #[default]
+    AllowAll,
+    /// Only endpoints within the listed groups may be called
+    Allow(Vec<String>),
+    /// Endpoints within the listed groups may not be called
+    Deny(Vec<String>),
+}
+
+impl EndpointPolicy {
+    /// Returns the group of an endpoint, i.e. its first path segment
+    pub fn endpoint_group(endpoint: &str) -> &str {
+        endpoint
+            .trim_start_matches('/')
+            .split(['/', '?'])
+            .next()
+            .unwrap_or_default()
+    }
+
+    /// Checks whether the endpoint may be called under this policy
+    pub fn is_allowed(&self, endpoint: &str) -> bool {
+        let group = EndpointPolicy::endpoint_group(endpoint);
+        match self {
+            EndpointPolicy::AllowAll => true,
+            EndpointPolicy::Allow(groups) => groups.iter().any(|g| g == group),
+            EndpointPolicy::Deny(groups) => !groups.iter().any(|g| g == group),
+        }
+    }
+
+    /// Returns an error if the endpoint may not be called under this policy
+    pub(crate) fn check(&self, endpoint: &str) -> Result<()> {
+        if self.is_allowed(endpoint) {
+            Ok(())
+        } else {
+            Err(NodeError::EndpointNotAllowed(endpoint.to_string()))
+        }
+    }
+}
+
+impl NodeInterface {
+    /// Restrict the endpoint groups which this `NodeInterface` may call
+    pub fn with_endpoint_policy(mut self, policy: EndpointPolicy) -> Self {
+        self.endpoint_policy = policy;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_policy() {
+        let deny = EndpointPolicy::Deny(vec!["mining".to_string(), "node".to_string()]);
+        assert!(!deny.is_allowed("/mining/candidate"));
+        assert!(!deny.is_allowed("/node/shutdown"));
+        assert!(deny.is_allowed("/wallet/balances"));
+
+        let allow = EndpointPolicy::Allow(vec!["info".to_string(), "blocks".to_string()]);
+        assert!(allow.is_allowed("/info"));
+        assert!(allow.is_allowed("/blocks/at/1?x=1"));
+        assert!(!allow.is_allowed("/wallet/status"));
+        assert_eq!(EndpointPolicy::endpoint_group("/utxo/byId/abc"), "utxo");
+    }
+}
@@ -0,0 +1,291 @@
+//! Helpers for exporting the transaction history of the node wallet as
+//! normalized records in CSV or JSON lines format.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use crate::{BlockHeight, NanoErg, TokenID};
+use ergo_lib::ergotree_ir::chain::ergo_box::{BoxId, ErgoBox};
+use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+use ergo_lib::wallet::miner_fee::MINERS_FEE_BASE16_BYTES;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+/// Number of blocks of wallet history requested from the node at once
+const HEIGHT_WINDOW: BlockHeight = 1000;
+
+/// The format used when exporting transaction history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    JsonLines,
+}
+
+/// Whether a transaction increased or decreased the wallet balance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Incoming,
+    Outgoing,
+    /// All outputs (except the fee) went back to the wallet
+    SelfTransfer,
+}
+
+/// The outputs of a wallet transaction classified by their recipient
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ClassifiedOutputs {
+    /// Outputs back to the wallet of a tx which spent wallet inputs
+    pub change: Vec<ErgoBox>,
+    /// Outputs to the wallet of a tx which spent no wallet inputs
+    pub received: Vec<ErgoBox>,
+    /// Outputs to addresses outside of the wallet (excluding the fee)
+    pub external: Vec<ErgoBox>,
+    /// Sum of the miner fee outputs
+    pub fee: NanoErg,
+}
+
+/// Classifies the outputs of a transaction into change, received and external
+/// outputs. Outputs going to one of the `wallet_trees` are change if the
+/// transaction spent any `wallet_inputs`, otherwise they were received.
+pub fn classify_outputs(
+    wallet_inputs: &[ErgoBox],
+    outputs: &[ErgoBox],
+    wallet_trees: &[ErgoTree],
+) -> ClassifiedOutputs {
+    let mut classified = ClassifiedOutputs::default();
+    for b in outputs {
+        if b.ergo_tree.to_base16_bytes().ok().as_deref() == Some(MINERS_FEE_BASE16_BYTES) {
+            classified.fee += b.value.as_u64();
+        } else if !wallet_trees.contains(&b.ergo_tree) {
+            classified.external.push(b.clone());
+        } else if wallet_inputs.is_empty() {
+            classified.received.push(b.clone());
+        } else {
+            classified.change.push(b.clone());
+        }
+    }
+    classified
+}
+
+/// A normalized record of a single wallet transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportRecord {
+    /// Timestamp (in milliseconds) of the block which included the transaction
+    pub timestamp: u64,
+    pub direction: Direction,
+    /// The nanoErgs received or sent (excluding the fee)
+    pub amount: NanoErg,
+    /// The net change of each token held by the wallet
+    pub tokens: Vec<(TokenID, i64)>,
+    /// The fee paid by the transaction if it was sent by the wallet
+    pub fee: NanoErg,
+    pub tx_id: String,
+}
+
+impl ExportRecord {
+    /// Writes the record as a single line in the given format
+    pub fn write_to<W: Write>(&self, writer: &mut W, format: ExportFormat) -> Result<()> {
+        let direction = match self.direction {
+            Direction::Incoming => "incoming",
+            Direction::Outgoing => "outgoing",
+            Direction::SelfTransfer => "self-transfer",
+        };
+        let line = match format {
+            ExportFormat::Csv => {
+                let tokens: Vec<String> = self
+                    .tokens
+                    .iter()
+                    .map(|(id, amount)| format!("{id}:{amount}"))
+                    .collect();
+                format!(
+                    "{},{},{},{},{},{}",
+                    self.timestamp,
+                    direction,
+                    self.amount,
+                    tokens.join(";"),
+                    self.fee,
+                    self.tx_id
+                )
+            }
+            ExportFormat::JsonLines => {
+                let tokens: Vec<Value> = self
+                    .tokens
+                    .iter()
+                    .map(|(id, amount)| json!({"tokenId": id, "amount": amount}))
+                    .collect();
+                json!({
+                    "timestamp": self.timestamp,
+                    "direction": direction,
+                    "amount": self.amount,
+                    "tokens": tokens,
+                    "fee": self.fee,
+                    "txId": self.tx_id,
+                })
+                .to_string()
+            }
+        };
+        writeln!(writer, "{line}").map_err(|e| NodeError::Other(e.to_string()))
+    }
+}
+
+impl NodeInterface {
+    /// Walks the wallet transaction history starting at `from_height` and
+    /// returns a normalized `ExportRecord` for every transaction.
+    /// Note: Wallet inputs are recognized by being outputs of earlier wallet
+    /// transactions, thus `from_height` should be the wallet creation height.
+    pub fn wallet_export_records(&self, from_height: BlockHeight) -> Result<Vec<ExportRecord>> {
+        let wallet_trees: Vec<_> = self
+            .wallet_address_trees()?
+            .into_iter()
+            .map(|(_, tree)| tree)
+            .collect();
+        let current_height = self.current_block_height()?;
+
+        let mut owned_boxes: HashMap<BoxId, ErgoBox> = HashMap::new();
+        let mut timestamps: HashMap<BlockHeight, u64> = HashMap::new();
+        let mut records = vec![];
+
+        let mut window_start = from_height;
+        while window_start <= current_height {
+            let window_end = window_start + HEIGHT_WINDOW - 1;
+            let mut txs = self.wallet_transactions(Some(window_start), Some(window_end), None)?;
+            txs.sort_by_key(|tx| tx.inclusion_height);
+
+            for tx in txs {
+                let height = tx.inclusion_height;
+                let inputs: Vec<ErgoBox> = tx
+                    .inputs
+                    .iter()
+                    .filter_map(|i| owned_boxes.remove(&i.box_id))
+                    .collect();
+                let outputs = tx.outputs;
+                let classified = classify_outputs(&inputs, &outputs, &wallet_trees);
+
+                let timestamp = match timestamps.get(&height) {
+                    Some(t) => *t,
+                    None => {
+                        let t = self.block_timestamp_at(height)?;
+                        timestamps.insert(height, t);
+                        t
+                    }
+                };
+                records.push(normalize_record(
+                    &String::from(tx.id),
+                    timestamp,
+                    &inputs,
+                    &classified,
+                ));
+
+                for b in classified.change.into_iter().chain(classified.received) {
+                    owned_boxes.insert(b.box_id(), b);
+                }
+            }
+            window_start = window_end + 1;
+        }
+        Ok(records)
+    }
+
+    /// Exports the wallet transaction history starting at `from_height`
+    /// into `writer` using the given format.
+    pub fn export_wallet_transactions<W: Write>(
+        &self,
+        writer: &mut W,
+        format: ExportFormat,
+        from_height: BlockHeight,
+    ) -> Result<()> {
+        let records = self.wallet_export_records(from_height)?;
+        if format == ExportFormat::Csv {
+            writeln!(writer, "timestamp,direction,amount,tokens,fee,tx_id")
+                .map_err(|e| NodeError::Other(e.to_string()))?;
+        }
+        for record in records {
+            record.write_to(writer, format)?;
+        }
+        Ok(())
+    }
+
+    /// Get the timestamp of the block at the given height
+    fn block_timestamp_at(&self, height: BlockHeight) -> Result<u64> {
+        let header_id = self.block_id_at_height(height)?;
+        Ok(self.block_header(&header_id)?.timestamp)
+    }
+}
+
+/// Builds an `ExportRecord` from the wallet-owned inputs and the classified
+/// outputs of a transaction. Change outputs are netted against the inputs so
+/// self-transfers do not inflate the reported amounts.
+fn normalize_record(
+    tx_id: &str,
+    timestamp: u64,
+    inputs: &[ErgoBox],
+    outputs: &ClassifiedOutputs,
+) -> ExportRecord {
+    let wallet_outputs: Vec<&ErgoBox> = outputs.change.iter().chain(&outputs.received).collect();
+    let input_value: i128 = inputs.iter().map(|b| *b.value.as_u64() as i128).sum();
+    let output_value: i128 = wallet_outputs
+        .iter()
+        .map(|b| *b.value.as_u64() as i128)
+        .sum();
+    let net = output_value - input_value;
+    // The fee is only paid by the wallet if it spent wallet inputs
+    let fee = if inputs.is_empty() { 0 } else { outputs.fee };
+
+    let mut tokens: BTreeMap<TokenID, i64> = BTreeMap::new();
+    for (b, sign) in inputs
+        .iter()
+        .map(|b| (b, -1))
+        .chain(wallet_outputs.iter().map(|b| (*b, 1)))
+    {
+        for token in b.tokens.iter().flat_map(|t| t.iter()) {
+            *tokens.entry(String::from(token.token_id)).or_default() +=
+                sign * u64::from(token.amount) as i64;
+        }
+    }
+    tokens.retain(|_, amount| *amount != 0);
+
+    let (direction, amount) = if !inputs.is_empty() && outputs.external.is_empty() {
+        (Direction::SelfTransfer, 0)
+    } else if net >= 0 {
+        (Direction::Incoming, net as u64)
+    } else {
+        (Direction::Outgoing, (-net as u64).saturating_sub(fee))
+    };
+    ExportRecord {
+        timestamp,
+        direction,
+        amount,
+        tokens: tokens.into_iter().collect(),
+        fee,
+        tx_id: tx_id.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{test_box, test_tree};
+    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+
+    fn ergo_box(value: NanoErg, tree: &ErgoTree, index: u16) -> ErgoBox {
+        test_box(value, tree, 1, index)
+    }
+
+    #[test]
+    fn test_self_transfer_is_not_counted_as_volume() {
+        let wallet_tree = test_tree();
+        let fee_tree =
+            ErgoTree::sigma_parse_bytes(&base16::decode(MINERS_FEE_BASE16_BYTES).unwrap()).unwrap();
+        let inputs = vec![ergo_box(10_000_000_000, &wallet_tree, 0)];
+        let outputs = vec![
+            ergo_box(9_998_900_000, &wallet_tree, 0),
+            ergo_box(1_100_000, &fee_tree, 1),
+        ];
+
+        let classified = classify_outputs(&inputs, &outputs, &[wallet_tree]);
+        assert_eq!(classified.change.len(), 1);
+        assert!(classified.external.is_empty());
+        assert_eq!(classified.fee, 1_100_000);
+
+        let record = normalize_record("tx", 0, &inputs, &classified);
+        assert_eq!(record.direction, Direction::SelfTransfer);
+        assert_eq!(record.amount, 0);
+        assert_eq!(record.fee, 1_100_000);
+    }
+}
@@ -0,0 +1,131 @@
+//! A small harness for running integration tests against a real node.
+//! The harness either attaches to a running node (`ERGO_IT_NODE_URL` and
+//! `ERGO_IT_API_KEY`) or starts a testnet node container using docker.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use serde_json::json;
+use std::net::TcpListener;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Docker image used when no image is provided via `ERGO_IT_DOCKER_IMAGE`
+pub const DEFAULT_DOCKER_IMAGE: &str = "ergoplatform/ergo:latest";
+
+/// Maximum time to wait for a started node to answer requests
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// A node used for integration tests. A container started by the harness
+/// is removed once the `NodeHarness` is dropped.
+#[derive(Debug)]
+pub struct NodeHarness {
+    pub node: NodeInterface,
+    container_id: Option<String>,
+}
+
+impl NodeHarness {
+    /// Attach to the node configured through the environment, or start a
+    /// testnet node container if `ERGO_IT_NODE_URL` is not set. The container
+    /// publishes the api on `ERGO_IT_HOST_PORT`, or on a free port if unset.
+    pub fn from_env() -> Result<NodeHarness> {
+        let api_key = std::env::var("ERGO_IT_API_KEY").unwrap_or_else(|_| "hello".to_string());
+        match std::env::var("ERGO_IT_NODE_URL") {
+            Ok(url) => NodeHarness::attach(&api_key, &url),
+            Err(_) => {
+                let image = std::env::var("ERGO_IT_DOCKER_IMAGE")
+                    .unwrap_or_else(|_| DEFAULT_DOCKER_IMAGE.to_string());
+                let config = std::env::var("ERGO_IT_NODE_CONFIG").map_err(|_| {
+                    NodeError::Other(
+                        "Either `ERGO_IT_NODE_URL` or `ERGO_IT_NODE_CONFIG` must be set".into(),
+                    )
+                })?;
+                let host_port = match std::env::var("ERGO_IT_HOST_PORT") {
+                    Ok(port) => port.parse().map_err(|_| {
+                        NodeError::Other(format!("Invalid `ERGO_IT_HOST_PORT`: {port}"))
+                    })?,
+                    Err(_) => free_port()?,
+                };
+                NodeHarness::start_container(&image, &config, &api_key, host_port)
+            }
+        }
+    }
+
+    /// Attach to an already running node
+    pub fn attach(api_key: &str, url: &str) -> Result<NodeHarness> {
+        let node = NodeInterface::from_url_str(api_key, url)?;
+        node.current_block_height()?;
+        Ok(NodeHarness {
+            node,
+            container_id: None,
+        })
+    }
+
+    /// Start a testnet node container from `image` using the node config
+    /// file at `config_path` (which must set the api key hash of `api_key`),
+    /// publishing its api on `host_port`, then wait until the node answers
+    /// requests.
+    pub fn start_container(
+        image: &str,
+        config_path: &str,
+        api_key: &str,
+        host_port: u16,
+    ) -> Result<NodeHarness> {
+        let output = Command::new("docker")
+            .args(["run", "-d", "-p", &format!("127.0.0.1:{host_port}:9052")])
+            .args(["-v", &format!("{config_path}:/etc/ergo/ergo.conf:ro")])
+            .args([image, "--testnet", "-c", "/etc/ergo/ergo.conf"])
+            .output()
+            .map_err(|e| NodeError::Other(format!("Failed to run docker: {e}")))?;
+        if !output.status.success() {
+            return Err(NodeError::Other(
+                String::from_utf8_lossy(&output.stderr).to_string(),
+            ));
+        }
+        let harness = NodeHarness {
+            node: NodeInterface::new(api_key, "127.0.0.1", &host_port.to_string())?,
+            container_id: Some(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        };
+
+        let start = Instant::now();
+        while harness.node.send_get_req("/info").is_err() {
+            if start.elapsed() > STARTUP_TIMEOUT {
+                return Err(NodeError::NodeUnreachable);
+            }
+            thread::sleep(Duration::from_secs(2));
+        }
+        Ok(harness)
+    }
+
+    /// Restore the node wallet from the given mnemonic (if it is not yet
+    /// initialized) and unlock it using `password`
+    pub fn provision_wallet(&self, mnemonic: &str, password: &str) -> Result<()> {
+        if !self.node.wallet_status()?.initialized {
+            let body = json!({
+                "pass": password,
+                "mnemonic": mnemonic,
+                "mnemonicPass": "",
+                "usePre1627KeyDerivation": false,
+            });
+            self.node.post_json("/wallet/restore", &body)?;
+        }
+        self.node.wallet_unlock(password)?;
+        Ok(())
+    }
+}
+
+impl Drop for NodeHarness {
+    fn drop(&mut self) {
+        if let Some(container_id) = &self.container_id {
+            let _ = Command::new("docker")
+                .args(["rm", "-f", container_id])
+                .output();
+        }
+    }
+}
+
+/// Returns a port which is currently free on the loopback interface
+pub fn free_port() -> Result<u16> {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .map_err(|e| NodeError::Other(format!("Failed to find a free port: {e}")))
+}
@@ -0,0 +1,92 @@
+//! Reconstruction of historical UTXO sets using the node's blockchain index,
+//! enabling backtesting and reconciliation against past heights.
+use crate::node_interface::{NodeInterface, Result};
+use crate::{BlockHeight, P2PKAddressString};
+use ergo_lib::chain::transaction::TxId;
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use std::collections::HashMap;
+
+/// Number of indexed boxes requested from the node per page
+const PAGE_LIMIT: usize = 100;
+
+/// Checks whether a box included at `inclusion_height` and spent at
+/// `spending_height` (if spent) was unspent at the end of block `height`
+fn unspent_at(
+    height: BlockHeight,
+    inclusion_height: BlockHeight,
+    spending_height: Option<BlockHeight>,
+) -> bool {
+    inclusion_height <= height && !matches!(spending_height, Some(spent) if spent <= height)
+}
+
+impl NodeInterface {
+    /// Returns the boxes of the given address which were unspent at the end
+    /// of block `height`. Requires the extra indexer.
+    pub fn address_unspent_boxes_at_height(
+        &self,
+        address: &P2PKAddressString,
+        height: BlockHeight,
+    ) -> Result<Vec<ErgoBox>> {
+        self.require_indexer()?;
+        self.check_address_network(address)?;
+        // Inclusion heights of spending transactions, shared between pages
+        let mut spending_heights: HashMap<TxId, BlockHeight> = HashMap::new();
+        let mut boxes = vec![];
+        for offset in (0..).step_by(PAGE_LIMIT) {
+            let items = self.boxes_by_address(address, offset, PAGE_LIMIT)?.items;
+            for item in &items {
+                let spending_height = match (item.spent_transaction_id, item.spending_height) {
+                    (_, Some(h)) => Some(h),
+                    (Some(tx_id), None) => {
+                        Some(self.indexed_tx_height(&tx_id, &mut spending_heights)?)
+                    }
+                    (None, None) => None,
+                };
+                if unspent_at(height, item.inclusion_height, spending_height) {
+                    boxes.push(item.ergo_box.clone());
+                }
+            }
+            if items.len() < PAGE_LIMIT {
+                break;
+            }
+        }
+        Ok(boxes)
+    }
+
+    /// Returns the boxes of all node wallet addresses which were unspent at
+    /// the end of block `height`. Requires the extra indexer.
+    pub fn unspent_boxes_at_height(&self, height: BlockHeight) -> Result<Vec<ErgoBox>> {
+        let mut boxes = vec![];
+        for address in self.wallet_addresses()? {
+            boxes.append(&mut self.address_unspent_boxes_at_height(&address, height)?);
+        }
+        Ok(boxes)
+    }
+
+    /// Get the inclusion height of an indexed transaction, caching the result
+    fn indexed_tx_height(
+        &self,
+        tx_id: &TxId,
+        cache: &mut HashMap<TxId, BlockHeight>,
+    ) -> Result<BlockHeight> {
+        if let Some(height) = cache.get(tx_id) {
+            return Ok(*height);
+        }
+        let height = self.indexed_transaction_by_id(tx_id)?.inclusion_height;
+        cache.insert(*tx_id, height);
+        Ok(height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unspent_at() {
+        assert!(unspent_at(100, 90, None));
+        assert!(unspent_at(100, 100, Some(101)));
+        assert!(!unspent_at(100, 90, Some(100)));
+        assert!(!unspent_at(100, 101, None));
+    }
+}
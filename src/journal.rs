@@ -0,0 +1,211 @@
+//! The `TxJournal` struct is defined here which records every transaction
+//! signed or submitted through a `NodeInterface` in an append-only file.
+//! The journal can be read back for crash recovery and audit trails.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use ergo_lib::chain::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The status of a transaction recorded in the journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TxStatus {
+    Signed,
+    Submitted,
+    Confirmed,
+    Dropped,
+    Failed,
+}
+
+/// A single line of the journal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    /// Unix timestamp in milliseconds of when the entry was recorded
+    pub timestamp: u64,
+    pub tx_id: String,
+    pub status: TxStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// The signed transaction itself, recorded by a `TxTracker` so the
+    /// tracking can be resumed from the journal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx: Option<Transaction>,
+}
+
+/// An append-only journal of transactions stored as JSON lines.
+/// Clones of a journal share the same file lock.
+#[derive(Debug, Clone)]
+pub struct TxJournal {
+    pub path: PathBuf,
+    lock: Arc<Mutex<()>>,
+}
+
+impl TxJournal {
+    /// Create a new `TxJournal` which appends to the file at `path`
+    pub fn new<P: AsRef<Path>>(path: P) -> TxJournal {
+        TxJournal {
+            path: path.as_ref().to_path_buf(),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Append a status update for the given transaction to the journal
+    pub fn record(&self, tx_id: &str, status: TxStatus, detail: Option<String>) -> Result<()> {
+        self.append(tx_id, status, detail, None)
+    }
+
+    /// Append a status update for the given transaction to the journal,
+    /// storing the signed transaction with it
+    pub fn record_transaction(
+        &self,
+        tx: &Transaction,
+        status: TxStatus,
+        detail: Option<String>,
+    ) -> Result<()> {
+        self.append(&String::from(tx.id()), status, detail, Some(tx.clone()))
+    }
+
+    fn append(
+        &self,
+        tx_id: &str,
+        status: TxStatus,
+        detail: Option<String>,
+        tx: Option<Transaction>,
+    ) -> Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let entry = JournalEntry {
+            timestamp,
+            tx_id: tx_id.to_string(),
+            status,
+            detail,
+            tx,
+        };
+        let line = serde_json::to_string(&entry).map_err(|e| NodeError::Other(e.to_string()))?;
+
+        let _guard = self.lock.lock().unwrap_or_else(|p| p.into_inner());
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| NodeError::Other(format!("Failed to open tx journal: {e}")))?;
+        writeln!(file, "{line}")
+            .map_err(|e| NodeError::Other(format!("Failed to write to tx journal: {e}")))
+    }
+
+    /// Read all entries of the journal in the order they were recorded
+    pub fn entries(&self) -> Result<Vec<JournalEntry>> {
+        let _guard = self.lock.lock().unwrap_or_else(|p| p.into_inner());
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(NodeError::Other(format!("Failed to read tx journal: {e}"))),
+        };
+        contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .map_err(|_| NodeError::Other(format!("Invalid tx journal entry: {line}")))
+            })
+            .collect()
+    }
+
+    /// Returns all entries recorded for the given transaction, oldest first
+    pub fn tx_entries(&self, tx_id: &str) -> Result<Vec<JournalEntry>> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|entry| entry.tx_id == tx_id)
+            .collect())
+    }
+
+    /// Returns the latest recorded status of every transaction in the journal
+    pub fn latest_statuses(&self) -> Result<BTreeMap<String, TxStatus>> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .map(|entry| (entry.tx_id, entry.status))
+            .collect())
+    }
+
+    /// Returns the ids of transactions which were submitted but have not
+    /// yet been recorded as confirmed, dropped or failed
+    pub fn pending_tx_ids(&self) -> Result<Vec<String>> {
+        Ok(self
+            .latest_statuses()?
+            .into_iter()
+            .filter(|(_, status)| matches!(status, TxStatus::Signed | TxStatus::Submitted))
+            .map(|(tx_id, _)| tx_id)
+            .collect())
+    }
+}
+
+impl NodeInterface {
+    /// Returns a `NodeInterface` which records every signed and submitted
+    /// transaction in the given `TxJournal`
+    pub fn with_tx_journal(mut self, journal: TxJournal) -> NodeInterface {
+        self.tx_journal = Some(journal);
+        self
+    }
+
+    /// Records a status update in the tx journal if one is configured.
+    /// Failing to write the journal does not fail the calling operation.
+    pub(crate) fn journal_tx_status(&self, tx_id: &str, status: TxStatus, detail: Option<String>) {
+        if let Some(journal) = &self.tx_journal {
+            journal.record(tx_id, status, detail).ok();
+        }
+    }
+
+    /// Records a submission refused by the node as failed. Transient errors
+    /// are not recorded, as the transaction may still have reached the node.
+    pub(crate) fn journal_failed_submission(&self, tx_id: &str, error: &NodeError) {
+        if !error.is_transient() {
+            self.journal_tx_status(tx_id, TxStatus::Failed, Some(error.to_string()));
+        }
+    }
+
+    /// Records a status update together with the signed transaction in the
+    /// tx journal if one is configured. Failing to write the journal does
+    /// not fail the calling operation.
+    pub(crate) fn journal_transaction(
+        &self,
+        tx: &Transaction,
+        status: TxStatus,
+        detail: Option<String>,
+    ) {
+        if let Some(journal) = &self.tx_journal {
+            journal.record_transaction(tx, status, detail).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_round_trip() {
+        let path = std::env::temp_dir().join(format!("tx-journal-{}.jsonl", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        let journal = TxJournal::new(&path);
+        journal.record("a", TxStatus::Submitted, None).unwrap();
+        journal.record("b", TxStatus::Submitted, None).unwrap();
+        journal
+            .record("a", TxStatus::Confirmed, Some("height 10".to_string()))
+            .unwrap();
+
+        assert_eq!(journal.entries().unwrap().len(), 3);
+        assert_eq!(journal.latest_statuses().unwrap()["a"], TxStatus::Confirmed);
+        assert_eq!(journal.pending_tx_ids().unwrap(), vec!["b".to_string()]);
+        std::fs::remove_file(&path).ok();
+    }
+}
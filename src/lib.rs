@@ -1,16 +1,67 @@
 #![allow(clippy::ptr_arg)]
 
-#[macro_use]
-extern crate json;
+pub mod address_matcher;
+pub mod airdrop;
+pub mod balances;
+pub mod blockchain;
+pub mod blocks;
+pub mod box_lease;
+pub mod box_selection;
+pub mod capabilities;
+pub mod coin_control;
+pub mod contracts;
+pub mod diagnostics;
+pub mod emission;
+pub mod endpoint_policy;
+pub mod export;
+#[cfg(feature = "it")]
+pub mod harness;
+pub mod history;
+pub mod journal;
 pub mod local_config;
+pub mod mempool;
+pub mod metrics;
+pub mod mining;
+pub mod multisig;
+pub mod nft;
+pub mod node_api;
 pub mod node_interface;
+#[cfg(feature = "async")]
+pub mod node_interface_async;
+pub mod node_pool;
+pub mod peers;
+pub mod politeness;
+pub mod query_backend;
 mod requests;
+pub mod scan_diff;
+pub mod scan_migration;
 pub mod scanning;
+pub mod scheduler;
+pub mod session;
+pub mod subscription;
+#[cfg(test)]
+mod test_fixtures;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tokens;
+pub mod tracking_rule;
 pub mod transactions;
+pub mod tx_request;
+pub mod tx_tracker;
+pub mod voting;
+pub mod wallet_snapshot;
+pub mod wallet_transactions;
+pub mod watcher;
 
+pub use coin_control::CoinControl;
 pub use local_config::*;
+pub use node_api::NodeApi;
 pub use node_interface::NodeInterface;
+#[cfg(feature = "async")]
+pub use node_interface_async::NodeInterfaceAsync;
 pub use scanning::Scan;
+pub use tracking_rule::TrackingRule;
+pub use tx_request::TxRequestBuilder;
 
 /// A Base58 encoded String of a Ergo P2PK address.
 pub type P2PKAddressString = String;
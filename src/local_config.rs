@@ -1,18 +1,26 @@
 /// Functions related to saving/accessing local data
 /// for interacting with an Ergo Node. (Ip/Port/Api Key)
-use crate::node_interface::{NodeError, NodeInterface, Result};
+use crate::endpoint_policy::EndpointPolicy;
+use crate::node_interface::{Network, NodeError, NodeInterface, Result};
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::Path;
 use yaml_rust::{Yaml, YamlLoader};
 
 static BAREBONES_CONFIG_YAML: &str = r#"
+# Network of the node, either "mainnet" or "testnet"
+network: "mainnet"
 # IP Address of the node (default is local, edit if yours is different)
 node_ip: "0.0.0.0"
-# Port that the node is on (default is 9053, edit if yours is different)
+# Port that the node is on (defaults to 9053 on mainnet and 9052 on testnet if omitted)
 node_port: "9053"
+# Connect to the node over https, e.g. when it is behind a reverse proxy with TLS
+node_use_https: false
 # API key for the node (edit if yours is different)
 node_api_key: "hello"
+# Optionally restrict the endpoint groups which may be called, using either
+# `allowed_endpoint_groups` or `denied_endpoint_groups`
+# denied_endpoint_groups: ["mining", "node"]
 "#;
 
 /// A ease-of-use function which attempts to acquire a `NodeInterface`
@@ -63,16 +71,60 @@ pub fn create_new_local_config_file() -> Result<()> {
 
 /// Uses the config yaml provided to create a new `NodeInterface`
 pub fn new_interface_from_yaml(config: Yaml) -> Result<NodeInterface> {
+    let network = match config["network"].as_str() {
+        Some(network) => Some(network.parse::<Network>()?),
+        None => None,
+    };
     let ip = config["node_ip"].as_str().ok_or_else(|| {
         NodeError::YamlError("`node_ip` is not specified in the provided Yaml".to_string())
     })?;
-    let port = config["node_port"].as_str().ok_or_else(|| {
-        NodeError::YamlError("`node_port` is not specified in the provided Yaml".to_string())
-    })?;
+    let port = match (config["node_port"].as_str(), network) {
+        (Some(port), _) => port,
+        (None, Some(network)) => network.default_port(),
+        (None, None) => {
+            return Err(NodeError::YamlError(
+                "`node_port` is not specified in the provided Yaml".to_string(),
+            ))
+        }
+    };
     let api_key = config["node_api_key"].as_str().ok_or_else(|| {
         NodeError::YamlError("`node_api_key` is not specified in the provided Yaml".to_string())
     })?;
-    NodeInterface::new(api_key, ip, port)
+    let endpoint_policy = endpoint_policy_from_yaml(&config)?;
+    let node = match config["node_use_https"].as_bool() {
+        Some(true) => NodeInterface::new_https(api_key, ip, port)?,
+        _ => NodeInterface::new(api_key, ip, port)?,
+    }
+    .with_endpoint_policy(endpoint_policy);
+    Ok(match network {
+        Some(network) => node.with_network(network),
+        None => node,
+    })
+}
+
+/// Reads the optional `allowed_endpoint_groups` or `denied_endpoint_groups`
+/// lists of a config into an `EndpointPolicy`
+fn endpoint_policy_from_yaml(config: &Yaml) -> Result<EndpointPolicy> {
+    let groups = |key: &str| -> Option<Vec<String>> {
+        config[key].as_vec().map(|groups| {
+            groups
+                .iter()
+                .filter_map(|g| g.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+    };
+    match (
+        groups("allowed_endpoint_groups"),
+        groups("denied_endpoint_groups"),
+    ) {
+        (Some(_), Some(_)) => Err(NodeError::YamlError(
+            "Only one of `allowed_endpoint_groups` and `denied_endpoint_groups` may be specified"
+                .to_string(),
+        )),
+        (Some(allowed), None) => Ok(EndpointPolicy::Allow(allowed)),
+        (None, Some(denied)) => Ok(EndpointPolicy::Deny(denied)),
+        (None, None) => Ok(EndpointPolicy::AllowAll),
+    }
 }
 
 /// Opens a local `node-interface.yaml` file and uses the
@@ -84,3 +136,45 @@ pub fn new_interface_from_local_config() -> Result<NodeInterface> {
     let yaml = YamlLoader::load_from_str(&yaml_str).unwrap()[0].clone();
     new_interface_from_yaml(yaml)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::TEST_ADDRESS;
+
+    #[test]
+    fn test_testnet_config_uses_default_port() {
+        let yaml = YamlLoader::load_from_str(
+            r#"
+network: "testnet"
+node_ip: "127.0.0.1"
+node_api_key: "hello"
+"#,
+        )
+        .unwrap()[0]
+            .clone();
+        let node = new_interface_from_yaml(yaml).unwrap();
+        assert_eq!(node.url.port(), Some(9052));
+        assert_eq!(node.network, Some(Network::Testnet));
+        assert!(node.check_address_network(TEST_ADDRESS).is_ok());
+        assert!(node
+            .check_address_network("9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA")
+            .is_err());
+    }
+
+    #[test]
+    fn test_https_config() {
+        let yaml = YamlLoader::load_from_str(
+            r#"
+node_ip: "node.example.com"
+node_port: "443"
+node_use_https: true
+node_api_key: "hello"
+"#,
+        )
+        .unwrap()[0]
+            .clone();
+        let node = new_interface_from_yaml(yaml).unwrap();
+        assert_eq!(node.url.as_str(), "https://node.example.com/");
+    }
+}
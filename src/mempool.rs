@@ -0,0 +1,94 @@
+//! Mempool-related endpoints for inspecting unconfirmed transactions, e.g.
+//! to check whether a tx is already in the pool before re-broadcasting.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use crate::transactions::is_miner_fee_tree;
+use crate::NanoErg;
+use ergo_lib::chain::transaction::{Transaction, TxId};
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use reqwest::StatusCode;
+
+/// An unconfirmed transaction from the mempool with its size and fee
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MempoolTx {
+    pub tx: Transaction,
+    /// Size of the sigma-serialized transaction in bytes
+    pub size: usize,
+    /// Sum of the miner fee outputs of the transaction
+    pub miner_fee: NanoErg,
+}
+
+impl MempoolTx {
+    /// Fee paid per byte of the serialized transaction
+    pub fn fee_per_byte(&self) -> f64 {
+        self.miner_fee as f64 / self.size.max(1) as f64
+    }
+}
+
+impl NodeInterface {
+    /// Get a page of unconfirmed transactions from the mempool together with
+    /// their serialized size and fee.
+    pub fn unconfirmed_transactions(&self, limit: u32, offset: u32) -> Result<Vec<MempoolTx>> {
+        let endpoint = format!("/transactions/unconfirmed?limit={limit}&offset={offset}");
+        let res_json = self.get_json(&endpoint)?;
+        let mut mempool_txs = vec![];
+        for tx_json in res_json.as_array().into_iter().flatten() {
+            let tx: Transaction = serde_json::from_value(tx_json.clone())
+                .map_err(|_| NodeError::FailedParsingNodeResponse(tx_json.to_string()))?;
+            mempool_txs.push(self.mempool_tx(tx)?);
+        }
+        Ok(mempool_txs)
+    }
+
+    /// Get an unconfirmed transaction from the mempool by its id, returning
+    /// `None` if the transaction is not in the mempool.
+    pub fn unconfirmed_transaction_by_id(&self, tx_id: &TxId) -> Result<Option<MempoolTx>> {
+        let endpoint = format!(
+            "/transactions/unconfirmed/byTransactionId/{}",
+            String::from(*tx_id)
+        );
+        let res = self.send_get_req(&endpoint)?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let tx_json = self.parse_response_to_json(Ok(res))?;
+        let tx: Transaction = serde_json::from_value(tx_json.clone())
+            .map_err(|_| NodeError::FailedParsingNodeResponse(tx_json.to_string()))?;
+        self.mempool_tx(tx).map(Some)
+    }
+
+    /// Checks whether the transaction is currently in the mempool
+    pub fn is_in_mempool(&self, tx_id: &TxId) -> Result<bool> {
+        let endpoint = format!(
+            "/transactions/unconfirmed/byTransactionId/{}",
+            String::from(*tx_id)
+        );
+        Ok(self.send_get_req(&endpoint)?.status().is_success())
+    }
+
+    /// Get the number of unconfirmed transactions in the mempool
+    pub fn mempool_size(&self) -> Result<u64> {
+        let res_json = self.get_json("/transactions/unconfirmed/size")?;
+        res_json["size"]
+            .as_u64()
+            .ok_or_else(|| NodeError::FailedParsingNodeResponse(res_json.to_string()))
+    }
+
+    /// Wraps a `Transaction` into a `MempoolTx`
+    fn mempool_tx(&self, tx: Transaction) -> Result<MempoolTx> {
+        let size = tx
+            .sigma_serialize_bytes()
+            .map_err(|e| NodeError::Other(e.to_string()))?
+            .len();
+        let miner_fee = tx
+            .outputs
+            .iter()
+            .filter(|o| is_miner_fee_tree(&o.ergo_tree))
+            .map(|o| *o.value.as_u64())
+            .sum();
+        Ok(MempoolTx {
+            tx,
+            size,
+            miner_fee,
+        })
+    }
+}
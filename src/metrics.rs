@@ -0,0 +1,101 @@
+//! Request metrics which are recorded for every request sent to the node,
+//! allowing long-running services to implement their own health reporting.
+use crate::node_interface::NodeInterface;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Summary of the requests sent to a single endpoint
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EndpointStats {
+    /// Total number of requests sent
+    pub requests: u64,
+    /// Number of requests which failed to reach the node or returned an error status
+    pub errors: u64,
+    /// Latency of the most recent request
+    pub last_latency: Duration,
+    /// Description of the most recent error
+    pub last_error: Option<String>,
+}
+
+/// Metrics of all requests sent by a `NodeInterface`, shared between its clones
+#[derive(Debug, Clone, Default)]
+pub struct RequestMetrics {
+    endpoints: Arc<Mutex<HashMap<String, EndpointStats>>>,
+    last_latency: Arc<Mutex<Option<Duration>>>,
+}
+
+impl RequestMetrics {
+    /// Records the outcome of a request to `endpoint`. Query parameters are
+    /// stripped so requests to the same path are aggregated together.
+    pub fn record(&self, endpoint: &str, latency: Duration, error: Option<String>) {
+        let path = endpoint.split('?').next().unwrap_or(endpoint).to_string();
+        let mut endpoints = self.endpoints.lock().unwrap_or_else(|p| p.into_inner());
+        let stats = endpoints.entry(path).or_default();
+        stats.requests += 1;
+        stats.last_latency = latency;
+        if error.is_some() {
+            stats.errors += 1;
+            stats.last_error = error;
+        }
+        *self.last_latency.lock().unwrap_or_else(|p| p.into_inner()) = Some(latency);
+    }
+
+    /// Returns a snapshot of the stats of every endpoint
+    pub fn snapshot(&self) -> HashMap<String, EndpointStats> {
+        self.endpoints
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .clone()
+    }
+
+    /// Clears all recorded metrics
+    pub fn reset(&self) {
+        self.endpoints
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .clear();
+        *self.last_latency.lock().unwrap_or_else(|p| p.into_inner()) = None;
+    }
+}
+
+impl NodeInterface {
+    /// Returns the latency of the most recent request sent to the node
+    pub fn last_latency(&self) -> Option<Duration> {
+        *self
+            .metrics
+            .last_latency
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+    }
+
+    /// Returns a summary of the request outcomes of every endpoint used
+    pub fn error_stats(&self) -> HashMap<String, EndpointStats> {
+        self.metrics.snapshot()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_aggregate_by_path() {
+        let metrics = RequestMetrics::default();
+        metrics.record("/info", Duration::from_millis(5), None);
+        metrics.record(
+            "/wallet/boxes/unspent?minConfirmations=0",
+            Duration::from_millis(7),
+            Some("500".to_string()),
+        );
+        metrics.record("/wallet/boxes/unspent", Duration::from_millis(3), None);
+
+        let stats = metrics.snapshot();
+        assert_eq!(stats["/info"].requests, 1);
+        let unspent = &stats["/wallet/boxes/unspent"];
+        assert_eq!(unspent.requests, 2);
+        assert_eq!(unspent.errors, 1);
+        assert_eq!(unspent.last_latency, Duration::from_millis(3));
+        assert_eq!(unspent.last_error, Some("500".to_string()));
+    }
+}
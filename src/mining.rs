@@ -0,0 +1,107 @@
+//! Typed access to the mining endpoints of the node, as used by mining
+//! pool software. The node must be started with `mining = true`.
+use crate::capabilities::NodeFeature;
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use crate::requests::check_node_error;
+use crate::{BlockHeight, P2PKAddressString};
+
+/// A block candidate to be mined as returned by `/mining/candidate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MiningCandidate {
+    /// Base16-encoded message (header bytes without the PoW solution) to mine
+    pub msg: String,
+    /// The target `b` in decimal. Kept as a string since it does not fit
+    /// into any primitive integer type.
+    pub target: String,
+    /// Height of the block candidate
+    pub height: BlockHeight,
+    /// Base16-encoded public key of the miner
+    pub pk: String,
+}
+
+impl NodeInterface {
+    /// Get the current block candidate to mine
+    pub fn mining_candidate(&self) -> Result<MiningCandidate> {
+        self.require_feature(NodeFeature::Mining)?;
+        let res = self.send_get_req("/mining/candidate")?;
+        let status = res.status();
+        let text = self.read_response_text(res)?;
+        check_node_error(status, &text)?;
+        parse_mining_candidate(&text).ok_or(NodeError::FailedParsingNodeResponse(text))
+    }
+
+    /// Get the address which receives the mining rewards of the node
+    pub fn mining_reward_address(&self) -> Result<P2PKAddressString> {
+        self.require_feature(NodeFeature::Mining)?;
+        let res_json = self.get_json("/mining/rewardAddress")?;
+        res_json["rewardAddress"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| NodeError::FailedParsingNodeResponse(res_json.to_string()))
+    }
+
+    /// Get the base16-encoded public key which receives the mining rewards
+    /// of the node
+    pub fn mining_reward_public_key(&self) -> Result<String> {
+        self.require_feature(NodeFeature::Mining)?;
+        let res_json = self.get_json("/mining/rewardPublicKey")?;
+        res_json["rewardPubkey"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| NodeError::FailedParsingNodeResponse(res_json.to_string()))
+    }
+
+    /// Submit an Autolykos v2 solution (the base16-encoded nonce) for the
+    /// current block candidate
+    pub fn submit_mining_solution(&self, nonce: &str) -> Result<()> {
+        self.require_feature(NodeFeature::Mining)?;
+        let body = serde_json::json!({ "n": nonce });
+        let res = self.send_post_req("/mining/solution", body.to_string())?;
+        if res.status().is_success() {
+            Ok(())
+        } else {
+            self.parse_response_to_json(Ok(res)).map(|_| ())
+        }
+    }
+}
+
+/// Parses a `/mining/candidate` response. The target `b` is extracted from
+/// the raw text as parsing it as a JSON number would lose precision.
+fn parse_mining_candidate(text: &str) -> Option<MiningCandidate> {
+    let json: serde_json::Value = serde_json::from_str(text).ok()?;
+    Some(MiningCandidate {
+        msg: json["msg"].as_str()?.to_string(),
+        target: raw_number_field(text, "b")?,
+        height: json["h"].as_u64()?,
+        pk: json["pk"].as_str()?.to_string(),
+    })
+}
+
+/// Extracts the digits of a numeric field from raw JSON text
+fn raw_number_field(text: &str, field: &str) -> Option<String> {
+    let key = format!("\"{field}\"");
+    let after_key = &text[text.find(&key)? + key.len()..];
+    let value = after_key.trim_start().strip_prefix(':')?.trim_start();
+    let digits: String = value.chars().take_while(|c| c.is_ascii_digit()).collect();
+    (!digits.is_empty()).then_some(digits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mining_candidate() {
+        let text = r#"{"msg": "0350e25cee8562697d55275c96bb01b34228f9bd68fd9933f2a25ff195526864",
+            "b": 748014723576678314041035877227113663879264849498014394977645987,
+            "h": 1086360,
+            "pk": "0278011ec0cf5feb92d61adb51dcb75876627ace6fd9446ab4cabc5313ab7b39a7"}"#;
+        let candidate = parse_mining_candidate(text).unwrap();
+        assert_eq!(
+            candidate.target,
+            "748014723576678314041035877227113663879264849498014394977645987"
+        );
+        assert_eq!(candidate.height, 1086360);
+        assert!(parse_mining_candidate(r#"{"msg": "00", "h": 1, "pk": "02"}"#).is_none());
+    }
+}
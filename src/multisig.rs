@@ -0,0 +1,92 @@
+//! Helpers for taking part in distributed multi-party signing flows using
+//! the node wallet. Hints (commitments and partial proofs) are exchanged
+//! between signers as transaction hints bags in the node's JSON format,
+//! i.e. `{"secretHints": {..}, "publicHints": {..}}` keyed by input index.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use crate::transactions::encode_boxes;
+use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+use ergo_lib::chain::transaction::Transaction;
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+use serde_json::{json, Value};
+
+impl NodeInterface {
+    /// Generate commitments for the inputs of an `UnsignedTransaction` which
+    /// can be signed by the node wallet. The secret hints must be kept by the
+    /// node owner while the public hints are shared with the other signers.
+    /// boxes_to_spend - optional list of input boxes. If not provided, the node will search for the boxes in UTXO
+    /// data_input_boxes - optional list of data boxes. If not provided, the node will search for the data boxes in UTXO
+    pub fn generate_commitments(
+        &self,
+        unsigned_tx: &UnsignedTransaction,
+        boxes_to_spend: Option<Vec<ErgoBox>>,
+        data_input_boxes: Option<Vec<ErgoBox>>,
+    ) -> Result<Value> {
+        let body = json!({
+            "tx": unsigned_tx,
+            "inputsRaw": encode_boxes(boxes_to_spend)?,
+            "dataInputsRaw": encode_boxes(data_input_boxes)?,
+        });
+        self.post_json("/wallet/generateCommitments", &body)
+    }
+
+    /// Extract the hints from a (partially) signed `Transaction`, given the
+    /// public keys of the signers which actually signed it (`real`) and of
+    /// those whose proofs were simulated (`simulated`). The resulting hints
+    /// are passed to `sign_transaction_with_hints` by the remaining signers.
+    pub fn extract_hints(
+        &self,
+        signed_tx: &Transaction,
+        real: &[ProveDlog],
+        simulated: &[ProveDlog],
+        boxes_to_spend: Option<Vec<ErgoBox>>,
+        data_input_boxes: Option<Vec<ErgoBox>>,
+    ) -> Result<Value> {
+        let body = json!({
+            "tx": signed_tx,
+            "real": real.iter().map(prove_dlog_json).collect::<Result<Vec<Value>>>()?,
+            "simulated": simulated.iter().map(prove_dlog_json).collect::<Result<Vec<Value>>>()?,
+            "inputsRaw": encode_boxes(boxes_to_spend)?,
+            "dataInputsRaw": encode_boxes(data_input_boxes)?,
+        });
+        self.post_json("/wallet/extractHints", &body)
+    }
+}
+
+/// Encodes a public key as a sigma proposition in the node's JSON format,
+/// where `op` is the signed opcode byte of `ProveDlog`
+fn prove_dlog_json(pk: &ProveDlog) -> Result<Value> {
+    let h =
+        pk.h.sigma_serialize_bytes()
+            .map_err(|e| NodeError::Other(e.to_string()))?;
+    Ok(json!({
+        "op": -51,
+        "h": base16::encode_lower(&h),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ergo_lib::ergotree_ir::chain::address::{Address, AddressEncoder};
+
+    #[test]
+    fn test_prove_dlog_json() {
+        let address = AddressEncoder::unchecked_parse_address_from_str(
+            "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA",
+        )
+        .unwrap();
+        let pk = match address {
+            Address::P2Pk(pk) => pk,
+            _ => panic!("expected a P2PK address"),
+        };
+        assert_eq!(
+            prove_dlog_json(&pk).unwrap(),
+            json!({
+                "op": -51,
+                "h": "02764ea2b0b9b06b5730a4257bba71fd7797eb1ec12bc3ae6025a01d7fba53830e"
+            })
+        );
+    }
+}
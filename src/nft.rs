@@ -0,0 +1,155 @@
+//! Helpers for minting NFTs following the EIP-4 asset standard, with the
+//! optional royalty stored in the issuer box as described by EIP-24.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use crate::tx_request::{AssetIssueRequest, PaymentRequest, TxRequestBuilder, TxRequestItem};
+use crate::{NanoErg, TokenID};
+use ergo_lib::ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use ergo_lib::ergotree_ir::mir::constant::Constant;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use ergo_lib::wallet::tx_builder::SUGGESTED_TX_FEE;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+/// The EIP-4 asset type of an NFT, stored in R7
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NftType {
+    Picture,
+    Audio,
+    Video,
+}
+
+impl NftType {
+    /// The R7 bytes identifying the asset type
+    pub fn type_bytes(&self) -> [u8; 2] {
+        match self {
+            NftType::Picture => [0x01, 0x01],
+            NftType::Audio => [0x01, 0x02],
+            NftType::Video => [0x01, 0x03],
+        }
+    }
+}
+
+impl NodeInterface {
+    /// Mints an NFT of the given type through the node wallet and returns its
+    /// token id. R7 holds the asset type, R8 the SHA-256 `content_hash` and R9
+    /// the `content_url` as per EIP-4. If a `royalty` (in per mille) is
+    /// provided, an issuer box holding it in R4 is created first and the NFT
+    /// is minted from it as per EIP-24.
+    pub fn mint_nft(
+        &self,
+        nft_type: NftType,
+        name: &str,
+        description: &str,
+        content_hash: &[u8],
+        content_url: &str,
+        royalty: Option<u32>,
+    ) -> Result<TokenID> {
+        let nft_value = *BoxValue::SAFE_USER_MIN.as_u64();
+        let fee = *SUGGESTED_TX_FEE().as_u64();
+        let mut tx_request = TxRequestBuilder::new().with_fee(fee);
+        if let Some(royalty) = royalty {
+            let issuer_box = self.create_issuer_box(royalty, nft_value + fee)?;
+            let issuer_bytes = issuer_box
+                .sigma_serialize_bytes()
+                .map_err(|e| NodeError::Other(e.to_string()))?;
+            tx_request = tx_request.with_raw_inputs(vec![base16::encode_lower(&issuer_bytes)]);
+        }
+        let tx_request = tx_request.with_request(TxRequestItem::AssetIssue(AssetIssueRequest {
+            address: None,
+            erg_value: Some(nft_value),
+            amount: 1,
+            name: name.to_string(),
+            description: description.to_string(),
+            decimals: 0,
+            registers: nft_registers(nft_type, content_hash, content_url)?,
+        }));
+
+        let signed_tx = self.generate_transaction_from_request(&tx_request)?;
+        // The id of a newly issued token is the id of the first input box
+        let token_id = signed_tx["inputs"][0]["boxId"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| NodeError::FailedParsingNodeResponse(signed_tx.to_string()))?;
+        self.submit_json_transaction(&signed_tx.to_string())?;
+        Ok(token_id)
+    }
+
+    /// Submits a transaction creating an EIP-24 issuer box holding `value`
+    /// at the first wallet address with the `royalty` in R4
+    fn create_issuer_box(&self, royalty: u32, value: NanoErg) -> Result<ErgoBox> {
+        let address = self
+            .wallet_addresses()?
+            .into_iter()
+            .next()
+            .ok_or(NodeError::NoAddressesInWallet)?;
+        let royalty = i32::try_from(royalty).map_err(|e| NodeError::Other(e.to_string()))?;
+        let mut registers = BTreeMap::new();
+        registers.insert("R4".to_string(), encode_register(Constant::from(royalty))?);
+        let tx_request =
+            TxRequestBuilder::new().with_request(TxRequestItem::Payment(PaymentRequest {
+                address,
+                value,
+                assets: vec![],
+                registers: registers.clone(),
+            }));
+
+        let signed_tx = self.generate_transaction_from_request(&tx_request)?;
+        let issuer_box_json = signed_tx["outputs"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|o| o["additionalRegisters"]["R4"].as_str() == Some(registers["R4"].as_str()))
+            .ok_or_else(|| NodeError::FailedParsingNodeResponse(signed_tx.to_string()))?;
+        let issuer_box = serde_json::from_value(issuer_box_json.clone())
+            .map_err(|e| NodeError::FailedParsingBox(e.to_string()))?;
+        self.submit_json_transaction(&signed_tx.to_string())?;
+        Ok(issuer_box)
+    }
+}
+
+/// Encodes the EIP-4 registers R7 to R9 of an NFT
+fn nft_registers(
+    nft_type: NftType,
+    content_hash: &[u8],
+    content_url: &str,
+) -> Result<BTreeMap<String, String>> {
+    let mut registers = BTreeMap::new();
+    let values = [
+        ("R7", nft_type.type_bytes().to_vec()),
+        ("R8", content_hash.to_vec()),
+        ("R9", content_url.as_bytes().to_vec()),
+    ];
+    for (register, bytes) in values {
+        registers.insert(
+            register.to_string(),
+            encode_register(Constant::from(bytes))?,
+        );
+    }
+    Ok(registers)
+}
+
+/// Serializes a register value into the hex format accepted by the node
+fn encode_register(value: Constant) -> Result<String> {
+    value
+        .sigma_serialize_bytes()
+        .map(|bytes| base16::encode_lower(&bytes))
+        .map_err(|e| NodeError::Other(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nft_registers() {
+        let registers = nft_registers(NftType::Picture, &[0xab; 32], "ipfs://cid").unwrap();
+        assert_eq!(registers["R7"], "0e020101");
+        assert_eq!(registers["R8"], format!("0e20{}", "ab".repeat(32)));
+        assert_eq!(
+            registers["R9"],
+            format!("0e0a{}", base16::encode_lower("ipfs://cid"))
+        );
+        assert_eq!(encode_register(Constant::from(20i32)).unwrap(), "0428");
+    }
+}
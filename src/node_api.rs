@@ -0,0 +1,155 @@
+//! Traits covering the core functionality of `NodeInterface`, grouped by
+//! area. Code which accepts any implementation of these traits (e.g.
+//! `impl WalletApi`) can be unit tested against a mock instead of a node.
+use crate::node_interface::{NodeInterface, Result, WalletStatus};
+use crate::scanning::ScanRegistrationRequest;
+use crate::tx_request::TxRequestBuilder;
+use crate::{BlockHeight, NanoErg, P2PKAddressString, ScanID};
+use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+use ergo_lib::chain::transaction::{Transaction, TxId};
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+
+/// Access to the state of the blockchain
+pub trait ChainApi {
+    fn current_block_height(&self) -> Result<BlockHeight>;
+    fn box_from_id(&self, box_id: &String) -> Result<ErgoBox>;
+}
+
+/// Access to the node wallet
+pub trait WalletApi {
+    fn wallet_status(&self) -> Result<WalletStatus>;
+    fn wallet_addresses(&self) -> Result<Vec<P2PKAddressString>>;
+    fn wallet_nano_ergs_balance(&self) -> Result<NanoErg>;
+    fn unspent_boxes(&self) -> Result<Vec<ErgoBox>>;
+    fn unspent_boxes_with_min_total(&self, total: NanoErg) -> Result<Vec<ErgoBox>>;
+}
+
+/// Access to the UTXO-set scans of the node
+pub trait ScanApi {
+    fn register_scan(&self, request: &ScanRegistrationRequest) -> Result<ScanID>;
+    fn scan_boxes(&self, scan_id: &ScanID) -> Result<Vec<ErgoBox>>;
+    fn add_box_to_scan(&self, scan_id: &ScanID, box_id: &String) -> Result<String>;
+    fn stop_tracking_box(&self, scan_id: &ScanID, box_id: &String) -> Result<()>;
+}
+
+/// Signing, submission and tracking of transactions
+pub trait TransactionApi {
+    fn sign_transaction(
+        &self,
+        unsigned_tx: &UnsignedTransaction,
+        boxes_to_spend: Option<Vec<ErgoBox>>,
+        data_input_boxes: Option<Vec<ErgoBox>>,
+    ) -> Result<Transaction>;
+    fn submit_transaction(&self, signed_tx: &Transaction) -> Result<TxId>;
+    fn send_transaction_from_request(&self, tx_request: &TxRequestBuilder) -> Result<TxId>;
+    fn tx_confirmations(&self, tx_id: &TxId) -> Result<Option<u64>>;
+}
+
+/// All node functionality covered by the traits of this module
+pub trait NodeApi: ChainApi + WalletApi + ScanApi + TransactionApi {}
+
+impl<T: ChainApi + WalletApi + ScanApi + TransactionApi> NodeApi for T {}
+
+impl ChainApi for NodeInterface {
+    fn current_block_height(&self) -> Result<BlockHeight> {
+        NodeInterface::current_block_height(self)
+    }
+
+    fn box_from_id(&self, box_id: &String) -> Result<ErgoBox> {
+        NodeInterface::box_from_id(self, box_id)
+    }
+}
+
+impl WalletApi for NodeInterface {
+    fn wallet_status(&self) -> Result<WalletStatus> {
+        NodeInterface::wallet_status(self)
+    }
+
+    fn wallet_addresses(&self) -> Result<Vec<P2PKAddressString>> {
+        NodeInterface::wallet_addresses(self)
+    }
+
+    fn wallet_nano_ergs_balance(&self) -> Result<NanoErg> {
+        NodeInterface::wallet_nano_ergs_balance(self)
+    }
+
+    fn unspent_boxes(&self) -> Result<Vec<ErgoBox>> {
+        NodeInterface::unspent_boxes(self)
+    }
+
+    fn unspent_boxes_with_min_total(&self, total: NanoErg) -> Result<Vec<ErgoBox>> {
+        NodeInterface::unspent_boxes_with_min_total(self, total)
+    }
+}
+
+impl ScanApi for NodeInterface {
+    fn register_scan(&self, request: &ScanRegistrationRequest) -> Result<ScanID> {
+        NodeInterface::register_scan(self, request)
+    }
+
+    fn scan_boxes(&self, scan_id: &ScanID) -> Result<Vec<ErgoBox>> {
+        NodeInterface::scan_boxes(self, scan_id)
+    }
+
+    fn add_box_to_scan(&self, scan_id: &ScanID, box_id: &String) -> Result<String> {
+        NodeInterface::add_box_to_scan(self, scan_id, box_id)
+    }
+
+    fn stop_tracking_box(&self, scan_id: &ScanID, box_id: &String) -> Result<()> {
+        NodeInterface::stop_tracking_box(self, scan_id, box_id)
+    }
+}
+
+impl TransactionApi for NodeInterface {
+    fn sign_transaction(
+        &self,
+        unsigned_tx: &UnsignedTransaction,
+        boxes_to_spend: Option<Vec<ErgoBox>>,
+        data_input_boxes: Option<Vec<ErgoBox>>,
+    ) -> Result<Transaction> {
+        NodeInterface::sign_transaction(self, unsigned_tx, boxes_to_spend, data_input_boxes)
+    }
+
+    fn submit_transaction(&self, signed_tx: &Transaction) -> Result<TxId> {
+        NodeInterface::submit_transaction(self, signed_tx)
+    }
+
+    fn send_transaction_from_request(&self, tx_request: &TxRequestBuilder) -> Result<TxId> {
+        NodeInterface::send_transaction_from_request(self, tx_request)
+    }
+
+    fn tx_confirmations(&self, tx_id: &TxId) -> Result<Option<u64>> {
+        NodeInterface::tx_confirmations(self, tx_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node_interface::NodeError;
+
+    struct MockChain {
+        height: BlockHeight,
+    }
+
+    impl ChainApi for MockChain {
+        fn current_block_height(&self) -> Result<BlockHeight> {
+            Ok(self.height)
+        }
+
+        fn box_from_id(&self, box_id: &String) -> Result<ErgoBox> {
+            Err(NodeError::Other(format!("No box {box_id}")))
+        }
+    }
+
+    fn blocks_until(chain: &impl ChainApi, target_height: BlockHeight) -> Result<u64> {
+        Ok(target_height.saturating_sub(chain.current_block_height()?))
+    }
+
+    #[test]
+    fn test_mock_chain_api() {
+        let chain = MockChain { height: 100 };
+        assert_eq!(blocks_until(&chain, 110).unwrap(), 10);
+        assert!(chain.box_from_id(&"abc".to_string()).is_err());
+    }
+}
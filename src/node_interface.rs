@@ -1,11 +1,31 @@
 //! The `NodeInterface` struct is defined which allows for interacting with an Ergo Node via Rust.
 
-use crate::{BlockHeight, NanoErg, P2PKAddressString, P2SAddressString};
+use crate::box_lease::BoxLeaseRegistry;
+use crate::box_selection::{BoxSelector, LargestFirst, OldestFirst};
+use crate::capabilities::{CapabilitiesCache, IndexerLag, IndexerLagPolicy, NodeFeature};
+use crate::endpoint_policy::EndpointPolicy;
+use crate::journal::TxJournal;
+use crate::metrics::RequestMetrics;
+use crate::politeness::Politeness;
+use crate::requests::string_field;
+use crate::session::SessionMonitor;
+use crate::tx_request::ChangeAddressPolicy;
+use crate::{BlockHeight, NanoErg, P2PKAddressString, P2SAddressString, TokenID};
+use ergo_lib::ergotree_ir::chain::address::{Address, AddressEncoder, NetworkPrefix};
 use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+use ergo_lib::ergotree_ir::serialization::sigma_byte_writer::SigmaByteWriter;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use ergo_lib::wallet::derivation_path::ChildIndexNormal;
+use ergo_lib::wallet::ext_pub_key::ExtPubKey;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use reqwest::Url;
-use serde_json::from_str;
+use serde_json::{from_str, json};
 use serde_with::serde_as;
 use serde_with::NoneAsEmptyString;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::Duration;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, NodeError>;
@@ -26,6 +46,8 @@ pub enum NodeError {
     FailedRegisteringScan(String),
     #[error("The node rejected the request you provided.\nNode Response: {0}")]
     BadRequest(String),
+    #[error("The node returned an error: {0}")]
+    Api(NodeApiError),
     #[error("The node wallet has no addresses.")]
     NoAddressesInWallet,
     #[error("The node is still syncing.")]
@@ -38,6 +60,184 @@ pub enum NodeError {
     FailedParsingWalletStatus(String),
     #[error("Failed to parse URL: {0}")]
     InvalidUrl(String),
+    #[error("Coin control violation: {0}")]
+    CoinControlViolation(String),
+    #[error("The {0} feature is disabled on the node. Please enable it in the node config.")]
+    FeatureDisabledOnNode(NodeFeature),
+    #[error("The node response exceeded the maximum allowed size of {0} bytes.")]
+    ResponseTooLarge(usize),
+    #[error("The extra indexer of the node is lagging behind: {0}.")]
+    IndexerBehind(IndexerLag),
+    #[error("Timed out waiting for the confirmation of transaction {0}.")]
+    ConfirmationTimeout(String),
+    #[error("The transaction is invalid: {0}")]
+    InvalidTransaction(String),
+    #[error("Failed parsing transaction id from node response: {0}")]
+    FailedParsingTxId(String),
+    #[error("The node returned transaction id {1} instead of the submitted {0}.")]
+    TxIdMismatch(String, String),
+    #[error("The endpoint {0} is not allowed by the endpoint policy.")]
+    EndpointNotAllowed(String),
+    #[error("The endpoint {0} is blocked as the NodeInterface is read-only.")]
+    ReadOnlyViolation(String),
+    #[error("The address {0} does not belong to the {1} network.")]
+    WrongNetworkAddress(String, Network),
+    #[error("The chain was reorganized at or below height {0}.")]
+    ChainReorganized(BlockHeight),
+}
+
+impl NodeError {
+    /// Checks whether the error is a node response stating that the
+    /// requested item (box, transaction, ..) does not exist
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, NodeError::Api(e) if e.error == 404)
+    }
+
+    /// Checks whether the error is likely temporary (the node being
+    /// unreachable, syncing or failing internally), so the request is
+    /// worth retrying later
+    pub fn is_transient(&self) -> bool {
+        match self {
+            NodeError::NodeUnreachable | NodeError::NodeSyncing | NodeError::IndexerBehind(_) => {
+                true
+            }
+            NodeError::Api(e) => e.error >= 500,
+            _ => false,
+        }
+    }
+}
+
+/// An error response of the node API, which has the form
+/// `{"error": <code>, "reason": .., "detail": ..}`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct NodeApiError {
+    /// HTTP status code of the error
+    pub error: u16,
+    /// Short description of the kind of error, e.g. `bad.request`
+    #[serde(default)]
+    pub reason: String,
+    /// Detailed description of the error, if the node provided one
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+impl NodeApiError {
+    /// Parses a node response body into a `NodeApiError`, returning `None`
+    /// if the body does not match the node's error schema
+    pub fn from_body(body: &str) -> Option<NodeApiError> {
+        let json: serde_json::Value = serde_json::from_str(body).ok()?;
+        if !json.get("error").is_some_and(|e| e.is_number()) {
+            return None;
+        }
+        serde_json::from_value(json).ok()
+    }
+
+    /// The most specific description of the error, i.e. the detail if
+    /// present and otherwise the reason
+    pub fn message(&self) -> &str {
+        self.detail.as_deref().unwrap_or(&self.reason)
+    }
+}
+
+impl std::fmt::Display for NodeApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.reason, self.error)?;
+        if let Some(detail) = &self.detail {
+            write!(f, ": {detail}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Consumes each of the `boxes` into a new list until the `total` is
+/// reached, failing if the boxes hold an insufficient number of nanoErgs
+pub(crate) fn select_until_total(total: NanoErg, boxes: &[ErgoBox]) -> Result<Vec<ErgoBox>> {
+    let mut count = 0;
+    let mut selected = vec![];
+    for b in boxes {
+        if count >= total {
+            break;
+        }
+        count += b.value.as_u64();
+        selected.push(b.clone());
+    }
+    if count < total {
+        return Err(NodeError::InsufficientErgsBalance());
+    }
+    Ok(selected)
+}
+
+/// Parses a hex-encoded serialized `ErgoTree` as returned by the node
+fn parse_tree_hex(tree_hex: &str) -> Result<ErgoTree> {
+    base16::decode(tree_hex)
+        .ok()
+        .and_then(|bytes| ErgoTree::sigma_parse_bytes(&bytes).ok())
+        .ok_or_else(|| NodeError::FailedParsingNodeResponse(tree_hex.to_string()))
+}
+
+/// The Ergo network which a node is part of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    /// The default API port of a node on this network
+    pub fn default_port(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "9053",
+            Network::Testnet => "9052",
+        }
+    }
+
+    /// The address prefix used on this network
+    pub fn prefix(&self) -> NetworkPrefix {
+        match self {
+            Network::Mainnet => NetworkPrefix::Mainnet,
+            Network::Testnet => NetworkPrefix::Testnet,
+        }
+    }
+}
+
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Network::Mainnet => write!(f, "mainnet"),
+            Network::Testnet => write!(f, "testnet"),
+        }
+    }
+}
+
+impl std::str::FromStr for Network {
+    type Err = NodeError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "mainnet" => Ok(Network::Mainnet),
+            "testnet" => Ok(Network::Testnet),
+            _ => Err(NodeError::YamlError(format!(
+                "`network` must be either \"mainnet\" or \"testnet\", found \"{s}\""
+            ))),
+        }
+    }
+}
+
+impl From<NetworkPrefix> for Network {
+    fn from(prefix: NetworkPrefix) -> Self {
+        match prefix {
+            NetworkPrefix::Mainnet => Network::Mainnet,
+            NetworkPrefix::Testnet => Network::Testnet,
+        }
+    }
+}
+
+/// A node which requests to wallet endpoints (`/wallet` and `/scan`) are
+/// routed to instead of the main node of a `NodeInterface`
+#[derive(Debug, Clone)]
+pub struct WalletRoute {
+    pub api_key: String,
+    pub url: Url,
 }
 
 /// The `NodeInterface` struct which holds the relevant Ergo node data
@@ -46,41 +246,296 @@ pub enum NodeError {
 pub struct NodeInterface {
     pub api_key: String,
     pub url: Url,
+    /// Leased boxes which are skipped during box selection
+    pub box_leases: BoxLeaseRegistry,
+    /// Cached optional features enabled on the node
+    pub capabilities_cache: CapabilitiesCache,
+    /// Optional journal recording every signed and submitted transaction
+    pub tx_journal: Option<TxJournal>,
+    /// Metrics of the requests sent to the node
+    pub metrics: RequestMetrics,
+    /// Maximum size in bytes of a node response which will be read
+    pub max_response_size: usize,
+    /// Policy for choosing the change address of generated transactions
+    pub change_address_policy: ChangeAddressPolicy,
+    /// Network of the node, used to validate addresses if set
+    pub network: Option<Network>,
+    /// Policy applied when the extra indexer lags behind the node
+    pub indexer_lag_policy: IndexerLagPolicy,
+    /// Optional separate node used for wallet endpoints
+    pub wallet_route: Option<WalletRoute>,
+    /// If set, requests to endpoints which mutate the node state or spend
+    /// wallet funds fail with `NodeError::ReadOnlyViolation`
+    pub read_only: bool,
+    /// Tracks node restarts and lost connections
+    pub session: SessionMonitor,
+    /// Restricts the endpoint groups which may be called
+    pub endpoint_policy: EndpointPolicy,
+    /// Optional rate limiting and caching for use with public nodes
+    pub politeness: Option<Politeness>,
 }
 
+/// Default maximum size in bytes of a node response (64 MiB)
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Characters which are percent-encoded in path segments and query
+/// parameter values, i.e. everything except the RFC 3986 unreserved ones
+const PARAM_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Number of recent headers used to estimate the average block time
+const BLOCK_TIME_SAMPLE_SIZE: u64 = 100;
+
+/// Computes the average time between blocks from `(height, timestamp)` pairs
+fn average_block_time(samples: &[(BlockHeight, u64)]) -> Option<Duration> {
+    let first = samples.iter().min_by_key(|(h, _)| *h)?;
+    let last = samples.iter().max_by_key(|(h, _)| *h)?;
+    if last.0 == first.0 {
+        return None;
+    }
+    let millis = last.1.saturating_sub(first.1) / (last.0 - first.0);
+    Some(Duration::from_millis(millis))
+}
+
+/// Estimates the timestamp of `target_height` from `(height, timestamp)` pairs.
+/// Heights within the samples return their exact timestamp.
+fn estimate_time_of_height(
+    samples: &[(BlockHeight, u64)],
+    target_height: BlockHeight,
+) -> Option<u64> {
+    if let Some((_, timestamp)) = samples.iter().find(|(h, _)| *h == target_height) {
+        return Some(*timestamp);
+    }
+    let block_time = average_block_time(samples)?.as_millis() as u64;
+    let (last_height, last_timestamp) = *samples.iter().max_by_key(|(h, _)| *h)?;
+    if target_height >= last_height {
+        Some(last_timestamp + (target_height - last_height) * block_time)
+    } else {
+        Some(last_timestamp.saturating_sub((last_height - target_height) * block_time))
+    }
+}
+
+/// Matches the addresses derived from `chain_key` at indices up to
+/// `max_index` against `addresses`, returning `(index, address)` pairs
+fn derivation_indices(
+    chain_key: &ExtPubKey,
+    addresses: &[P2PKAddressString],
+    max_index: u32,
+) -> Vec<(u32, P2PKAddressString)> {
+    let wallet_addresses: Vec<(Address, &P2PKAddressString)> = addresses
+        .iter()
+        .filter_map(|a| {
+            AddressEncoder::unchecked_parse_address_from_str(a)
+                .ok()
+                .map(|parsed| (parsed, a))
+        })
+        .collect();
+    let mut indices = vec![];
+    for index in 0..=max_index {
+        if indices.len() == wallet_addresses.len() {
+            break;
+        }
+        let child_index = match ChildIndexNormal::normal(index) {
+            Ok(child_index) => child_index,
+            Err(_) => break,
+        };
+        let derived = Address::from(chain_key.child(child_index));
+        if let Some((_, address)) = wallet_addresses.iter().find(|(a, _)| *a == derived) {
+            indices.push((index, address.to_string()));
+        }
+    }
+    indices
+}
+
+/// Builds the base url of a node from its `scheme` (`http` or `https`),
+/// `host` and `port`. The host may be a hostname, an IPv4 address or an IPv6
+/// address (with or without brackets), optionally followed by a path.
+pub fn node_url(scheme: &str, host: &str, port: &str) -> Result<Url> {
+    if scheme != "http" && scheme != "https" {
+        return Err(NodeError::InvalidUrl(format!(
+            "Unsupported scheme `{scheme}`"
+        )));
+    }
+    let (host, path) = match host.find('/') {
+        Some(i) => host.split_at(i),
+        None => (host, ""),
+    };
+    let host = if host.contains(':') && !host.starts_with('[') {
+        format!("[{host}]")
+    } else {
+        host.to_string()
+    };
+    let port: u16 = port
+        .parse()
+        .map_err(|_| NodeError::InvalidUrl(format!("Invalid port `{port}`")))?;
+    let mut url = Url::parse(&format!("{scheme}://{host}/"))
+        .map_err(|e| NodeError::InvalidUrl(e.to_string()))?;
+    url.set_port(Some(port))
+        .map_err(|_| NodeError::InvalidUrl(format!("Invalid port `{port}`")))?;
+    url.set_path(path);
+    Ok(normalize_base_url(url))
+}
+
+/// Ensures the path of a base url ends with `/` so endpoints are joined
+/// onto it rather than replacing its last segment
+pub(crate) fn normalize_base_url(mut url: Url) -> Url {
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+    url
+}
+
+/// Joins an endpoint onto the base url of a node, keeping any path of the
+/// base url (e.g. when the node is served behind a reverse proxy)
+pub(crate) fn endpoint_url(base_url: &Url, endpoint: &str) -> Result<Url> {
+    base_url
+        .join(endpoint.trim_start_matches('/'))
+        .map_err(|e| NodeError::InvalidUrl(e.to_string()))
+}
+
+/// Percent-encodes a value so it can be used as a single path segment or
+/// query parameter value of an endpoint, e.g. `/utxo/byId/{box_id}`
+pub fn encode_param(value: &str) -> String {
+    utf8_percent_encode(value, PARAM_ENCODE_SET).to_string()
+}
+
+/// The result of validating an address via `/utils/address/{address}`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressValidation {
+    pub address: String,
+    pub is_valid: bool,
+    /// Network encoded in the address, if it could be decoded
+    #[serde(skip)]
+    pub network: Option<Network>,
+    /// Reason the address is invalid
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Decodes the network encoded in the prefix byte of an address
+pub fn address_network(address: &str) -> Option<Network> {
+    AddressEncoder::unchecked_parse_network_address_from_str(address)
+        .ok()
+        .map(|a| a.network().into())
+}
+
+/// Checks only the first character of the address. Use
+/// `NodeInterface::check_address` to fully validate an address.
 pub fn is_mainnet_address(address: &str) -> bool {
     address.starts_with('9')
 }
 
+/// Checks only the first character of the address. Use
+/// `NodeInterface::check_address` to fully validate an address.
 pub fn is_testnet_address(address: &str) -> bool {
     address.starts_with('3')
 }
 
 impl NodeInterface {
     /// Create a new `NodeInterface` using details about the Node
-    /// Sets url to `http://ip:port` using `ip` and `port`
+    /// Sets url to `http://ip:port` using `ip` (which may also be a
+    /// hostname or IPv6 address) and `port`
     pub fn new(api_key: &str, ip: &str, port: &str) -> Result<Self> {
-        let url = Url::parse(("http://".to_string() + ip + ":" + port + "/").as_str())
-            .map_err(|e| NodeError::InvalidUrl(e.to_string()))?;
-        Ok(NodeInterface {
-            api_key: api_key.to_string(),
-            url,
-        })
+        Ok(NodeInterface::from_url(
+            api_key,
+            node_url("http", ip, port)?,
+        ))
+    }
+
+    /// Create a new `NodeInterface` for a node served over TLS, e.g. behind
+    /// a reverse proxy. Sets url to `https://host:port` using `host` and `port`
+    pub fn new_https(api_key: &str, host: &str, port: &str) -> Result<Self> {
+        Ok(NodeInterface::from_url(
+            api_key,
+            node_url("https", host, port)?,
+        ))
     }
 
     pub fn from_url(api_key: &str, url: Url) -> Self {
         NodeInterface {
             api_key: api_key.to_string(),
-            url,
+            url: normalize_base_url(url),
+            box_leases: BoxLeaseRegistry::default(),
+            capabilities_cache: CapabilitiesCache::default(),
+            tx_journal: None,
+            metrics: RequestMetrics::default(),
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            change_address_policy: ChangeAddressPolicy::default(),
+            network: None,
+            indexer_lag_policy: IndexerLagPolicy::default(),
+            wallet_route: None,
+            read_only: false,
+            session: SessionMonitor::default(),
+            endpoint_policy: EndpointPolicy::default(),
+            politeness: None,
         }
     }
 
     pub fn from_url_str(api_key: &str, url: &str) -> Result<Self> {
         let url = Url::parse(url).map_err(|e| NodeError::InvalidUrl(e.to_string()))?;
-        Ok(NodeInterface {
+        Ok(NodeInterface::from_url(api_key, url))
+    }
+
+    /// Set the maximum size in bytes of a node response which will be read.
+    /// Larger responses fail with `NodeError::ResponseTooLarge`.
+    pub fn with_max_response_size(mut self, max_response_size: usize) -> Self {
+        self.max_response_size = max_response_size;
+        self
+    }
+
+    /// Returns a clone of the `NodeInterface` which uses the provided api
+    /// key for all requests, allowing a different key for specific calls.
+    /// Caches, metrics and leases stay shared with the original.
+    pub fn with_api_key(&self, api_key: &str) -> Self {
+        let mut node = self.clone();
+        node.api_key = api_key.to_string();
+        node
+    }
+
+    /// Route all wallet endpoints (`/wallet` and `/scan`) to a separate node
+    /// using its own api key. Useful for sending read requests to a public
+    /// node while keeping the wallet on a private authenticated node.
+    pub fn with_wallet_node(mut self, api_key: &str, url: &str) -> Result<Self> {
+        let url = Url::parse(url).map_err(|e| NodeError::InvalidUrl(e.to_string()))?;
+        self.wallet_route = Some(WalletRoute {
             api_key: api_key.to_string(),
-            url,
-        })
+            url: normalize_base_url(url),
+        });
+        Ok(self)
+    }
+
+    /// Put the `NodeInterface` into read-only mode, blocking all endpoints
+    /// which mutate the node state or spend wallet funds (e.g. wallet sends,
+    /// scan registration, shutdown). Intended for monitoring services.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Set the network of the node, enabling validation of the network
+    /// of addresses provided to the `NodeInterface`
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Checks that the address is valid and belongs to the network of the
+    /// node. Always succeeds for valid addresses if no network is set.
+    pub fn check_address_network(&self, address: &str) -> Result<()> {
+        match self.network {
+            Some(network) => AddressEncoder::new(network.prefix())
+                .parse_address_from_str(address)
+                .map(|_| ())
+                .map_err(|_| NodeError::WrongNetworkAddress(address.to_string(), network)),
+            None => AddressEncoder::unchecked_parse_address_from_str(address)
+                .map(|_| ())
+                .map_err(|e| NodeError::Other(e.to_string())),
+        }
     }
 
     /// Get all addresses from the node wallet
@@ -89,11 +544,7 @@ impl NodeInterface {
         let res = self.send_get_req(endpoint)?;
 
         let mut addresses: Vec<String> = vec![];
-        for segment in res
-            .text()
-            .expect("Failed to get addresses from wallet.")
-            .split('\"')
-        {
+        for segment in self.read_response_text(res)?.split('\"') {
             let seg = segment.trim();
             if is_mainnet_address(seg) || is_testnet_address(seg) {
                 addresses.push(seg.to_string());
@@ -105,6 +556,43 @@ impl NodeInterface {
         Ok(addresses)
     }
 
+    /// Returns the EIP-3 derivation index of every wallet address as
+    /// `(index, address)` pairs. The addresses at `m/44'/429'/0'/0/index` for
+    /// indices up to `max_index` are derived locally from `chain_key`, the
+    /// extended public key at `m/44'/429'/0'/0`, and matched against the
+    /// wallet addresses, thus the node wallet is never modified.
+    pub fn wallet_address_derivation_indices(
+        &self,
+        chain_key: &ExtPubKey,
+        max_index: u32,
+    ) -> Result<Vec<(u32, P2PKAddressString)>> {
+        let wallet_addresses = self.wallet_addresses()?;
+        Ok(derivation_indices(chain_key, &wallet_addresses, max_index))
+    }
+
+    /// Derive the key at the given derivation path and return its address
+    pub fn wallet_derive_key(&self, derivation_path: &str) -> Result<P2PKAddressString> {
+        let endpoint = "/wallet/deriveKey";
+        let body = json!({
+            "derivationPath": derivation_path,
+        });
+        let res_json = self.use_json_endpoint_and_check_errors(endpoint, &body.to_string())?;
+        string_field(&res_json, "address")
+    }
+
+    /// Derive the next key of the wallet and return its derivation path and address
+    pub fn wallet_derive_next_key(&self) -> Result<(String, P2PKAddressString)> {
+        let endpoint = "/wallet/deriveNextKey";
+        let res_json = self.parse_response_to_json(self.send_get_req(endpoint))?;
+        match (
+            res_json["derivationPath"].as_str(),
+            res_json["address"].as_str(),
+        ) {
+            (Some(path), Some(address)) => Ok((path.to_string(), address.to_string())),
+            _ => Err(NodeError::FailedParsingNodeResponse(res_json.to_string())),
+        }
+    }
+
     /// A CLI interactive interface for prompting a user to select an address
     pub fn select_wallet_address(&self) -> Result<P2PKAddressString> {
         let address_list = self.wallet_addresses()?;
@@ -150,13 +638,45 @@ impl NodeInterface {
         Ok(box_list)
     }
 
+    /// Get all addresses from the node wallet together with their ErgoTrees
+    pub(crate) fn wallet_address_trees(&self) -> Result<Vec<(P2PKAddressString, ErgoTree)>> {
+        let mut address_trees = vec![];
+        for address in self.wallet_addresses()? {
+            let tree = AddressEncoder::unchecked_parse_address_from_str(&address)
+                .map_err(|e| NodeError::Other(e.to_string()))?
+                .script()
+                .map_err(|e| NodeError::Other(e.to_string()))?;
+            address_trees.push((address, tree));
+        }
+        Ok(address_trees)
+    }
+
+    /// Acquires unspent boxes from the node wallet grouped by the wallet
+    /// address which they belong to. A box is assigned to an address when
+    /// its ErgoTree matches the script of said address.
+    pub fn wallet_unspent_boxes_by_address(
+        &self,
+    ) -> Result<HashMap<P2PKAddressString, Vec<ErgoBox>>> {
+        let address_trees = self.wallet_address_trees()?;
+
+        let mut grouped: HashMap<P2PKAddressString, Vec<ErgoBox>> = address_trees
+            .iter()
+            .map(|(address, _)| (address.clone(), vec![]))
+            .collect();
+        for b in self.unspent_boxes()? {
+            if let Some((address, _)) = address_trees.iter().find(|(_, t)| *t == b.ergo_tree) {
+                if let Some(boxes) = grouped.get_mut(address) {
+                    boxes.push(b);
+                }
+            }
+        }
+        Ok(grouped)
+    }
+
     /// Returns unspent boxes from the node wallet ordered from highest to
     /// lowest nanoErgs value.
     pub fn unspent_boxes_sorted(&self) -> Result<Vec<ErgoBox>> {
-        let mut boxes = self.unspent_boxes()?;
-        boxes.sort_by(|a, b| b.value.as_u64().partial_cmp(a.value.as_u64()).unwrap());
-
-        Ok(boxes)
+        Ok(LargestFirst.order_boxes(self.unspent_boxes()?))
     }
 
     /// Returns a sorted list of unspent boxes which cover at least the
@@ -164,7 +684,7 @@ impl NodeInterface {
     /// Note: This box selection strategy simply uses the largest
     /// value holding boxes from the user's wallet first.
     pub fn unspent_boxes_with_min_total(&self, total: NanoErg) -> Result<Vec<ErgoBox>> {
-        self.consume_boxes_until_total(total, &self.unspent_boxes_sorted()?)
+        self.unspent_boxes_with_selector(total, &LargestFirst)
     }
 
     /// Returns a list of unspent boxes which cover at least the
@@ -172,27 +692,19 @@ impl NodeInterface {
     /// Note: This box selection strategy simply uses the oldest unspent
     /// boxes from the user's full node wallet first.
     pub fn unspent_boxes_with_min_total_by_age(&self, total: NanoErg) -> Result<Vec<ErgoBox>> {
-        self.consume_boxes_until_total(total, &self.unspent_boxes()?)
+        self.unspent_boxes_with_selector(total, &OldestFirst)
     }
 
     /// Given a `Vec<ErgoBox>`, consume each ErgoBox into a new list until
-    /// the `total` is reached. If there are an insufficient number of
-    /// nanoErgs in the provided `boxes` then it returns an error.
-    fn consume_boxes_until_total(&self, total: NanoErg, boxes: &[ErgoBox]) -> Result<Vec<ErgoBox>> {
-        let mut count = 0;
-        let mut filtered_boxes = vec![];
-        for b in boxes {
-            if count >= total {
-                break;
-            } else {
-                count += b.value.as_u64();
-                filtered_boxes.push(b.clone());
-            }
-        }
-        if count < total {
-            return Err(NodeError::InsufficientErgsBalance());
-        }
-        Ok(filtered_boxes)
+    /// the `total` is reached. Boxes leased in `box_leases` are skipped.
+    /// If there are an insufficient number of nanoErgs in the provided
+    /// `boxes` then it returns an error.
+    pub(crate) fn consume_boxes_until_total(
+        &self,
+        total: NanoErg,
+        boxes: &[ErgoBox],
+    ) -> Result<Vec<ErgoBox>> {
+        select_until_total(total, &self.box_leases.filter_unleased(boxes))
     }
 
     /// Acquires the unspent box with the highest value of Ergs inside
@@ -217,6 +729,34 @@ impl NodeInterface {
         Err(NodeError::NoBoxesFound)
     }
 
+    /// Acquires the unspent box from the wallet which holds the largest
+    /// amount of the given token. Ties are broken by the value of Ergs.
+    pub fn largest_box_with_token(&self, token_id: &TokenID) -> Result<ErgoBox> {
+        let token_amount = |b: &ErgoBox| -> u64 {
+            b.tokens
+                .iter()
+                .flat_map(|tokens| tokens.iter())
+                .filter(|t| String::from(t.token_id) == *token_id)
+                .map(|t| u64::from(t.amount))
+                .sum()
+        };
+        self.unspent_boxes()?
+            .into_iter()
+            .filter(|b| token_amount(b) > 0)
+            .max_by_key(|b| (token_amount(b), *b.value.as_u64()))
+            .ok_or(NodeError::NoBoxesFound)
+    }
+
+    /// Acquires the unspent box with the highest value of Ergs inside
+    /// from the wallet which holds no tokens, e.g. for paying fees
+    pub fn largest_pure_erg_box(&self) -> Result<ErgoBox> {
+        self.unspent_boxes()?
+            .into_iter()
+            .filter(|b| b.tokens.is_none())
+            .max_by_key(|b| *b.value.as_u64())
+            .ok_or(NodeError::NoBoxesFound)
+    }
+
     /// Acquires the unspent box with the highest value of Ergs inside
     /// from the wallet and serializes it
     pub fn serialized_highest_value_unspent_box(&self) -> Result<String> {
@@ -235,31 +775,89 @@ impl NodeInterface {
         Ok(serialized_boxes)
     }
 
-    /// Given a P2S Ergo address, extract the hex-encoded serialized ErgoTree (script)
-    pub fn p2s_to_tree(&self, address: &P2SAddressString) -> Result<String> {
-        let endpoint = "/script/addressToTree/".to_string() + address;
+    /// Acquires unspent boxes which cover `total` amount of nanoErgs
+    /// from the wallet and serializes them locally into raw bytes, avoiding
+    /// the per-box node requests and base16 encoding
+    pub fn unspent_boxes_bytes_with_min_total(&self, total: NanoErg) -> Result<Vec<Vec<u8>>> {
+        self.unspent_boxes_with_min_total(total)?
+            .iter()
+            .map(|b| {
+                b.sigma_serialize_bytes()
+                    .map_err(|e| NodeError::Other(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// Acquires unspent boxes which cover `total` amount of nanoErgs from
+    /// the wallet and serializes them one after another into the provided
+    /// buffer. Returns the byte range of every serialized box within `buf`.
+    pub fn write_unspent_boxes_with_min_total(
+        &self,
+        total: NanoErg,
+        buf: &mut Vec<u8>,
+    ) -> Result<Vec<Range<usize>>> {
+        let mut ranges = vec![];
+        for b in self.unspent_boxes_with_min_total(total)? {
+            let start = buf.len();
+            b.sigma_serialize(&mut SigmaByteWriter::new(buf, None))
+                .map_err(|e| NodeError::Other(e.to_string()))?;
+            ranges.push(start..buf.len());
+        }
+        Ok(ranges)
+    }
+
+    /// Given a P2S Ergo address, extract the `ErgoTree` (script) protecting its boxes
+    pub fn p2s_to_tree(&self, address: &P2SAddressString) -> Result<ErgoTree> {
+        let endpoint = "/script/addressToTree/".to_string() + &encode_param(address);
         let res = self.send_get_req(&endpoint);
         let res_json = self.parse_response_to_json(res)?;
 
-        Ok(res_json["tree"].to_string())
+        parse_tree_hex(&string_field(&res_json, "tree")?)
+    }
+
+    /// Given a hex-encoded serialized `ErgoTree`, convert it to the address
+    /// protected by it (the inverse of `p2s_to_tree`)
+    pub fn tree_to_address(&self, ergo_tree_hex: &str) -> Result<P2SAddressString> {
+        let endpoint = "/utils/ergoTreeToAddress/".to_string() + &encode_param(ergo_tree_hex);
+        let res = self.send_get_req(&endpoint);
+        let res_json = self.parse_response_to_json(res)?;
+
+        string_field(&res_json, "address")
+    }
+
+    /// Validate an address using the node, including the network it is
+    /// encoded for
+    pub fn check_address(&self, address: &str) -> Result<AddressValidation> {
+        let endpoint = "/utils/address/".to_string() + &encode_param(address);
+        let mut validation: AddressValidation = self.get_typed(&endpoint)?;
+        if validation.is_valid {
+            validation.network = address_network(address);
+        }
+        Ok(validation)
+    }
+
+    /// Get the hex-encoded Blake2b256 hash of the message, as computed by
+    /// the node
+    pub fn blake2b_hash(&self, message: &str) -> Result<String> {
+        self.post_typed("/utils/hash/blake2b", &json!(message))
     }
 
     /// Given a P2S Ergo address, convert it to a hex-encoded Sigma byte array constant
     pub fn p2s_to_bytes(&self, address: &P2SAddressString) -> Result<String> {
-        let endpoint = "/script/addressToBytes/".to_string() + address;
+        let endpoint = "/script/addressToBytes/".to_string() + &encode_param(address);
         let res = self.send_get_req(&endpoint);
         let res_json = self.parse_response_to_json(res)?;
 
-        Ok(res_json["bytes"].to_string())
+        string_field(&res_json, "bytes")
     }
 
     /// Given an Ergo P2PK Address, convert it to a raw hex-encoded EC point
     pub fn p2pk_to_raw(&self, address: &P2PKAddressString) -> Result<String> {
-        let endpoint = "/utils/addressToRaw/".to_string() + address;
+        let endpoint = "/utils/addressToRaw/".to_string() + &encode_param(address);
         let res = self.send_get_req(&endpoint);
         let res_json = self.parse_response_to_json(res)?;
 
-        Ok(res_json["raw"].to_string())
+        string_field(&res_json, "raw")
     }
 
     /// Given an Ergo P2PK Address, convert it to a raw hex-encoded EC point
@@ -272,17 +870,27 @@ impl NodeInterface {
 
     /// Given a raw hex-encoded EC point, convert it to a P2PK address
     pub fn raw_to_p2pk(&self, raw: &str) -> Result<P2PKAddressString> {
-        let endpoint = "/utils/rawToAddress/".to_string() + raw;
+        let endpoint = "/utils/rawToAddress/".to_string() + &encode_param(raw);
         let res = self.send_get_req(&endpoint);
         let res_json = self.parse_response_to_json(res)?;
 
-        Ok(res_json["address"].to_string())
+        string_field(&res_json, "address")
+    }
+
+    /// Given a raw hex-encoded EC point, convert it to a P2PK `Address`
+    pub fn raw_to_address(&self, raw: &str) -> Result<Address> {
+        let address = self.raw_to_p2pk(raw)?;
+        AddressEncoder::unchecked_parse_address_from_str(&address)
+            .map_err(|_| NodeError::FailedParsingNodeResponse(address))
     }
 
     /// Given a raw hex-encoded EC point from a register (thus with type encoded characters in front),
     /// convert it to a P2PK address
     pub fn raw_from_register_to_p2pk(&self, typed_raw: &str) -> Result<P2PKAddressString> {
-        self.raw_to_p2pk(&typed_raw[2..])
+        let raw = typed_raw
+            .get(2..)
+            .ok_or_else(|| NodeError::Other(format!("Invalid register value: {typed_raw}")))?;
+        self.raw_to_p2pk(raw)
     }
 
     /// Given a `Vec<ErgoBox>` return the given boxes (which must be part of the UTXO-set) as
@@ -305,25 +913,49 @@ impl NodeInterface {
     /// Given a box id return the given box (which must be part of the
     /// UTXO-set) as a serialized string in Base16 encoding
     pub fn serialized_box_from_id(&self, box_id: &String) -> Result<String> {
-        let endpoint = "/utxo/byIdBinary/".to_string() + box_id;
+        let endpoint = "/utxo/byIdBinary/".to_string() + &encode_param(box_id);
         let res = self.send_get_req(&endpoint);
         let res_json = self.parse_response_to_json(res)?;
 
-        Ok(res_json["bytes"].to_string())
+        string_field(&res_json, "bytes")
     }
 
     /// Given a box id return the given box (which must be part of the
     /// UTXO-set) as a serialized string in Base16 encoding
     pub fn box_from_id(&self, box_id: &String) -> Result<ErgoBox> {
-        let endpoint = "/utxo/byId/".to_string() + box_id;
+        let endpoint = "/utxo/byId/".to_string() + &encode_param(box_id);
         let res = self.send_get_req(&endpoint);
         let res_json = self.parse_response_to_json(res)?;
 
-        if let Ok(ergo_box) = from_str(&res_json.to_string()) {
-            Ok(ergo_box)
-        } else {
-            Err(NodeError::FailedParsingBox(res_json.pretty(2)))
-        }
+        serde_json::from_value(res_json.clone())
+            .map_err(|_| NodeError::FailedParsingBox(format!("{res_json:#}")))
+    }
+
+    /// Given a box id return the given box, which must be part of the
+    /// UTXO-set or an unconfirmed output in the mempool
+    pub fn box_from_id_with_mempool(&self, box_id: &String) -> Result<ErgoBox> {
+        let endpoint = "/utxo/withPool/byId/".to_string() + &encode_param(box_id);
+        let res = self.send_get_req(&endpoint);
+        let res_json = self.parse_response_to_json(res)?;
+
+        serde_json::from_value(res_json.clone())
+            .map_err(|_| NodeError::FailedParsingBox(format!("{res_json:#}")))
+    }
+
+    /// Given a box id return the given box (which must be part of the
+    /// UTXO-set or an unconfirmed output in the mempool) as a serialized
+    /// string in Base16 encoding
+    pub fn serialized_box_from_id_with_mempool(&self, box_id: &String) -> Result<String> {
+        let endpoint = "/utxo/withPool/byIdBinary/".to_string() + &encode_param(box_id);
+        let res = self.send_get_req(&endpoint);
+        let res_json = self.parse_response_to_json(res)?;
+
+        string_field(&res_json, "bytes")
+    }
+
+    /// Get the boxes created in the genesis state of the blockchain
+    pub fn genesis_boxes(&self) -> Result<Vec<ErgoBox>> {
+        self.get_typed("/utxo/genesis")
     }
 
     /// Get the current nanoErgs balance held in the Ergo Node wallet
@@ -348,6 +980,8 @@ impl NodeInterface {
         let endpoint = "/info";
         let res = self.send_get_req(endpoint);
         let res_json = self.parse_response_to_json(res)?;
+        // Errors are ignored so the height is returned regardless
+        let _ = self.observe_node_info(&res_json);
 
         let height_json = res_json["fullHeight"].clone();
 
@@ -355,45 +989,106 @@ impl NodeInterface {
             Err(NodeError::NodeSyncing)
         } else {
             height_json
-                .to_string()
-                .parse()
-                .map_err(|_| NodeError::FailedParsingNodeResponse(res_json.to_string()))
+                .as_u64()
+                .ok_or_else(|| NodeError::FailedParsingNodeResponse(res_json.to_string()))
         }
     }
 
+    /// Get the average time between blocks over the most recent headers
+    pub fn average_block_time(&self) -> Result<Duration> {
+        let samples = self.recent_header_timestamps()?;
+        average_block_time(&samples)
+            .ok_or_else(|| NodeError::Other("Not enough headers to estimate block time.".into()))
+    }
+
+    /// Estimate the timestamp (in milliseconds) at which the block at
+    /// `target_height` is/was created, using the timestamps of the most
+    /// recent headers and the average block time.
+    pub fn estimate_time_of_height(&self, target_height: BlockHeight) -> Result<u64> {
+        let samples = self.recent_header_timestamps()?;
+        estimate_time_of_height(&samples, target_height)
+            .ok_or_else(|| NodeError::Other("Not enough headers to estimate block time.".into()))
+    }
+
+    /// Get the `(height, timestamp)` pairs of the most recent headers
+    fn recent_header_timestamps(&self) -> Result<Vec<(BlockHeight, u64)>> {
+        Ok(self
+            .last_headers(BLOCK_TIME_SAMPLE_SIZE)?
+            .iter()
+            .map(|h| (h.height as BlockHeight, h.timestamp))
+            .collect())
+    }
+
     /// Get wallet status /wallet/status
     pub fn wallet_status(&self) -> Result<WalletStatus> {
         let endpoint = "/wallet/status";
         let res = self.send_get_req(endpoint);
         let res_json = self.parse_response_to_json(res)?;
 
-        if let Ok(wallet_status) = from_str(&res_json.to_string()) {
-            Ok(wallet_status)
-        } else {
-            Err(NodeError::FailedParsingWalletStatus(res_json.pretty(2)))
-        }
+        serde_json::from_value(res_json.clone())
+            .map_err(|_| NodeError::FailedParsingWalletStatus(format!("{res_json:#}")))
     }
 
     /// Unlock wallet
     pub fn wallet_unlock(&self, password: &str) -> Result<bool> {
         let endpoint = "/wallet/unlock";
-        let body = object! {
-            pass: password,
+        let body = json!({
+            "pass": password,
+        });
+
+        let res = self.send_post_req(endpoint, body.to_string())?;
+
+        self.parse_response_to_json(Ok(res))?;
+        Ok(true)
+    }
+
+    /// Lock wallet
+    pub fn wallet_lock(&self) -> Result<bool> {
+        let endpoint = "/wallet/lock";
+        let res = self.send_get_req(endpoint)?;
+
+        self.parse_response_to_json(Ok(res))?;
+        Ok(true)
+    }
+
+    /// Trigger a rescan of the wallet, starting at `from_height` if provided
+    /// and otherwise at the genesis block. Needed after registering new scans
+    /// or restoring a wallet so past boxes are picked up.
+    pub fn wallet_rescan(&self, from_height: Option<BlockHeight>) -> Result<bool> {
+        let endpoint = "/wallet/rescan";
+        let body = match from_height {
+            Some(height) => json!({ "fromHeight": height }),
+            None => json!({}),
         };
 
         let res = self.send_post_req(endpoint, body.to_string())?;
 
-        if res.status().is_success() {
-            Ok(true)
-        } else {
-            let json = self.parse_response_to_json(Ok(res))?;
-            Err(NodeError::BadRequest(json["error"].to_string()))
-        }
+        self.parse_response_to_json(Ok(res))?;
+        Ok(true)
+    }
+
+    /// Check whether the provided mnemonic (and optional mnemonic password)
+    /// corresponds to the node wallet.
+    /// Note: The node API does not provide an endpoint for changing the
+    /// wallet password, thus rotating credentials requires restoring the
+    /// wallet from its mnemonic with a new password.
+    pub fn wallet_check_mnemonic(&self, mnemonic: &str, mnemonic_pass: &str) -> Result<bool> {
+        let endpoint = "/wallet/check";
+        let body = json!({
+            "mnemonic": mnemonic,
+            "mnemonicPass": mnemonic_pass,
+        });
+
+        let res = self.send_post_req(endpoint, body.to_string());
+        let json = self.parse_response_to_json(res)?;
+        json["matched"]
+            .as_bool()
+            .ok_or_else(|| NodeError::FailedParsingNodeResponse(json.to_string()))
     }
 }
 
 #[serde_as]
-#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct WalletStatus {
     #[serde(rename = "isInitialized")]
     pub initialized: bool,
@@ -412,6 +1107,91 @@ pub struct WalletStatus {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_node_url() {
+        let url = node_url("http", "127.0.0.1", "9053").unwrap();
+        assert_eq!(url.as_str(), "http://127.0.0.1:9053/");
+        let url = node_url("http", "::1", "9053").unwrap();
+        assert_eq!(url.as_str(), "http://[::1]:9053/");
+        let url = node_url("https", "node.example.com/ergo", "8443").unwrap();
+        assert_eq!(url.as_str(), "https://node.example.com:8443/ergo/");
+        assert_eq!(
+            endpoint_url(&url, "/info").unwrap().as_str(),
+            "https://node.example.com:8443/ergo/info"
+        );
+        assert!(node_url("http", "127.0.0.1", "port").is_err());
+        assert!(node_url("ftp", "127.0.0.1", "9053").is_err());
+    }
+
+    #[test]
+    fn test_encode_param() {
+        assert_eq!(
+            encode_param("9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA"),
+            "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA"
+        );
+        assert_eq!(encode_param("a/b?c=d&e f"), "a%2Fb%3Fc%3Dd%26e%20f");
+        let url = node_url("http", "127.0.0.1", "9053").unwrap();
+        let endpoint = "/utxo/byId/".to_string() + &encode_param("../info");
+        assert_eq!(
+            endpoint_url(&url, &endpoint).unwrap().as_str(),
+            "http://127.0.0.1:9053/utxo/byId/..%2Finfo"
+        );
+    }
+
+    #[test]
+    fn test_parse_tree_hex() {
+        let tree_hex = "0008cd0327e65711a59378c59359c3e1d0f7abe906479eccb76094e50fe79d743ccc15e6";
+        let tree = parse_tree_hex(tree_hex).unwrap();
+        assert_eq!(tree.to_base16_bytes().unwrap(), tree_hex);
+        assert!(parse_tree_hex("\"0008cd\"").is_err());
+    }
+
+    #[test]
+    fn test_derivation_indices() {
+        use ergo_lib::wallet::derivation_path::{ChildIndexHardened, DerivationPath};
+        use ergo_lib::wallet::ext_secret_key::ExtSecretKey;
+        use ergo_lib::wallet::mnemonic::Mnemonic;
+
+        let seed = Mnemonic::to_seed("change me do not use me change me do not use me", "");
+        let account = DerivationPath::new(ChildIndexHardened::from_31_bit(0).unwrap(), vec![]);
+        let chain_key = ExtSecretKey::derive_master(seed)
+            .unwrap()
+            .derive(account)
+            .unwrap()
+            .public_key()
+            .unwrap();
+        let encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
+        let address_at = |i: u32| {
+            let child = chain_key.child(ChildIndexNormal::normal(i).unwrap());
+            encoder.address_to_str(&Address::from(child))
+        };
+        let addresses = vec![address_at(0), address_at(3)];
+        assert_eq!(
+            derivation_indices(&chain_key, &addresses, 10),
+            vec![(0, address_at(0)), (3, address_at(3))]
+        );
+        assert_eq!(
+            derivation_indices(&chain_key, &addresses, 2),
+            vec![(0, address_at(0))]
+        );
+    }
+
+    #[test]
+    fn test_address_network() {
+        assert_eq!(
+            address_network("9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA"),
+            Some(Network::Mainnet)
+        );
+        assert_eq!(
+            address_network("3WvsT2Gm4EpsM9Pg18PdY6XyhNNMqXDsvJTbbf6ihLvAmSb7u5RN"),
+            Some(Network::Testnet)
+        );
+        assert_eq!(
+            address_network("9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vB"),
+            None
+        );
+    }
+
     #[test]
     fn test_parsing_wallet_status_unlocked() {
         let node_response_json_str = r#"{
@@ -425,6 +1205,16 @@ mod tests {
         assert_eq!(t.height, 251965);
     }
 
+    #[test]
+    fn test_estimate_time_of_height() {
+        let samples = vec![(100, 1_000_000), (101, 1_120_000), (102, 1_240_000)];
+        assert_eq!(average_block_time(&samples), Some(Duration::from_secs(120)));
+        assert_eq!(estimate_time_of_height(&samples, 101), Some(1_120_000));
+        assert_eq!(estimate_time_of_height(&samples, 105), Some(1_600_000));
+        assert_eq!(estimate_time_of_height(&samples, 98), Some(760_000));
+        assert_eq!(estimate_time_of_height(&samples[..1], 105), None);
+    }
+
     #[test]
     fn test_parsing_wallet_status_locked() {
         let node_response_json_str = r#"{
@@ -0,0 +1,249 @@
+//! The `NodeInterfaceAsync` struct is defined here which mirrors the core
+//! methods of `NodeInterface` using a non-blocking `reqwest::Client`, so it
+//! can be used from within async runtimes such as tokio.
+use crate::endpoint_policy::EndpointPolicy;
+use crate::node_interface::{
+    encode_param, endpoint_url, node_url, normalize_base_url, NodeError, Result, WalletStatus,
+    DEFAULT_MAX_RESPONSE_SIZE,
+};
+use crate::requests::{check_node_error, is_mutating_endpoint};
+use crate::{BlockHeight, NanoErg, P2PKAddressString};
+use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+use ergo_lib::chain::transaction::{Transaction, TxId};
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
+use reqwest::{Client, RequestBuilder, Url};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+/// An async counterpart of `NodeInterface` for interacting with an Ergo Node
+#[derive(Debug, Clone)]
+pub struct NodeInterfaceAsync {
+    pub api_key: String,
+    pub url: Url,
+    /// Maximum size in bytes of a node response which will be read
+    pub max_response_size: usize,
+    /// If set, requests to endpoints which mutate the node state or spend
+    /// wallet funds fail with `NodeError::ReadOnlyViolation`
+    pub read_only: bool,
+    /// Restricts the endpoint groups which may be called
+    pub endpoint_policy: EndpointPolicy,
+    client: Client,
+}
+
+impl NodeInterfaceAsync {
+    /// Create a new `NodeInterfaceAsync` using details about the Node
+    /// Sets url to `http://ip:port` using `ip` and `port`
+    pub fn new(api_key: &str, ip: &str, port: &str) -> Result<Self> {
+        Ok(NodeInterfaceAsync::from_url(
+            api_key,
+            node_url("http", ip, port)?,
+        ))
+    }
+
+    /// Create a new `NodeInterfaceAsync` for a node served over TLS.
+    /// Sets url to `https://host:port` using `host` and `port`
+    pub fn new_https(api_key: &str, host: &str, port: &str) -> Result<Self> {
+        Ok(NodeInterfaceAsync::from_url(
+            api_key,
+            node_url("https", host, port)?,
+        ))
+    }
+
+    pub fn from_url(api_key: &str, url: Url) -> Self {
+        NodeInterfaceAsync {
+            api_key: api_key.to_string(),
+            url: normalize_base_url(url),
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            read_only: false,
+            endpoint_policy: EndpointPolicy::default(),
+            client: Client::new(),
+        }
+    }
+
+    pub fn from_url_str(api_key: &str, url: &str) -> Result<Self> {
+        let url = Url::parse(url).map_err(|e| NodeError::InvalidUrl(e.to_string()))?;
+        Ok(NodeInterfaceAsync::from_url(api_key, url))
+    }
+
+    /// Put the `NodeInterfaceAsync` into read-only mode, blocking all
+    /// endpoints which mutate the node state or spend wallet funds
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Restrict the endpoint groups which this `NodeInterfaceAsync` may call
+    pub fn with_endpoint_policy(mut self, policy: EndpointPolicy) -> Self {
+        self.endpoint_policy = policy;
+        self
+    }
+
+    /// Builds the url of an endpoint after checking that it may be called
+    /// under the endpoint policy and read-only mode
+    fn route(&self, endpoint: &str) -> Result<Url> {
+        self.endpoint_policy.check(endpoint)?;
+        if self.read_only && is_mutating_endpoint(endpoint) {
+            return Err(NodeError::ReadOnlyViolation(endpoint.to_string()));
+        }
+        endpoint_url(&self.url, endpoint)
+    }
+
+    /// Sets required headers for a request
+    fn set_req_headers(&self, rb: RequestBuilder) -> RequestBuilder {
+        let api_key = HeaderValue::from_str(&self.api_key)
+            .unwrap_or_else(|_| HeaderValue::from_static("None"));
+        rb.header("accept", "application/json")
+            .header("api_key", api_key)
+            .header(CONTENT_TYPE, "application/json")
+    }
+
+    /// Sends a GET request to the Ergo node and returns the JSON response,
+    /// with node error responses returned as `NodeError::Api`
+    pub async fn get_json(&self, endpoint: &str) -> Result<Value> {
+        let url = self.route(endpoint)?;
+        self.send(self.client.get(url)).await
+    }
+
+    /// Sends a POST request with a JSON body to the Ergo node and returns the
+    /// JSON response, with node error responses returned as `NodeError::Api`
+    pub async fn post_json(&self, endpoint: &str, body: &Value) -> Result<Value> {
+        let url = self.route(endpoint)?;
+        self.send(self.client.post(url).body(body.to_string()))
+            .await
+    }
+
+    async fn send(&self, rb: RequestBuilder) -> Result<Value> {
+        let mut resp = self
+            .set_req_headers(rb)
+            .send()
+            .await
+            .map_err(|_| NodeError::NodeUnreachable)?;
+        if resp
+            .content_length()
+            .is_some_and(|len| len > self.max_response_size as u64)
+        {
+            return Err(NodeError::ResponseTooLarge(self.max_response_size));
+        }
+        let status = resp.status();
+        // Read chunk by chunk so an oversized body without a (truthful)
+        // Content-Length is aborted without being buffered completely
+        let mut bytes = vec![];
+        while let Some(chunk) = resp.chunk().await.map_err(|_| {
+            NodeError::FailedParsingNodeResponse(
+                "Node Response Not Parseable into Text.".to_string(),
+            )
+        })? {
+            if bytes.len() + chunk.len() > self.max_response_size {
+                return Err(NodeError::ResponseTooLarge(self.max_response_size));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        let text = String::from_utf8_lossy(&bytes).to_string();
+        check_node_error(status, &text)?;
+        serde_json::from_str(&text).map_err(|_| NodeError::FailedParsingNodeResponse(text))
+    }
+
+    /// Get the current block height of the blockchain
+    pub async fn current_block_height(&self) -> Result<BlockHeight> {
+        let res_json = self.get_json("/info").await?;
+        res_json["fullHeight"]
+            .as_u64()
+            .ok_or(NodeError::NodeSyncing)
+    }
+
+    /// Get all addresses from the node wallet
+    pub async fn wallet_addresses(&self) -> Result<Vec<P2PKAddressString>> {
+        let addresses: Vec<P2PKAddressString> =
+            parse_value(self.get_json("/wallet/addresses").await?)?;
+        if addresses.is_empty() {
+            return Err(NodeError::NoAddressesInWallet);
+        }
+        Ok(addresses)
+    }
+
+    /// Get wallet status /wallet/status
+    pub async fn wallet_status(&self) -> Result<WalletStatus> {
+        let res_json = self.get_json("/wallet/status").await?;
+        serde_json::from_value(res_json.clone())
+            .map_err(|_| NodeError::FailedParsingWalletStatus(res_json.to_string()))
+    }
+
+    /// Get the current nanoErgs balance held in the Ergo Node wallet
+    pub async fn wallet_nano_ergs_balance(&self) -> Result<NanoErg> {
+        let res_json = self.get_json("/wallet/balances").await?;
+        res_json["balance"].as_u64().ok_or(NodeError::NodeSyncing)
+    }
+
+    /// Acquires unspent boxes from the node wallet
+    pub async fn unspent_boxes(&self) -> Result<Vec<ErgoBox>> {
+        let endpoint = "/wallet/boxes/unspent?minConfirmations=0&minInclusionHeight=0";
+        let res_json = self.get_json(endpoint).await?;
+        Ok(res_json
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|b| serde_json::from_value(b["box"].clone()).ok())
+            .collect())
+    }
+
+    /// Given a box id return the given box (which must be part of the UTXO-set)
+    pub async fn box_from_id(&self, box_id: &str) -> Result<ErgoBox> {
+        let res_json = self
+            .get_json(&format!("/utxo/byId/{}", encode_param(box_id)))
+            .await?;
+        serde_json::from_value(res_json.clone())
+            .map_err(|_| NodeError::FailedParsingBox(res_json.to_string()))
+    }
+
+    /// Sign an `UnsignedTransaction` using the node wallet, letting the node
+    /// look up the input and data input boxes in the UTXO-set
+    pub async fn sign_transaction(&self, unsigned_tx: &UnsignedTransaction) -> Result<Transaction> {
+        let body = json!({ "tx": unsigned_tx });
+        parse_value(self.post_json("/wallet/transaction/sign", &body).await?)
+    }
+
+    /// Submits a Signed `Transaction` to the Ergo Blockchain mempool.
+    pub async fn submit_transaction(&self, signed_tx: &Transaction) -> Result<TxId> {
+        let body = serde_json::to_value(signed_tx)
+            .map_err(|_| NodeError::Other("Failed Converting `Transaction` to json".to_string()))?;
+        parse_value(self.post_json("/transactions", &body).await?)
+    }
+
+    /// Sign an `UnsignedTransaction` and then submit it to the mempool.
+    pub async fn sign_and_submit_transaction(
+        &self,
+        unsigned_tx: &UnsignedTransaction,
+    ) -> Result<TxId> {
+        let signed_tx = self.sign_transaction(unsigned_tx).await?;
+        self.submit_transaction(&signed_tx).await
+    }
+}
+
+/// Deserializes a node response into the expected type
+fn parse_value<T: DeserializeOwned>(value: Value) -> Result<T> {
+    serde_json::from_value(value.clone())
+        .map_err(|_| NodeError::FailedParsingNodeResponse(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_checks() {
+        let node = NodeInterfaceAsync::from_url_str("key", "http://localhost:9053")
+            .unwrap()
+            .read_only()
+            .with_endpoint_policy(EndpointPolicy::Deny(vec!["mining".to_string()]));
+        assert!(node.route("/wallet/balances").is_ok());
+        assert!(matches!(
+            node.route("/wallet/transaction/send"),
+            Err(NodeError::ReadOnlyViolation(_))
+        ));
+        assert!(matches!(
+            node.route("/mining/candidate"),
+            Err(NodeError::EndpointNotAllowed(_))
+        ));
+    }
+}
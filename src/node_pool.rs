@@ -0,0 +1,84 @@
+//! The `NodePool` struct is defined here which groups multiple nodes,
+//! allowing the same query to be checked against every node.
+use crate::node_interface::{NodeInterface, Result};
+use ergo_lib::chain::transaction::TxId;
+use reqwest::Url;
+use std::thread;
+
+/// Whether a node has seen a given transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxSighting {
+    /// The transaction is neither in the mempool nor indexed by the node
+    NotSeen,
+    /// The transaction is in the mempool of the node
+    InMempool,
+    /// The transaction is included in a block indexed by the node
+    Confirmed,
+}
+
+/// The outcome of checking a single node for a transaction
+#[derive(Debug)]
+pub struct PropagationStatus {
+    pub url: Url,
+    pub sighting: Result<TxSighting>,
+}
+
+/// A group of nodes which queries can be checked against
+#[derive(Debug, Clone, Default)]
+pub struct NodePool {
+    pub nodes: Vec<NodeInterface>,
+}
+
+impl NodePool {
+    /// Create a new `NodePool` from the provided nodes
+    pub fn new(nodes: Vec<NodeInterface>) -> NodePool {
+        NodePool { nodes }
+    }
+
+    /// Add a node to the pool
+    pub fn with_node(mut self, node: NodeInterface) -> NodePool {
+        self.nodes.push(node);
+        self
+    }
+
+    /// Queries every node of the pool concurrently and reports whether it
+    /// has seen the transaction in its mempool or (if the indexer is enabled)
+    /// in a block. Useful for diagnosing propagation issues after submission.
+    pub fn confirm_propagation(&self, tx_id: &TxId) -> Vec<PropagationStatus> {
+        thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .nodes
+                .iter()
+                .map(|node| scope.spawn(move || node.tx_sighting(tx_id)))
+                .collect();
+            self.nodes
+                .iter()
+                .zip(handles)
+                .map(|(node, handle)| PropagationStatus {
+                    url: node.url.clone(),
+                    sighting: handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+                })
+                .collect()
+        })
+    }
+}
+
+impl NodeInterface {
+    /// Checks whether this node has seen the transaction in its mempool or
+    /// (if the indexer is enabled) in a block
+    pub fn tx_sighting(&self, tx_id: &TxId) -> Result<TxSighting> {
+        if self.is_in_mempool(tx_id)? {
+            return Ok(TxSighting::InMempool);
+        }
+        if self.indexer_enabled() {
+            match self.indexed_transaction_by_id(tx_id) {
+                Ok(_) => return Ok(TxSighting::Confirmed),
+                Err(e) if e.is_not_found() => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(TxSighting::NotSeen)
+    }
+}
@@ -0,0 +1,88 @@
+//! Typed access to the peers endpoints of the node, e.g. for building
+//! node operator dashboards.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use serde::Deserialize;
+
+/// The direction of a connection to a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ConnectionType {
+    Incoming,
+    Outgoing,
+}
+
+/// Information about a peer known to the node
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerInfo {
+    /// Network address of the peer in the form `host:port`
+    pub address: String,
+    /// Name the peer reported during the handshake
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Timestamp (ms) of the last message received from the peer
+    #[serde(default)]
+    pub last_message: Option<u64>,
+    /// Timestamp (ms) of the last handshake with the peer
+    #[serde(default)]
+    pub last_handshake: Option<u64>,
+    /// Direction of the connection, if the peer is connected
+    #[serde(default)]
+    pub connection_type: Option<ConnectionType>,
+}
+
+impl NodeInterface {
+    /// Get all peers known to the node
+    pub fn all_peers(&self) -> Result<Vec<PeerInfo>> {
+        self.peers("/peers/all")
+    }
+
+    /// Get the peers the node is currently connected to
+    pub fn connected_peers(&self) -> Result<Vec<PeerInfo>> {
+        self.peers("/peers/connected")
+    }
+
+    /// Get the addresses of the peers blacklisted by the node
+    pub fn blacklisted_peers(&self) -> Result<Vec<String>> {
+        let res_json = self.get_json("/peers/blacklisted")?;
+        serde_json::from_value(res_json["addresses"].clone())
+            .map_err(|_| NodeError::FailedParsingNodeResponse(res_json.to_string()))
+    }
+
+    /// Instruct the node to connect to the peer at the given `host:port`
+    /// address
+    pub fn connect_to_peer(&self, address: &str) -> Result<()> {
+        let body = serde_json::Value::String(address.to_string());
+        let res = self.send_post_req("/peers/connect", body.to_string())?;
+        if res.status().is_success() {
+            Ok(())
+        } else {
+            self.parse_response_to_json(Ok(res)).map(|_| ())
+        }
+    }
+
+    /// Acquires a list of peers from one of the peers endpoints
+    fn peers(&self, endpoint: &str) -> Result<Vec<PeerInfo>> {
+        self.get_typed(endpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_peer_info() {
+        let json = r#"[
+            {"address": "/213.239.193.208:9030", "lastMessage": 1700000000000,
+             "lastHandshake": 1699999990000, "name": "ergo-mainnet-5.0",
+             "connectionType": "Outgoing"},
+            {"address": "/159.65.11.55:9030", "lastMessage": 0,
+             "lastHandshake": 0, "name": null, "connectionType": null}
+        ]"#;
+        let peers: Vec<PeerInfo> = serde_json::from_str(json).unwrap();
+        assert_eq!(peers[0].connection_type, Some(ConnectionType::Outgoing));
+        assert_eq!(peers[0].name.as_deref(), Some("ergo-mainnet-5.0"));
+        assert_eq!(peers[1].connection_type, None);
+        assert_eq!(peers[1].name, None);
+    }
+}
@@ -0,0 +1,160 @@
+//! A "politeness" mode for using public community nodes, combining rate
+//! limiting of all requests, short-lived caching of GET responses and
+//! jittered polling intervals so applications don't get IP-banned.
+use crate::node_interface::{NodeInterface, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Settings of the politeness mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolitenessConfig {
+    /// Maximum number of requests sent to the node per second
+    pub max_requests_per_second: f64,
+    /// How long GET responses are reused before requesting them again
+    pub cache_ttl: Duration,
+    /// Fraction (0.0 to 1.0) by which polling intervals are randomly varied
+    pub poll_jitter: f64,
+}
+
+impl Default for PolitenessConfig {
+    /// Settings tuned for public community nodes
+    fn default() -> Self {
+        PolitenessConfig {
+            max_requests_per_second: 2.0,
+            cache_ttl: Duration::from_secs(5),
+            poll_jitter: 0.2,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PolitenessState {
+    next_slot: Instant,
+    cache: HashMap<String, (Instant, serde_json::Value)>,
+    rng_state: u64,
+}
+
+/// The politeness mode of a `NodeInterface`, shared between its clones
+#[derive(Debug, Clone)]
+pub struct Politeness {
+    pub config: PolitenessConfig,
+    state: Arc<Mutex<PolitenessState>>,
+}
+
+impl Politeness {
+    pub fn new(config: PolitenessConfig) -> Politeness {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Politeness {
+            config,
+            state: Arc::new(Mutex::new(PolitenessState {
+                next_slot: Instant::now(),
+                cache: HashMap::new(),
+                rng_state: seed | 1,
+            })),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, PolitenessState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Blocks the current thread until a request may be sent
+    pub(crate) fn wait_for_slot(&self) {
+        let spacing = Duration::from_secs_f64(1.0 / self.config.max_requests_per_second.max(0.001));
+        let wait = {
+            let mut state = self.state();
+            let now = Instant::now();
+            let slot = state.next_slot.max(now);
+            state.next_slot = slot + spacing;
+            slot - now
+        };
+        std::thread::sleep(wait);
+    }
+
+    /// Returns a cached response for the endpoint if it is still fresh
+    pub(crate) fn cached(&self, endpoint: &str) -> Option<serde_json::Value> {
+        let state = self.state();
+        state
+            .cache
+            .get(endpoint)
+            .filter(|(at, _)| at.elapsed() < self.config.cache_ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Caches the response of an endpoint, dropping expired entries
+    pub(crate) fn store(&self, endpoint: &str, value: &serde_json::Value) {
+        let ttl = self.config.cache_ttl;
+        let mut state = self.state();
+        state.cache.retain(|_, (at, _)| at.elapsed() < ttl);
+        state
+            .cache
+            .insert(endpoint.to_string(), (Instant::now(), value.clone()));
+    }
+
+    /// Randomly varies the interval by up to `poll_jitter` in either direction
+    pub fn jittered(&self, interval: Duration) -> Duration {
+        let mut state = self.state();
+        // xorshift64
+        state.rng_state ^= state.rng_state << 13;
+        state.rng_state ^= state.rng_state >> 7;
+        state.rng_state ^= state.rng_state << 17;
+        let unit = (state.rng_state % 10_000) as f64 / 10_000.0;
+        let factor = 1.0 + self.config.poll_jitter * (2.0 * unit - 1.0);
+        interval.mul_f64(factor.max(0.0))
+    }
+}
+
+impl NodeInterface {
+    /// Enable the politeness mode, rate limiting all requests, caching GET
+    /// responses and jittering polling intervals
+    pub fn with_politeness(mut self, config: PolitenessConfig) -> Self {
+        self.politeness = Some(Politeness::new(config));
+        self
+    }
+
+    /// Varies a polling interval if the politeness mode is enabled
+    pub fn poll_interval(&self, interval: Duration) -> Duration {
+        match &self.politeness {
+            Some(politeness) => politeness.jittered(interval),
+            None => interval,
+        }
+    }
+
+    /// Sends GET requests to multiple endpoints, requesting every distinct
+    /// endpoint only once. With the politeness mode enabled the requests are
+    /// spread according to the rate limit and cached responses are reused.
+    pub fn get_json_batch(&self, endpoints: &[&str]) -> Vec<Result<serde_json::Value>> {
+        let mut responses: HashMap<&str, serde_json::Value> = HashMap::new();
+        endpoints
+            .iter()
+            .map(|endpoint| {
+                if let Some(value) = responses.get(endpoint) {
+                    return Ok(value.clone());
+                }
+                let value = self.get_json(endpoint)?;
+                responses.insert(endpoint, value.clone());
+                Ok(value)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jitter_stays_within_bounds() {
+        let politeness = Politeness::new(PolitenessConfig::default());
+        for _ in 0..100 {
+            let interval = politeness.jittered(Duration::from_secs(10));
+            assert!(interval >= Duration::from_secs(8) && interval <= Duration::from_secs(12));
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! The `QueryBackend` struct is defined here which serves box queries using
+//! either the node's blockchain index or registered UTXO-set scans,
+//! depending on what the connected node supports.
+use crate::capabilities::NodeFeature;
+use crate::node_interface::{encode_param, NodeError, NodeInterface, Result};
+use crate::scanning::Scan;
+use crate::tracking_rule::TrackingRule;
+use crate::{P2SAddressString, TokenID};
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use std::collections::HashMap;
+
+/// Number of indexed boxes requested from the node per page
+const PAGE_LIMIT: usize = 100;
+
+/// The source used to answer box queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryBackendKind {
+    /// The node's extra index (`/blockchain` endpoints)
+    Indexer,
+    /// UTXO-set scans registered on demand with the node.
+    /// Note: Scans only find boxes created after registration unless
+    /// the node rescans the blockchain.
+    Scans,
+}
+
+/// A `QueryBackend` answers box queries by address or token using whichever
+/// backend the connected node supports. If an indexer query finds the
+/// indexer unavailable, the backend switches to scans and the query is
+/// answered by a scan instead. Any other error is returned as is without
+/// changing the backend.
+#[derive(Debug, Clone)]
+pub struct QueryBackend {
+    pub kind: QueryBackendKind,
+    pub node_interface: NodeInterface,
+    scans: HashMap<String, Scan>,
+}
+
+impl QueryBackend {
+    /// Create a new `QueryBackend`, detecting whether the node has the
+    /// blockchain index enabled.
+    pub fn new(node_interface: &NodeInterface) -> QueryBackend {
+        let kind = if node_interface.indexer_enabled() {
+            QueryBackendKind::Indexer
+        } else {
+            QueryBackendKind::Scans
+        };
+        QueryBackend::with_kind(kind, node_interface)
+    }
+
+    /// Create a new `QueryBackend` which always starts with the provided backend
+    pub fn with_kind(kind: QueryBackendKind, node_interface: &NodeInterface) -> QueryBackend {
+        QueryBackend {
+            kind,
+            node_interface: node_interface.clone(),
+            scans: HashMap::new(),
+        }
+    }
+
+    /// Returns the unspent boxes protected by the provided address
+    pub fn unspent_boxes_by_address(&mut self, address: &P2SAddressString) -> Result<Vec<ErgoBox>> {
+        self.node_interface.check_address_network(address)?;
+        if self.kind == QueryBackendKind::Indexer {
+            match self.indexed_address_boxes(address) {
+                Err(e) if self.indexer_unavailable(&e) => self.kind = QueryBackendKind::Scans,
+                boxes => return boxes,
+            }
+        }
+        let tracking_rule = TrackingRule::address(address)?;
+        self.scan_boxes(&format!("address:{address}"), tracking_rule)
+    }
+
+    /// Returns the unspent boxes which hold the provided token
+    pub fn unspent_boxes_by_token(&mut self, token_id: &TokenID) -> Result<Vec<ErgoBox>> {
+        if self.kind == QueryBackendKind::Indexer {
+            let endpoint =
+                "/blockchain/box/unspent/byTokenId/".to_string() + &encode_param(token_id);
+            let boxes = self
+                .node_interface
+                .require_indexer()
+                .and_then(|_| self.indexed_token_boxes(&endpoint));
+            match boxes {
+                Err(e) if self.indexer_unavailable(&e) => self.kind = QueryBackendKind::Scans,
+                boxes => return boxes,
+            }
+        }
+        let tracking_rule = TrackingRule::contains_asset(token_id);
+        self.scan_boxes(&format!("token:{token_id}"), tracking_rule)
+    }
+
+    /// Checks whether an error of an indexer query shows that the indexer is
+    /// disabled on the node. A 404 only counts if probing the indexer confirms
+    /// it, as the node also answers 404 for missing boxes or transactions.
+    fn indexer_unavailable(&self, error: &NodeError) -> bool {
+        match error {
+            NodeError::FeatureDisabledOnNode(NodeFeature::Indexer) => true,
+            _ if error.is_not_found() => !self
+                .node_interface
+                .endpoint_available("/blockchain/indexedHeight"),
+            _ => false,
+        }
+    }
+
+    /// Acquires boxes found by a scan for the given key, registering
+    /// the scan with the node if it has not been registered yet.
+    fn scan_boxes(&mut self, key: &str, tracking_rule: TrackingRule) -> Result<Vec<ErgoBox>> {
+        if !self.scans.contains_key(key) {
+            let scan = Scan::ensure(key, &tracking_rule, &self.node_interface)?;
+            self.scans.insert(key.to_string(), scan);
+        }
+        self.scans[key].get_boxes()
+    }
+
+    /// Acquires all pages of the unspent boxes of an address from the indexer
+    fn indexed_address_boxes(&self, address: &str) -> Result<Vec<ErgoBox>> {
+        let mut box_list = vec![];
+        for offset in (0..).step_by(PAGE_LIMIT) {
+            let page = self
+                .node_interface
+                .unspent_boxes_by_address(address, offset, PAGE_LIMIT)?;
+            let last_page = page.len() < PAGE_LIMIT;
+            box_list.extend(page);
+            if last_page {
+                break;
+            }
+        }
+        Ok(box_list)
+    }
+
+    /// Acquires all pages of boxes from an indexer token endpoint
+    fn indexed_token_boxes(&self, endpoint: &str) -> Result<Vec<ErgoBox>> {
+        let mut box_list = vec![];
+        for offset in (0..).step_by(PAGE_LIMIT) {
+            let paged_endpoint = format!("{endpoint}?offset={offset}&limit={PAGE_LIMIT}");
+            let page: Vec<ErgoBox> = self.node_interface.get_typed(&paged_endpoint)?;
+            let last_page = page.len() < PAGE_LIMIT;
+            box_list.extend(page);
+            if last_page {
+                break;
+            }
+        }
+        Ok(box_list)
+    }
+}
+
+impl NodeInterface {
+    /// Checks whether the node has the blockchain index (extra indexer) enabled
+    pub fn indexer_enabled(&self) -> bool {
+        self.capabilities()
+            .map(|c| c.supports(NodeFeature::Indexer))
+            .unwrap_or(false)
+    }
+
+    /// Create a `QueryBackend` using the backend supported by this node
+    pub fn query_backend(&self) -> QueryBackend {
+        QueryBackend::new(self)
+    }
+}
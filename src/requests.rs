@@ -1,78 +1,316 @@
-use crate::node_interface::{NodeError, NodeInterface, Result};
+use crate::node_interface::{endpoint_url, NodeApiError, NodeError, NodeInterface, Result};
 use crate::JsonString;
-use json::JsonValue;
 use reqwest::blocking::{RequestBuilder, Response};
 use reqwest::header::{HeaderValue, CONTENT_TYPE};
+use reqwest::StatusCode;
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Read;
+use std::time::Instant;
+
+/// Endpoints which are sent to the `wallet_route` of a `NodeInterface`
+const WALLET_ENDPOINT_PREFIXES: [&str; 2] = ["/wallet", "/scan"];
+
+/// Endpoints which are blocked when a `NodeInterface` is in read-only mode
+const MUTATING_ENDPOINTS: [&str; 24] = [
+    "/node/shutdown",
+    "/transactions",
+    "/transactions/bytes",
+    "/wallet/init",
+    "/wallet/restore",
+    "/wallet/unlock",
+    "/wallet/lock",
+    "/wallet/rescan",
+    "/wallet/updateChangeAddress",
+    "/wallet/deriveKey",
+    "/wallet/deriveNextKey",
+    "/wallet/payment/send",
+    "/wallet/transaction/send",
+    "/wallet/transaction/sign",
+    "/wallet/generateCommitments",
+    "/wallet/getPrivateKey",
+    "/scan/register",
+    "/scan/deregister",
+    "/scan/addBox",
+    "/scan/stopTracking",
+    "/scan/p2sRule",
+    "/mining/solution",
+    "/peers/connect",
+    "/utxo/genesis/reset",
+];
+
+/// Checks whether an endpoint mutates the node state or spends wallet funds
+pub(crate) fn is_mutating_endpoint(endpoint: &str) -> bool {
+    let path = endpoint.split('?').next().unwrap_or_default();
+    MUTATING_ENDPOINTS.contains(&path.trim_end_matches('/'))
+}
+
+/// Checks a node response for an error, i.e. a non-success status code or
+/// an error body of the form `{"error": <code>, "reason": .., "detail": ..}`.
+/// Error bodies are returned as `NodeError::Api`, any other failed response
+/// as `NodeError::BadRequest` holding the raw body.
+pub(crate) fn check_node_error(status: StatusCode, text: &str) -> Result<()> {
+    // Avoid parsing large successful responses which cannot be error bodies
+    if status.is_success() && !text.trim_start().starts_with('{') {
+        return Ok(());
+    }
+    match NodeApiError::from_body(text) {
+        Some(api_error) => Err(NodeError::Api(api_error)),
+        None if status.is_success() => Ok(()),
+        None => Err(NodeError::BadRequest(text.to_string())),
+    }
+}
+
+/// Extracts the string field `key` of a node response, failing with
+/// `NodeError::FailedParsingNodeResponse` if it is missing
+pub(crate) fn string_field(json: &Value, key: &str) -> Result<String> {
+    json[key]
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| NodeError::FailedParsingNodeResponse(json.to_string()))
+}
+
+/// Builds a `HeaderValue` from an api key
+fn api_header(api_key: &str) -> HeaderValue {
+    match HeaderValue::from_str(api_key) {
+        Ok(k) => k,
+        _ => HeaderValue::from_static("None"),
+    }
+}
+
+/// Sets required headers for a request using the provided api key header
+fn set_headers(rb: RequestBuilder, api_key: HeaderValue) -> RequestBuilder {
+    rb.header("accept", "application/json")
+        .header("api_key", api_key)
+        .header(CONTENT_TYPE, "application/json")
+}
 
 impl NodeInterface {
     /// Builds a `HeaderValue` to use for requests with the api key specified
     pub fn get_node_api_header(&self) -> HeaderValue {
-        match HeaderValue::from_str(&self.api_key) {
-            Ok(k) => k,
-            _ => HeaderValue::from_static("None"),
-        }
+        api_header(&self.api_key)
     }
 
     /// Sets required headers for a request
     pub fn set_req_headers(&self, rb: RequestBuilder) -> RequestBuilder {
-        rb.header("accept", "application/json")
-            .header("api_key", self.get_node_api_header())
-            .header(CONTENT_TYPE, "application/json")
+        set_headers(rb, api_header(&self.api_key))
     }
 
     /// Sends a GET request to the Ergo node
     pub fn send_get_req(&self, endpoint: &str) -> Result<Response> {
-        let url = self
-            .url
-            .join(endpoint)
-            .map_err(|e| NodeError::InvalidUrl(e.to_string()))?;
+        let (url, api_key) = self.route(endpoint)?;
         let client = reqwest::blocking::Client::new().get(url);
-        self.set_req_headers(client)
-            .send()
-            .map_err(|_| NodeError::NodeUnreachable)
+        self.send_and_record(endpoint, set_headers(client, api_header(api_key)))
     }
 
     /// Sends a POST request to the Ergo node
     pub fn send_post_req(&self, endpoint: &str, body: String) -> Result<Response> {
-        let url = self
-            .url
-            .join(endpoint)
-            .map_err(|e| NodeError::InvalidUrl(e.to_string()))?;
+        let (url, api_key) = self.route(endpoint)?;
         let client = reqwest::blocking::Client::new().post(url);
-        self.set_req_headers(client)
-            .body(body)
-            .send()
-            .map_err(|_| NodeError::NodeUnreachable)
+        self.send_and_record(
+            endpoint,
+            set_headers(client, api_header(api_key)).body(body),
+        )
+    }
+
+    /// Resolves the full url and api key used for an endpoint, routing
+    /// wallet endpoints to the `wallet_route` if one is set. Endpoints not
+    /// allowed by the `endpoint_policy` and mutating endpoints of a
+    /// read-only `NodeInterface` are rejected.
+    fn route(&self, endpoint: &str) -> Result<(Url, &str)> {
+        self.endpoint_policy.check(endpoint)?;
+        if self.read_only && is_mutating_endpoint(endpoint) {
+            return Err(NodeError::ReadOnlyViolation(endpoint.to_string()));
+        }
+        let (base_url, api_key) = match &self.wallet_route {
+            Some(route)
+                if WALLET_ENDPOINT_PREFIXES
+                    .iter()
+                    .any(|prefix| endpoint.starts_with(prefix)) =>
+            {
+                (&route.url, route.api_key.as_str())
+            }
+            _ => (&self.url, self.api_key.as_str()),
+        };
+        Ok((endpoint_url(base_url, endpoint)?, api_key))
+    }
+
+    /// Sends a prepared request and records its outcome in the metrics.
+    /// The first successful request after a connection failure is reported
+    /// as a reconnect. In politeness mode the request waits for the rate
+    /// limit first.
+    fn send_and_record(&self, endpoint: &str, rb: RequestBuilder) -> Result<Response> {
+        if let Some(politeness) = &self.politeness {
+            politeness.wait_for_slot();
+        }
+        let start = Instant::now();
+        let res = rb.send();
+        let error = match &res {
+            Ok(r) if r.status().is_success() => None,
+            Ok(r) => Some(r.status().to_string()),
+            Err(e) => Some(e.to_string()),
+        };
+        self.metrics.record(endpoint, start.elapsed(), error);
+        match res {
+            Ok(resp) => {
+                if self.session.take_connection_lost() {
+                    self.observe_reconnect();
+                }
+                Ok(resp)
+            }
+            Err(e) => {
+                if e.is_connect() {
+                    self.session.connection_lost();
+                }
+                Err(NodeError::NodeUnreachable)
+            }
+        }
     }
 
-    /// Parses response from node into JSON
-    pub fn parse_response_to_json(&self, resp: Result<Response>) -> Result<JsonValue> {
-        let text = resp?.text().map_err(|_| {
+    /// Reads the body of a response as text, failing with
+    /// `NodeError::ResponseTooLarge` if it exceeds `max_response_size`
+    pub fn read_response_text(&self, resp: Response) -> Result<String> {
+        let limit = self.max_response_size;
+        if resp.content_length().is_some_and(|len| len > limit as u64) {
+            return Err(NodeError::ResponseTooLarge(limit));
+        }
+        let mut bytes = vec![];
+        resp.take(limit as u64 + 1)
+            .read_to_end(&mut bytes)
+            .map_err(|_| {
+                NodeError::FailedParsingNodeResponse(
+                    "Node Response Not Parseable into Text.".to_string(),
+                )
+            })?;
+        if bytes.len() > limit {
+            return Err(NodeError::ResponseTooLarge(limit));
+        }
+        String::from_utf8(bytes).map_err(|_| {
             NodeError::FailedParsingNodeResponse(
                 "Node Response Not Parseable into Text.".to_string(),
             )
-        })?;
-        let json = json::parse(&text).map_err(|_| NodeError::FailedParsingNodeResponse(text))?;
-        Ok(json)
+        })
+    }
+
+    /// Parses response from node into a `serde_json::Value`. Error responses
+    /// from the node are returned as `NodeError::Api`.
+    pub fn parse_response_to_json(&self, resp: Result<Response>) -> Result<Value> {
+        let resp = resp?;
+        let status = resp.status();
+        let text = self.read_response_text(resp)?;
+        check_node_error(status, &text)?;
+        serde_json::from_str(&text).map_err(|_| NodeError::FailedParsingNodeResponse(text))
     }
 
     /// General function for submitting a Json String body to an endpoint
-    /// which also returns a `JsonValue` response.
+    /// which also returns a `serde_json::Value` response.
     pub fn use_json_endpoint_and_check_errors(
         &self,
         endpoint: &str,
         json_body: &JsonString,
-    ) -> Result<JsonValue> {
+    ) -> Result<Value> {
         let res = self.send_post_req(endpoint, json_body.to_string());
 
+        self.parse_response_to_json(res)
+    }
+
+    /// Sends a GET request to an arbitrary node endpoint and returns the
+    /// response as a `serde_json::Value`. Error responses from the node are
+    /// returned as `NodeError::Api`. In politeness mode recent
+    /// responses are reused.
+    pub fn get_json(&self, endpoint: &str) -> Result<Value> {
+        if let Some(value) = self.politeness.as_ref().and_then(|p| p.cached(endpoint)) {
+            return Ok(value);
+        }
+        let res = self.send_get_req(endpoint);
+        let value = self.parse_response_to_json(res)?;
+        if endpoint == "/info" {
+            // Errors are ignored so the original request is unaffected
+            let _ = self.observe_node_info(&value);
+        }
+        if let Some(politeness) = &self.politeness {
+            politeness.store(endpoint, &value);
+        }
+        Ok(value)
+    }
+
+    /// Sends a POST request with the given JSON body to an arbitrary node
+    /// endpoint and returns the response as a `serde_json::Value`. Error
+    /// responses from the node are returned as `NodeError::Api`.
+    pub fn post_json(&self, endpoint: &str, body: &Value) -> Result<Value> {
+        let res = self.send_post_req(endpoint, body.to_string());
+        self.parse_response_to_json(res)
+    }
+
+    /// Sends a GET request to an arbitrary node endpoint and decodes the
+    /// response into `T`. Useful for endpoints which the crate does not
+    /// wrap yet. Error responses from the node are returned as
+    /// `NodeError::Api`.
+    pub fn get_typed<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        let res_json = self.get_json(endpoint)?;
+        serde_json::from_value(res_json.clone())
+            .map_err(|_| NodeError::FailedParsingNodeResponse(res_json.to_string()))
+    }
+
+    /// Sends a POST request with `body` serialized as JSON to an arbitrary
+    /// node endpoint and decodes the response into `T`. Error responses
+    /// from the node are returned as `NodeError::Api`.
+    pub fn post_typed<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: &B,
+    ) -> Result<T> {
+        let body = serde_json::to_string(body).map_err(|e| NodeError::Other(e.to_string()))?;
+        let res = self.send_post_req(endpoint, body);
         let res_json = self.parse_response_to_json(res)?;
-        let error_details = res_json["detail"].to_string();
+        serde_json::from_value(res_json.clone())
+            .map_err(|_| NodeError::FailedParsingNodeResponse(res_json.to_string()))
+    }
+}
 
-        // Check if send tx request failed and returned error json
-        if error_details != "null" {
-            return Err(NodeError::BadRequest(error_details));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_node_error() {
+        let not_found = r#"{"error": 404, "reason": "not-found", "detail": "Address not found"}"#;
+        match check_node_error(StatusCode::NOT_FOUND, not_found) {
+            Err(NodeError::Api(e)) => {
+                assert_eq!(e.error, 404);
+                assert_eq!(e.reason, "not-found");
+                assert_eq!(e.message(), "Address not found");
+            }
+            _ => panic!("Expected Api error"),
+        }
+        assert!(check_node_error(StatusCode::OK, not_found).is_err());
+        assert!(check_node_error(StatusCode::OK, r#"{"error": null}"#).is_ok());
+        assert!(check_node_error(StatusCode::OK, "[1, 2]").is_ok());
+        match check_node_error(StatusCode::BAD_GATEWAY, "Bad Gateway") {
+            Err(NodeError::BadRequest(body)) => assert_eq!(body, "Bad Gateway"),
+            _ => panic!("Expected BadRequest"),
         }
+    }
+
+    #[test]
+    fn test_string_field() {
+        let json: Value = serde_json::from_str(r#"{"raw": "02ab", "height": 1}"#).unwrap();
+        assert_eq!(string_field(&json, "raw").unwrap(), "02ab");
+        assert!(string_field(&json, "height").is_err());
+        assert!(string_field(&json, "address").is_err());
+    }
 
-        Ok(res_json)
+    #[test]
+    fn test_is_mutating_endpoint() {
+        assert!(is_mutating_endpoint("/wallet/transaction/send"));
+        assert!(is_mutating_endpoint("/transactions"));
+        assert!(is_mutating_endpoint("/scan/deregister?scanId=1"));
+        assert!(is_mutating_endpoint("/scan/p2sRule"));
+        assert!(is_mutating_endpoint("/wallet/getPrivateKey"));
+        assert!(!is_mutating_endpoint("/transactions/unconfirmed?limit=10"));
+        assert!(!is_mutating_endpoint("/wallet/balances"));
+        assert!(!is_mutating_endpoint("/wallet/transaction/generate"));
     }
 }
@@ -0,0 +1,215 @@
+//! Incremental consumption of scan results: `scan_diff_since` returns the
+//! boxes added to and spent from a scan since a persisted `ScanCursor`, so
+//! restartable services can process scan changes exactly once without
+//! keeping snapshots of all scan boxes.
+use crate::node_interface::{encode_param, NodeError, NodeInterface, Result};
+use crate::{BlockDuration, BlockHeight, ScanID};
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use serde_json::Value;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// Number of scan boxes requested from the node per page
+const PAGE_LIMIT: usize = 100;
+
+/// Prefix of the string representation of a `ScanCursor`
+const CURSOR_PREFIX: &str = "scan-cursor:v2:";
+
+/// An opaque position within the history of a scan. Cursors should be
+/// persisted via their string representation (`to_string`/`parse`).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ScanCursor {
+    height: BlockHeight,
+    /// Id of the best chain block at `height`, used to detect reorganizations
+    block_id: Option<String>,
+    /// Lowest inclusion height of the scan boxes unspent at `height`, which
+    /// bounds the spent boxes which need to be checked
+    min_unspent_height: BlockHeight,
+}
+
+impl ScanCursor {
+    /// A cursor from which all boxes of a scan are returned
+    pub fn start() -> ScanCursor {
+        ScanCursor::default()
+    }
+
+    /// A cursor `blocks` blocks before this one, used to continue after
+    /// `NodeError::ChainReorganized`. Boxes between both cursors are
+    /// returned again, thus consumers should deduplicate them by box id.
+    pub fn rewind(&self, blocks: BlockDuration) -> ScanCursor {
+        ScanCursor {
+            height: self.height.saturating_sub(blocks),
+            block_id: None,
+            min_unspent_height: 0,
+        }
+    }
+}
+
+impl fmt::Display for ScanCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{CURSOR_PREFIX}{}:{}:{}",
+            self.height,
+            self.min_unspent_height,
+            self.block_id.as_deref().unwrap_or("-")
+        )
+    }
+}
+
+impl FromStr for ScanCursor {
+    type Err = NodeError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || NodeError::Other(format!("Invalid scan cursor: {s}"));
+        let fields: Vec<&str> = s
+            .strip_prefix(CURSOR_PREFIX)
+            .ok_or_else(invalid)?
+            .split(':')
+            .collect();
+        match fields.as_slice() {
+            [height, min_unspent_height, block_id] => Ok(ScanCursor {
+                height: height.parse().map_err(|_| invalid())?,
+                min_unspent_height: min_unspent_height.parse().map_err(|_| invalid())?,
+                block_id: Some(block_id.to_string()).filter(|id| id != "-"),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// The changes of a scan between two cursors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanDiff {
+    /// Boxes which were included in a block since the previous cursor,
+    /// including boxes which have been spent in the meantime
+    pub added: Vec<ErgoBox>,
+    /// Boxes which were spent since the previous cursor
+    pub spent: Vec<ErgoBox>,
+    /// The cursor to pass to the next `scan_diff_since` call
+    pub cursor: ScanCursor,
+}
+
+impl NodeInterface {
+    /// Get the boxes added to and spent from the scan since the `cursor`,
+    /// considering confirmed boxes only, together with a new cursor. The
+    /// diff extends up to the height the wallet (and thus the scan) has
+    /// processed. Fails with `NodeError::ChainReorganized` if the block at
+    /// the cursor is no longer part of the best chain.
+    pub fn scan_diff_since(&self, scan_id: &ScanID, cursor: &ScanCursor) -> Result<ScanDiff> {
+        if let Some(block_id) = &cursor.block_id {
+            if self.block_id_at_height(cursor.height)? != *block_id {
+                return Err(NodeError::ChainReorganized(cursor.height));
+            }
+        }
+        let height = self.wallet_status()?.height;
+        let mut diff = ScanDiff {
+            added: vec![],
+            spent: vec![],
+            cursor: cursor.clone(),
+        };
+        if height <= cursor.height {
+            return Ok(diff);
+        }
+        let window = cursor.height + 1..=height;
+        let scan_id = encode_param(scan_id);
+
+        let unspent_endpoint = format!(
+            "/scan/unspentBoxes/{scan_id}?minConfirmations=1&minInclusionHeight={}&maxInclusionHeight={height}",
+            window.start()
+        );
+        let spent_endpoint = format!(
+            "/scan/spentBoxes/{scan_id}?minConfirmations=1&minInclusionHeight={}&maxInclusionHeight={height}",
+            cursor.min_unspent_height
+        );
+        // Inclusion heights of boxes which are unspent at `height`
+        let mut unspent_heights = vec![];
+        for endpoint in [unspent_endpoint, spent_endpoint] {
+            for offset in (0..).step_by(PAGE_LIMIT) {
+                let items =
+                    self.get_json(&format!("{endpoint}&offset={offset}&limit={PAGE_LIMIT}"))?;
+                let items = items
+                    .as_array()
+                    .ok_or_else(|| NodeError::FailedParsingNodeResponse(items.to_string()))?;
+                for item in items {
+                    if item["spendingHeight"].as_u64().is_some_and(|h| h > height) {
+                        unspent_heights.extend(item["inclusionHeight"].as_u64());
+                    }
+                    let (added, spent) = classify_scan_box(item, &window);
+                    if !added && !spent {
+                        continue;
+                    }
+                    let ergo_box: ErgoBox = serde_json::from_value(item["box"].clone())
+                        .map_err(|e| NodeError::FailedParsingBox(e.to_string()))?;
+                    if added {
+                        diff.added.push(ergo_box.clone());
+                    }
+                    if spent {
+                        diff.spent.push(ergo_box);
+                    }
+                }
+                if items.len() < PAGE_LIMIT {
+                    break;
+                }
+            }
+        }
+        let oldest_unspent = self.get_json(&format!(
+            "/scan/unspentBoxes/{scan_id}?minConfirmations=1&maxInclusionHeight={height}&sortDirection=asc&limit=1"
+        ))?;
+        unspent_heights.extend(oldest_unspent[0]["inclusionHeight"].as_u64());
+
+        diff.cursor = ScanCursor {
+            height,
+            block_id: Some(self.block_id_at_height(height)?),
+            min_unspent_height: unspent_heights.into_iter().min().unwrap_or(height + 1),
+        };
+        Ok(diff)
+    }
+}
+
+/// Determines whether a scan box was added and/or spent within the window
+fn classify_scan_box(item: &Value, window: &RangeInclusive<BlockHeight>) -> (bool, bool) {
+    let in_window = |key: &str| item[key].as_u64().is_some_and(|h| window.contains(&h));
+    (in_window("inclusionHeight"), in_window("spendingHeight"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_scan_cursor_round_trip() {
+        let cursor = ScanCursor {
+            height: 1200,
+            block_id: Some("ab".repeat(32)),
+            min_unspent_height: 1100,
+        };
+        assert_eq!(cursor.to_string().parse::<ScanCursor>().unwrap(), cursor);
+        assert!("1200".parse::<ScanCursor>().is_err());
+        assert!("scan-cursor:v2:1200".parse::<ScanCursor>().is_err());
+        assert_eq!(ScanCursor::start().to_string(), "scan-cursor:v2:0:0:-");
+        assert_eq!(
+            ScanCursor::start()
+                .to_string()
+                .parse::<ScanCursor>()
+                .unwrap(),
+            ScanCursor::start()
+        );
+        assert_eq!(cursor.rewind(10).height, 1190);
+    }
+
+    #[test]
+    fn test_classify_scan_box() {
+        let window = 101..=110;
+        let created = json!({"inclusionHeight": 105, "spendingHeight": null});
+        let created_and_spent = json!({"inclusionHeight": 102, "spendingHeight": 108});
+        let spent = json!({"inclusionHeight": 50, "spendingHeight": 101});
+        let spent_later = json!({"inclusionHeight": 50, "spendingHeight": 111});
+        assert_eq!(classify_scan_box(&created, &window), (true, false));
+        assert_eq!(classify_scan_box(&created_and_spent, &window), (true, true));
+        assert_eq!(classify_scan_box(&spent, &window), (false, true));
+        assert_eq!(classify_scan_box(&spent_later, &window), (false, false));
+    }
+}
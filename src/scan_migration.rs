@@ -0,0 +1,98 @@
+//! Helpers for moving an application from wallet-based to scan-based box
+//! tracking, registering a scan per address and verifying that the scans
+//! find the same unspent boxes as the node wallet.
+use crate::node_interface::{NodeInterface, Result};
+use crate::scanning::Scan;
+use crate::tracking_rule::TrackingRule;
+use crate::{BlockHeight, P2PKAddressString};
+use ergo_lib::ergotree_ir::chain::ergo_box::BoxId;
+use std::collections::BTreeSet;
+
+/// The differences between the unspent wallet boxes and the scan boxes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanParity {
+    /// Unspent wallet boxes of the migrated addresses not found by the scans
+    pub missing_in_scans: Vec<BoxId>,
+    /// Boxes found by the scans which are not unspent wallet boxes
+    pub extra_in_scans: Vec<BoxId>,
+}
+
+impl ScanParity {
+    /// Checks whether the scans found exactly the unspent wallet boxes
+    pub fn is_consistent(&self) -> bool {
+        self.missing_in_scans.is_empty() && self.extra_in_scans.is_empty()
+    }
+}
+
+/// Compares the box ids of the wallet with the box ids found by the scans
+fn box_parity(wallet_ids: &[BoxId], scan_ids: &[BoxId]) -> ScanParity {
+    let wallet_ids: BTreeSet<&BoxId> = wallet_ids.iter().collect();
+    let scan_ids: BTreeSet<&BoxId> = scan_ids.iter().collect();
+    ScanParity {
+        missing_in_scans: wallet_ids.difference(&scan_ids).map(|id| **id).collect(),
+        extra_in_scans: scan_ids.difference(&wallet_ids).map(|id| **id).collect(),
+    }
+}
+
+impl NodeInterface {
+    /// Registers a scan tracking each of the given wallet addresses (reusing
+    /// scans registered by a previous migration) and triggers a wallet rescan
+    /// starting at `rescan_from` so the scans pick up existing boxes. The
+    /// rescan runs in the background on the node, thus `verify_scan_parity`
+    /// should be used once it has finished.
+    pub fn migrate_addresses_to_scans(
+        &self,
+        addresses: &[P2PKAddressString],
+        rescan_from: Option<BlockHeight>,
+    ) -> Result<Vec<Scan>> {
+        let mut scans = vec![];
+        for address in addresses {
+            self.check_address_network(address)?;
+            let tracking_rule = TrackingRule::address(address)?;
+            scans.push(Scan::ensure(
+                &format!("address:{address}"),
+                &tracking_rule,
+                self,
+            )?);
+        }
+        self.wallet_rescan(rescan_from)?;
+        Ok(scans)
+    }
+
+    /// Compares the unspent wallet boxes of the given addresses with the
+    /// boxes found by the given scans
+    pub fn verify_scan_parity(
+        &self,
+        addresses: &[P2PKAddressString],
+        scans: &[Scan],
+    ) -> Result<ScanParity> {
+        let wallet_ids: Vec<BoxId> = self
+            .wallet_unspent_boxes_by_address()?
+            .into_iter()
+            .filter(|(address, _)| addresses.contains(address))
+            .flat_map(|(_, boxes)| boxes)
+            .map(|b| b.box_id())
+            .collect();
+        let mut scan_ids = vec![];
+        for scan in scans {
+            scan_ids.extend(scan.get_boxes()?.iter().map(|b| b.box_id()));
+        }
+        Ok(box_parity(&wallet_ids, &scan_ids))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ergo_lib::ergo_chain_types::Digest32;
+
+    #[test]
+    fn test_box_parity() {
+        let id = |byte: u8| BoxId::from(Digest32::from([byte; 32]));
+        let parity = box_parity(&[id(1), id(2)], &[id(2), id(3)]);
+        assert_eq!(parity.missing_in_scans, vec![id(1)]);
+        assert_eq!(parity.extra_in_scans, vec![id(3)]);
+        assert!(!parity.is_consistent());
+        assert!(box_parity(&[id(1)], &[id(1)]).is_consistent());
+    }
+}
@@ -1,12 +1,92 @@
 /// A struct `Scan` is defined here which wraps the concept of UTXO-set
 /// scanning in a Rust-based struct interface.
-use crate::node_interface::NodeInterface;
+use crate::node_interface::{encode_param, NodeInterface};
 pub use crate::node_interface::{NodeError, Result};
-use crate::{P2PKAddressString, ScanID};
+use crate::tracking_rule::TrackingRule;
+use crate::{BlockHeight, P2PKAddressString, ScanID};
+use ergo_lib::ergotree_ir::chain::address::AddressEncoder;
 use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
-use json;
-use json::JsonValue;
-use serde_json::from_str;
+use ergo_lib::ergotree_ir::mir::constant::Constant;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, json, Value};
+use std::convert::TryFrom;
+
+/// Value of `min_confirmations` which includes unconfirmed boxes from the mempool
+pub const INCLUDE_MEMPOOL: i32 = -1;
+
+/// Query parameters of `/scan/unspentBoxes` restricting which boxes are
+/// returned. Unset parameters use the defaults of the node.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanBoxesOptions {
+    /// Minimum number of confirmations, `INCLUDE_MEMPOOL` to also return
+    /// unconfirmed boxes
+    pub min_confirmations: Option<i32>,
+    pub max_confirmations: Option<i32>,
+    pub min_inclusion_height: Option<BlockHeight>,
+    pub max_inclusion_height: Option<BlockHeight>,
+}
+
+impl ScanBoxesOptions {
+    /// Create new `ScanBoxesOptions` using the defaults of the node
+    pub fn new() -> ScanBoxesOptions {
+        ScanBoxesOptions::default()
+    }
+
+    /// Only return boxes with at least `confirmations` confirmations
+    pub fn with_min_confirmations(mut self, confirmations: u32) -> ScanBoxesOptions {
+        self.min_confirmations = Some(i32::try_from(confirmations).unwrap_or(i32::MAX));
+        self
+    }
+
+    /// Only return boxes with at most `confirmations` confirmations
+    pub fn with_max_confirmations(mut self, confirmations: u32) -> ScanBoxesOptions {
+        self.max_confirmations = Some(i32::try_from(confirmations).unwrap_or(i32::MAX));
+        self
+    }
+
+    /// Also return unconfirmed boxes which are outputs of mempool transactions
+    pub fn with_mempool(mut self) -> ScanBoxesOptions {
+        self.min_confirmations = Some(INCLUDE_MEMPOOL);
+        self
+    }
+
+    /// Only return boxes included at or after the given height
+    pub fn with_min_inclusion_height(mut self, height: BlockHeight) -> ScanBoxesOptions {
+        self.min_inclusion_height = Some(height);
+        self
+    }
+
+    /// Only return boxes included at or before the given height
+    pub fn with_max_inclusion_height(mut self, height: BlockHeight) -> ScanBoxesOptions {
+        self.max_inclusion_height = Some(height);
+        self
+    }
+
+    /// The query string for the set parameters, empty if none are set
+    fn query(&self) -> String {
+        let params: Vec<String> = [
+            ("minConfirmations", self.min_confirmations.map(i64::from)),
+            ("maxConfirmations", self.max_confirmations.map(i64::from)),
+            (
+                "minInclusionHeight",
+                self.min_inclusion_height.map(|h| h as i64),
+            ),
+            (
+                "maxInclusionHeight",
+                self.max_inclusion_height.map(|h| h as i64),
+            ),
+        ]
+        .iter()
+        .filter_map(|(key, value)| value.map(|v| format!("{key}={v}")))
+        .collect();
+        if params.is_empty() {
+            String::new()
+        } else {
+            format!("?{}", params.join("&"))
+        }
+    }
+}
 
 /// A `Scan` is a name + scan_id for a given scan with extra methods for acquiring boxes.
 #[derive(Debug, Clone)]
@@ -16,6 +96,86 @@ pub struct Scan {
     pub node_interface: NodeInterface,
 }
 
+/// How the boxes found by a scan interact with the node wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WalletInteraction {
+    /// Boxes are only tracked by the scan
+    Off,
+    /// Boxes are tracked by the scan and, if they belong to the wallet,
+    /// also by the wallet
+    #[default]
+    Shared,
+    /// Boxes are tracked by the scan and the wallet
+    Forced,
+}
+
+/// A request to register a scan as accepted by `/scan/register`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanRegistrationRequest {
+    pub scan_name: String,
+    #[serde(default)]
+    pub wallet_interaction: WalletInteraction,
+    /// Whether boxes created off-chain (in the mempool) are dropped from
+    /// the scan once they are spent before being included in a block
+    #[serde(default = "default_remove_offchain")]
+    pub remove_offchain: bool,
+    pub tracking_rule: TrackingRule,
+}
+
+fn default_remove_offchain() -> bool {
+    true
+}
+
+impl ScanRegistrationRequest {
+    /// Create a new `ScanRegistrationRequest` using the node's defaults
+    /// for the wallet interaction and off-chain box removal
+    pub fn new(scan_name: &str, tracking_rule: TrackingRule) -> ScanRegistrationRequest {
+        ScanRegistrationRequest {
+            scan_name: scan_name.to_string(),
+            wallet_interaction: WalletInteraction::default(),
+            remove_offchain: default_remove_offchain(),
+            tracking_rule,
+        }
+    }
+
+    /// Set how the boxes found by the scan interact with the node wallet
+    pub fn with_wallet_interaction(
+        mut self,
+        wallet_interaction: WalletInteraction,
+    ) -> ScanRegistrationRequest {
+        self.wallet_interaction = wallet_interaction;
+        self
+    }
+
+    /// Set whether spent off-chain boxes are removed from the scan
+    pub fn with_remove_offchain(mut self, remove_offchain: bool) -> ScanRegistrationRequest {
+        self.remove_offchain = remove_offchain;
+        self
+    }
+}
+
+/// A scan registered with the node as returned by `/scan/listAll`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisteredScan {
+    pub scan_id: u32,
+    pub scan_name: String,
+    #[serde(default)]
+    pub wallet_interaction: WalletInteraction,
+    #[serde(default = "default_remove_offchain")]
+    pub remove_offchain: bool,
+    pub tracking_rule: TrackingRule,
+}
+
+impl RegisteredScan {
+    /// Builds a `Scan` for this registered scan
+    pub fn to_scan(&self, node_interface: &NodeInterface) -> Scan {
+        Scan::new(&self.scan_name, &self.scan_id.to_string(), node_interface)
+    }
+}
+
 impl Scan {
     /// Manually create a new `Scan` struct. It is assumed that
     /// a scan with the given `id` has already been registered
@@ -33,15 +193,23 @@ impl Scan {
     /// a `Scan` struct in a `Result`.
     pub fn register(
         name: &String,
-        tracking_rule: JsonValue,
+        tracking_rule: TrackingRule,
         node_interface: &NodeInterface,
     ) -> Result<Scan> {
-        let scan_json = object! {
-        scanName: name.to_string(),
-        trackingRule: tracking_rule,
-        };
+        let request = ScanRegistrationRequest::new(name, tracking_rule);
+        let scan_id = node_interface.register_scan(&request)?;
+        Ok(Scan::new(name, &scan_id, node_interface))
+    }
 
-        let scan_id = node_interface.register_scan(&scan_json)?;
+    /// Builds a `Scan` for the scan with the given name and tracking rule,
+    /// registering it with the node only if no such scan exists yet. This
+    /// avoids registering duplicate scans when an application restarts.
+    pub fn ensure(
+        name: &str,
+        tracking_rule: &TrackingRule,
+        node_interface: &NodeInterface,
+    ) -> Result<Scan> {
+        let scan_id = node_interface.ensure_scan(name, tracking_rule)?;
         Ok(Scan::new(name, &scan_id, node_interface))
     }
 
@@ -75,17 +243,17 @@ impl Scan {
 
     /// Saves UTXO-set scans (specifically id) to local scanIDs.json
     pub fn save_scan_ids_locally(scans: Vec<Scan>) -> Result<bool> {
-        let mut id_json = object! {};
-        let mut json_list: Vec<JsonValue> = vec![];
+        let mut json_list: Vec<Value> = vec![];
         for scan in scans {
             if &scan.id == "null" {
                 return Err(NodeError::FailedRegisteringScan(scan.name));
             }
-            let sub_json = object! {name: scan.name, id: scan.id};
-            json_list.push(sub_json);
+            json_list.push(json!({"name": scan.name, "id": scan.id}));
         }
-        id_json["scans"] = json_list.into();
-        std::fs::write("scanIDs.json", json::stringify_pretty(id_json, 4)).map_err(|_| {
+        let id_json = json!({ "scans": json_list });
+        let file_string =
+            serde_json::to_string_pretty(&id_json).map_err(|e| NodeError::Other(e.to_string()))?;
+        std::fs::write("scanIDs.json", file_string).map_err(|_| {
             NodeError::Other("Failed to save scans to local scanIDs.json".to_string())
         })?;
         Ok(true)
@@ -95,15 +263,21 @@ impl Scan {
     pub fn read_local_scan_ids(node: &NodeInterface) -> Result<Vec<Scan>> {
         let file_string = &std::fs::read_to_string("scanIDs.json")
             .map_err(|_| NodeError::Other("Unable to read scanIDs.json".to_string()))?;
-        let scan_json = json::parse(file_string)
+        let scan_json: Value = from_str(file_string)
             .map_err(|_| NodeError::Other("Failed to parse scanIDs.json".to_string()))?;
 
-        let scans: &Vec<Scan> = &scan_json["scans"]
-            .members()
-            .map(|scan| Scan::new(&scan["name"].to_string(), &scan["id"].to_string(), node))
+        let scans = scan_json["scans"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|scan| {
+                let name = scan["name"].as_str().unwrap_or_default();
+                let id = scan["id"].as_str().unwrap_or_default();
+                Scan::new(name, id, node)
+            })
             .collect();
 
-        Ok(scans.clone())
+        Ok(scans)
     }
 
     /// Serialize a "P2PKAddressString" to be used within a scan tracking rule
@@ -114,6 +288,21 @@ impl Scan {
         let raw = node.p2pk_to_raw(address)?;
         Ok("0e240008cd".to_string() + &raw)
     }
+
+    /// Serialize the ErgoTree of any address (P2PK or P2S) as a `Coll[Byte]`
+    /// constant to be used within an `equals` scan tracking rule on `R1`
+    pub fn serialize_address_for_tracking(address: &str) -> Result<String> {
+        let tree_bytes = AddressEncoder::unchecked_parse_address_from_str(address)
+            .map_err(|e| NodeError::Other(e.to_string()))?
+            .script()
+            .map_err(|e| NodeError::Other(e.to_string()))?
+            .sigma_serialize_bytes()
+            .map_err(|e| NodeError::Other(e.to_string()))?;
+        let constant_bytes = Constant::from(tree_bytes)
+            .sigma_serialize_bytes()
+            .map_err(|e| NodeError::Other(e.to_string()))?;
+        Ok(base16::encode_lower(&constant_bytes))
+    }
 }
 
 /// Scanning-related endpoints
@@ -128,22 +317,52 @@ impl NodeInterface {
 
     /// Registers a scan with the node and either returns the `scan_id`
     /// or an error
-    pub fn register_scan(&self, scan_json: &JsonValue) -> Result<ScanID> {
+    pub fn register_scan(&self, request: &ScanRegistrationRequest) -> Result<ScanID> {
         let endpoint = "/scan/register";
-        let body = scan_json.clone().to_string();
+        let body = serde_json::to_string(request).map_err(|e| NodeError::Other(e.to_string()))?;
         let res = self.send_post_req(endpoint, body);
         let res_json = self.parse_response_to_json(res)?;
+        res_json["scanId"]
+            .as_u64()
+            .map(|scan_id| scan_id.to_string())
+            .ok_or_else(|| NodeError::FailedParsingNodeResponse(res_json.to_string()))
+    }
 
-        if res_json["error"].is_null() {
-            Ok(res_json["scanId"].to_string())
-        } else {
-            Err(NodeError::BadRequest(res_json["error"].to_string()))
+    /// Get all scans registered with the node
+    pub fn list_all_scans(&self) -> Result<Vec<RegisteredScan>> {
+        self.get_typed("/scan/listAll")
+    }
+
+    /// Returns the id of the registered scan with the given name and
+    /// tracking rule, registering a new scan only if none exists yet
+    pub fn ensure_scan(&self, scan_name: &str, tracking_rule: &TrackingRule) -> Result<ScanID> {
+        let existing = self
+            .list_all_scans()?
+            .into_iter()
+            .find(|scan| scan.scan_name == scan_name && scan.tracking_rule == *tracking_rule);
+        match existing {
+            Some(scan) => Ok(scan.scan_id.to_string()),
+            None => self.register_scan(&ScanRegistrationRequest::new(
+                scan_name,
+                tracking_rule.clone(),
+            )),
         }
     }
 
     /// Using the `scan_id` of a registered scan, acquires unspent boxes which have been found by said scan
     pub fn scan_boxes(&self, scan_id: &ScanID) -> Result<Vec<ErgoBox>> {
-        let endpoint = "/scan/unspentBoxes/".to_string() + scan_id;
+        self.scan_boxes_with_options(scan_id, &ScanBoxesOptions::default())
+    }
+
+    /// Using the `scan_id` of a registered scan, acquires the unspent boxes
+    /// found by said scan which match the given `ScanBoxesOptions`
+    pub fn scan_boxes_with_options(
+        &self,
+        scan_id: &ScanID,
+        options: &ScanBoxesOptions,
+    ) -> Result<Vec<ErgoBox>> {
+        let endpoint =
+            "/scan/unspentBoxes/".to_string() + &encode_param(scan_id) + &options.query();
         let res = self.send_get_req(&endpoint);
         let res_json = self.parse_response_to_json(res)?;
 
@@ -168,26 +387,133 @@ impl NodeInterface {
     /// Using the `scan_id` of a registered scan, manually adds a box to said
     /// scan.
     pub fn add_box_to_scan(&self, scan_id: &ScanID, box_id: &String) -> Result<String> {
-        let ergo_box = serde_json::to_string(&self.box_from_id(box_id)?)
+        let ergo_box = serde_json::to_value(self.box_from_id(box_id)?)
             .map_err(|_| NodeError::FailedParsingBox(box_id.clone()))?;
 
-        let scan_id_int: u64 = scan_id
-            .parse()
-            .map_err(|_| NodeError::Other("Scan ID was not a valid integer number.".to_string()))?;
+        let scan_id_int = parse_scan_id(scan_id)?;
 
         let endpoint = "/scan/addBox";
-        let body = object! {
-            "scanIds": vec![scan_id_int],
+        let body = json!({
+            "scanIds": [scan_id_int],
             "box": ergo_box,
-        };
+        });
 
         let res = self.send_post_req(endpoint, body.to_string());
         let res_json = self.parse_response_to_json(res)?;
+        Ok(res_json
+            .as_str()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| res_json.to_string()))
+    }
 
-        if res_json["error"].is_null() {
-            Ok(res_json.to_string())
-        } else {
-            Err(NodeError::BadRequest(res_json["error"].to_string()))
-        }
+    /// Using the `scan_id` of a registered scan, stops tracking the box
+    /// with the given id, e.g. after it was added by mistake via
+    /// `add_box_to_scan`.
+    pub fn stop_tracking_box(&self, scan_id: &ScanID, box_id: &String) -> Result<()> {
+        let endpoint = "/scan/stopTracking";
+        let body = json!({
+            "scanId": parse_scan_id(scan_id)?,
+            "boxId": box_id,
+        });
+
+        let res = self.send_post_req(endpoint, body.to_string());
+        self.parse_response_to_json(res)?;
+        Ok(())
+    }
+
+    /// Deregisters the scan with the given `scan_id` from the node
+    pub fn deregister_scan(&self, scan_id: &ScanID) -> Result<()> {
+        let endpoint = "/scan/deregister";
+        let body = json!({
+            "scanId": parse_scan_id(scan_id)?,
+        });
+
+        let res = self.send_post_req(endpoint, body.to_string());
+        self.parse_response_to_json(res)?;
+        Ok(())
+    }
+}
+
+/// Parses a `ScanID` into the integer expected by the scan endpoints
+fn parse_scan_id(scan_id: &ScanID) -> Result<u64> {
+    scan_id
+        .parse()
+        .map_err(|_| NodeError::Other("Scan ID was not a valid integer number.".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::TEST_ADDRESS;
+
+    #[test]
+    fn test_serialize_p2pk_address_for_tracking() {
+        let address = TEST_ADDRESS;
+        let pk = AddressEncoder::unchecked_parse_address_from_str(address)
+            .unwrap()
+            .content_bytes();
+        let serialized = Scan::serialize_address_for_tracking(address).unwrap();
+        assert_eq!(
+            serialized,
+            "0e240008cd".to_string() + &base16::encode_lower(&pk)
+        );
+    }
+
+    #[test]
+    fn test_scan_boxes_options_query() {
+        assert_eq!(ScanBoxesOptions::new().query(), "");
+        assert_eq!(
+            ScanBoxesOptions::new().with_mempool().query(),
+            "?minConfirmations=-1"
+        );
+        let options = ScanBoxesOptions::new()
+            .with_min_confirmations(10)
+            .with_max_confirmations(20)
+            .with_min_inclusion_height(100)
+            .with_max_inclusion_height(200);
+        assert_eq!(
+            options.query(),
+            "?minConfirmations=10&maxConfirmations=20&minInclusionHeight=100&maxInclusionHeight=200"
+        );
+    }
+
+    #[test]
+    fn test_scan_registration_request_json() {
+        let rule = TrackingRule::contains_asset(&"08b59b14".to_string());
+        let request = ScanRegistrationRequest::new("Oracle Pool Box Scan", rule.clone());
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({
+                "scanName": "Oracle Pool Box Scan",
+                "walletInteraction": "shared",
+                "removeOffchain": true,
+                "trackingRule": {"predicate": "containsAsset", "assetId": "08b59b14"}
+            })
+        );
+        let request = request
+            .with_wallet_interaction(WalletInteraction::Off)
+            .with_remove_offchain(false);
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["walletInteraction"], "off");
+        assert_eq!(json["removeOffchain"], false);
+    }
+
+    #[test]
+    fn test_parse_registered_scans() {
+        let json = r#"[
+            {"scanId": 11, "scanName": "Oracle Pool Box Scan", "walletInteraction": "off",
+             "removeOffchain": true,
+             "trackingRule": {"predicate": "containsAsset", "assetId": "08b59b14"}},
+            {"scanId": 12, "scanName": "Legacy Scan",
+             "trackingRule": {"predicate": "equals", "register": "R1", "value": "0e01ab"}}
+        ]"#;
+        let scans: Vec<RegisteredScan> = serde_json::from_str(json).unwrap();
+        assert_eq!(scans[0].scan_id, 11);
+        assert_eq!(scans[0].wallet_interaction, WalletInteraction::Off);
+        assert_eq!(
+            scans[0].tracking_rule,
+            TrackingRule::contains_asset(&"08b59b14".to_string())
+        );
+        assert_eq!(scans[1].wallet_interaction, WalletInteraction::Shared);
     }
 }
@@ -0,0 +1,107 @@
+//! The `HeightScheduler` struct is defined here which runs registered actions
+//! once the chain reaches given heights, driven by a `BlockSubscription`.
+use crate::node_interface::Result;
+use crate::subscription::BlockSubscription;
+use crate::{BlockDuration, BlockHeight};
+
+type Action = Box<dyn FnMut(BlockHeight) + Send>;
+
+enum Trigger {
+    /// Run once when the given height is reached
+    AtHeight(BlockHeight),
+    /// Run on every height which is a multiple of the given number of blocks
+    EveryNBlocks(BlockDuration),
+}
+
+struct ScheduledAction {
+    trigger: Trigger,
+    action: Action,
+    done: bool,
+}
+
+/// A `HeightScheduler` holds actions which are run when the chain reaches
+/// certain heights, for epoch-based protocols such as oracles or auctions.
+pub struct HeightScheduler {
+    pub subscription: BlockSubscription,
+    actions: Vec<ScheduledAction>,
+}
+
+impl HeightScheduler {
+    /// Create a new `HeightScheduler` driven by the given `BlockSubscription`
+    pub fn new(subscription: BlockSubscription) -> HeightScheduler {
+        HeightScheduler {
+            subscription,
+            actions: vec![],
+        }
+    }
+
+    /// Run `callback` once when the chain reaches `height`
+    pub fn on_height<F>(&mut self, height: BlockHeight, callback: F) -> &mut HeightScheduler
+    where
+        F: FnMut(BlockHeight) + Send + 'static,
+    {
+        self.push(Trigger::AtHeight(height), callback)
+    }
+
+    /// Run `callback` on every height which is a multiple of `n` blocks
+    pub fn every_n_blocks<F>(&mut self, n: BlockDuration, callback: F) -> &mut HeightScheduler
+    where
+        F: FnMut(BlockHeight) + Send + 'static,
+    {
+        self.push(Trigger::EveryNBlocks(n.max(1)), callback)
+    }
+
+    /// Checks the node once and runs all actions which became due.
+    /// Returns the new height if a new block appeared.
+    pub fn tick(&mut self) -> Result<Option<BlockHeight>> {
+        let previous = self.subscription.last_height();
+        let height = match self.subscription.poll()? {
+            Some(height) => height,
+            None => return Ok(None),
+        };
+        // Heights skipped between two polls are caught up on as well
+        let from = previous.map(|p| p + 1).unwrap_or(height);
+        for action in self.actions.iter_mut().filter(|a| !a.done) {
+            match action.trigger {
+                Trigger::AtHeight(h) if h <= height => {
+                    (action.action)(height);
+                    action.done = true;
+                }
+                Trigger::EveryNBlocks(n) => {
+                    for h in from..=height {
+                        if h % n == 0 {
+                            (action.action)(h);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        self.actions.retain(|a| !a.done);
+        Ok(Some(height))
+    }
+
+    /// Blocks the current thread, running actions as they become due.
+    /// Returns once all one-off actions ran and no recurring actions remain,
+    /// or when an error is encountered.
+    pub fn run(&mut self) -> Result<()> {
+        while !self.actions.is_empty() {
+            if self.tick()?.is_none() {
+                std::thread::sleep(self.subscription.poll_interval);
+            }
+        }
+        Ok(())
+    }
+
+    fn push<F>(&mut self, trigger: Trigger, callback: F) -> &mut HeightScheduler
+    where
+        F: FnMut(BlockHeight) + Send + 'static,
+    {
+        self.actions.push(ScheduledAction {
+            trigger,
+            action: Box::new(callback),
+            done: false,
+        });
+        self
+    }
+}
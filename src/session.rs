@@ -0,0 +1,167 @@
+//! Detection of node restarts and lost connections. Restarts are detected
+//! from the `/info` responses fetched anyway (or by `revalidate_session`),
+//! reconnects from the first successful response after a connection failure.
+//! The cached capabilities are then re-detected and `NodeEvent`s are emitted
+//! to the registered listeners so long-running services can re-register
+//! scans or re-unlock the wallet.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use serde_json::Value;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// An event concerning the connection to the node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeEvent {
+    /// The node was restarted (its launch time or app version changed)
+    Restarted {
+        previous_app_version: String,
+        app_version: String,
+    },
+    /// The node is reachable again after a connection failure
+    Reconnected,
+    /// The node wallet is initialized but locked, e.g. after a restart
+    WalletLocked,
+}
+
+type NodeEventListener = Arc<dyn Fn(&NodeEvent) + Send + Sync>;
+
+/// The identity of a running node process
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NodeIdentity {
+    launch_time: u64,
+    app_version: String,
+}
+
+#[derive(Debug, Default)]
+struct SessionState {
+    identity: Option<NodeIdentity>,
+    connection_lost: bool,
+}
+
+/// Tracks the node session, shared between clones of a `NodeInterface`
+#[derive(Clone, Default)]
+pub struct SessionMonitor {
+    state: Arc<Mutex<SessionState>>,
+    listeners: Arc<Mutex<Vec<NodeEventListener>>>,
+}
+
+impl fmt::Debug for SessionMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionMonitor")
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SessionMonitor {
+    /// Marks the connection to the node as lost
+    pub(crate) fn connection_lost(&self) {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .connection_lost = true;
+    }
+
+    /// Clears the connection lost flag, returning whether it was set
+    pub(crate) fn take_connection_lost(&self) -> bool {
+        std::mem::take(
+            &mut self
+                .state
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .connection_lost,
+        )
+    }
+
+    /// Stores the current node identity and returns the previous one
+    fn replace_identity(&self, identity: NodeIdentity) -> Option<NodeIdentity> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .identity
+            .replace(identity)
+    }
+
+    fn emit(&self, event: &NodeEvent) {
+        let listeners = self
+            .listeners
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        for listener in listeners {
+            listener(event);
+        }
+    }
+}
+
+impl NodeInterface {
+    /// Register a listener which is called with every `NodeEvent`
+    pub fn on_node_event<F>(&self, listener: F)
+    where
+        F: Fn(&NodeEvent) + Send + Sync + 'static,
+    {
+        self.session
+            .listeners
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(Arc::new(listener));
+    }
+
+    /// Checks whether the node was restarted since the last check by
+    /// fetching `/info`. If so, capabilities are re-detected, the wallet
+    /// readiness is checked and the resulting events are emitted and
+    /// returned. Intended to be called separately from regular requests,
+    /// e.g. periodically by long-running services.
+    pub fn revalidate_session(&self) -> Result<Vec<NodeEvent>> {
+        let res = self.send_get_req("/info");
+        let info = self.parse_response_to_json(res)?;
+        let mut events = self.observe_node_info(&info)?;
+        if !events.is_empty() {
+            if let Ok(status) = self.wallet_status() {
+                if status.initialized && !status.unlocked {
+                    events.push(NodeEvent::WalletLocked);
+                    self.session.emit(&NodeEvent::WalletLocked);
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    /// Detects a node restart from an already fetched `/info` response,
+    /// clearing the cached capabilities and emitting `NodeEvent::Restarted`
+    /// if the launch time or app version changed. No requests are made.
+    pub(crate) fn observe_node_info(&self, info: &Value) -> Result<Vec<NodeEvent>> {
+        let identity = NodeIdentity {
+            launch_time: info["launchTime"]
+                .as_u64()
+                .ok_or_else(|| NodeError::FailedParsingNodeResponse(info.to_string()))?,
+            app_version: info["appVersion"].as_str().unwrap_or_default().to_string(),
+        };
+        let mut events = vec![];
+        if let Some(previous) = self.session.replace_identity(identity.clone()) {
+            if previous != identity {
+                events.push(NodeEvent::Restarted {
+                    previous_app_version: previous.app_version,
+                    app_version: identity.app_version,
+                });
+            }
+        }
+        self.emit_session_events(&events);
+        Ok(events)
+    }
+
+    /// Handles the first successful response after a connection failure.
+    /// No requests are made, a restart is detected once `/info` is fetched.
+    pub(crate) fn observe_reconnect(&self) {
+        self.emit_session_events(&[NodeEvent::Reconnected]);
+    }
+
+    fn emit_session_events(&self, events: &[NodeEvent]) {
+        if !events.is_empty() {
+            self.capabilities_cache.clear();
+        }
+        for event in events {
+            self.session.emit(event);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! The `BlockSubscription` struct is defined here which polls the node for
+//! new blocks, allowing applications to react to every new block height.
+use crate::node_interface::{NodeInterface, Result};
+use crate::BlockHeight;
+use std::time::Duration;
+
+/// Default interval between height polls
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Settings for adaptive polling, where the interval between polls grows
+/// by `backoff` every time no new block is found (up to `max_interval`) and
+/// resets to `min_interval` once a new block appears.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptivePolling {
+    pub min_interval: Duration,
+    pub max_interval: Duration,
+    pub backoff: f64,
+}
+
+impl Default for AdaptivePolling {
+    fn default() -> Self {
+        AdaptivePolling {
+            min_interval: Duration::from_secs(5),
+            max_interval: Duration::from_secs(60),
+            backoff: 1.5,
+        }
+    }
+}
+
+impl AdaptivePolling {
+    /// Computes the interval to wait after a poll which did (or did not)
+    /// observe a new block
+    pub fn next_interval(&self, current: Duration, new_block: bool) -> Duration {
+        if new_block {
+            self.min_interval
+        } else {
+            current
+                .mul_f64(self.backoff)
+                .clamp(self.min_interval, self.max_interval)
+        }
+    }
+}
+
+/// A `BlockSubscription` reports every new block height observed on the node.
+#[derive(Debug, Clone)]
+pub struct BlockSubscription {
+    pub node_interface: NodeInterface,
+    pub poll_interval: Duration,
+    /// Adaptive polling settings, if set the interval changes between polls
+    pub adaptive: Option<AdaptivePolling>,
+    last_height: Option<BlockHeight>,
+}
+
+impl BlockSubscription {
+    /// Create a new `BlockSubscription` polling the node every `poll_interval`
+    pub fn new(node_interface: &NodeInterface, poll_interval: Duration) -> BlockSubscription {
+        BlockSubscription {
+            node_interface: node_interface.clone(),
+            poll_interval,
+            adaptive: None,
+            last_height: None,
+        }
+    }
+
+    /// Enable adaptive polling, starting at the minimum interval
+    pub fn with_adaptive_polling(mut self, adaptive: AdaptivePolling) -> BlockSubscription {
+        self.poll_interval = adaptive.min_interval;
+        self.adaptive = Some(adaptive);
+        self
+    }
+
+    /// The most recent height reported by this subscription
+    pub fn last_height(&self) -> Option<BlockHeight> {
+        self.last_height
+    }
+
+    /// Checks the node once and returns the new height if a new block
+    /// has appeared since the last poll. With adaptive polling enabled the
+    /// `poll_interval` is adjusted according to the outcome.
+    pub fn poll(&mut self) -> Result<Option<BlockHeight>> {
+        let height = self.node_interface.current_block_height()?;
+        let new_block = match self.last_height {
+            Some(last) => height > last,
+            None => true,
+        };
+        if let Some(adaptive) = &self.adaptive {
+            self.poll_interval = adaptive.next_interval(self.poll_interval, new_block);
+        }
+        if !new_block {
+            return Ok(None);
+        }
+        self.last_height = Some(height);
+        Ok(Some(height))
+    }
+
+    /// Blocks the current thread until a new block appears and returns its height
+    pub fn next_height(&mut self) -> Result<BlockHeight> {
+        loop {
+            if let Some(height) = self.poll()? {
+                return Ok(height);
+            }
+            std::thread::sleep(self.node_interface.poll_interval(self.poll_interval));
+        }
+    }
+}
+
+impl NodeInterface {
+    /// Create a `BlockSubscription` which polls this node every `poll_interval`
+    pub fn subscribe_blocks(&self, poll_interval: Duration) -> BlockSubscription {
+        BlockSubscription::new(self, poll_interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adaptive_polling_interval() {
+        let adaptive = AdaptivePolling::default();
+        let mut interval = adaptive.min_interval;
+        for _ in 0..10 {
+            interval = adaptive.next_interval(interval, false);
+        }
+        assert_eq!(interval, adaptive.max_interval);
+        assert_eq!(
+            adaptive.next_interval(interval, true),
+            adaptive.min_interval
+        );
+        assert_eq!(
+            adaptive.next_interval(Duration::from_secs(10), false),
+            Duration::from_secs(15)
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! Fixtures shared by the unit tests of the crate
+use crate::NanoErg;
+use ergo_lib::chain::transaction::TxId;
+use ergo_lib::ergotree_ir::chain::address::{Address, AddressEncoder};
+use ergo_lib::ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergo_lib::ergotree_ir::chain::ergo_box::{BoxTokens, ErgoBox, NonMandatoryRegisters};
+use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+
+/// A testnet P2PK address
+pub(crate) const TEST_ADDRESS: &str = "3Wwc4HWrTcYkRycPNhEUSwNNBdqSBuiHy2zFvjMHukccxE77BaX3";
+
+/// The parsed `TEST_ADDRESS`
+pub(crate) fn test_address() -> Address {
+    AddressEncoder::unchecked_parse_address_from_str(TEST_ADDRESS).unwrap()
+}
+
+/// The `ErgoTree` protecting boxes of `TEST_ADDRESS`
+pub(crate) fn test_tree() -> ErgoTree {
+    test_address().script().unwrap()
+}
+
+/// A box without tokens and registers, created as output `index` of the
+/// zero transaction id
+pub(crate) fn test_box(
+    value: NanoErg,
+    tree: &ErgoTree,
+    creation_height: u32,
+    index: u16,
+) -> ErgoBox {
+    test_box_with(
+        value,
+        tree,
+        None,
+        NonMandatoryRegisters::empty(),
+        creation_height,
+        index,
+    )
+}
+
+/// A box with the given tokens and registers, created as output `index` of
+/// the zero transaction id
+pub(crate) fn test_box_with(
+    value: NanoErg,
+    tree: &ErgoTree,
+    tokens: Option<BoxTokens>,
+    registers: NonMandatoryRegisters,
+    creation_height: u32,
+    index: u16,
+) -> ErgoBox {
+    ErgoBox::new(
+        BoxValue::new(value).unwrap(),
+        tree.clone(),
+        tokens,
+        registers,
+        creation_height,
+        TxId::zero(),
+        index,
+    )
+    .unwrap()
+}
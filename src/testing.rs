@@ -0,0 +1,820 @@
+//! The `MockNode` struct is defined here which serves canned JSON responses
+//! for the endpoints used by this crate from a local HTTP server, allowing
+//! downstream projects to test code using a `NodeInterface` without a node.
+//! Fixtures are included for `/info`, the wallet, scans and tx submission.
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// Api key accepted by the `NodeInterface` returned by `MockNode::node_interface`
+pub const MOCK_API_KEY: &str = "hello";
+/// Tx id returned by the mocked wallet transaction send endpoints
+pub const MOCK_TX_ID: &str = "4c6282be413c6e300a530618b37790be5f286ded758accc2aebd41554a1be308";
+
+/// A request received by the `MockNode`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// The request path including the query string
+    pub path: String,
+    pub api_key: Option<String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone)]
+enum MockResponse {
+    Json(u16, String),
+    /// Responds with the `id` of the submitted transaction as the node does
+    EchoTxId,
+}
+
+type Routes = Arc<Mutex<HashMap<(String, String), MockResponse>>>;
+
+/// A mock node serving canned JSON responses on a local port. Responses are
+/// matched by method and path (ignoring the query string). Paths ending with
+/// `*` match any path starting with the preceding prefix. Unknown endpoints
+/// respond with a node style 404 error.
+#[derive(Debug)]
+pub struct MockNode {
+    pub addr: SocketAddr,
+    routes: Routes,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    shutdown: Arc<AtomicBool>,
+    server: Option<JoinHandle<()>>,
+}
+
+impl MockNode {
+    /// Starts a `MockNode` on a free local port serving the default fixtures
+    pub fn start() -> Result<MockNode> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .map_err(|e| NodeError::Other(format!("Failed to start mock node: {e}")))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| NodeError::Other(e.to_string()))?;
+        let mut node = MockNode {
+            addr,
+            routes: Arc::new(Mutex::new(HashMap::new())),
+            requests: Arc::new(Mutex::new(vec![])),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            server: None,
+        };
+        node.add_default_fixtures();
+
+        let routes = node.routes.clone();
+        let requests = node.requests.clone();
+        let shutdown = node.shutdown.clone();
+        node.server = Some(std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if shutdown.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    handle_connection(stream, &routes, &requests).ok();
+                }
+            }
+        }));
+        Ok(node)
+    }
+
+    /// Create a `NodeInterface` connected to this mock node
+    pub fn node_interface(&self) -> NodeInterface {
+        NodeInterface::from_url_str(MOCK_API_KEY, &format!("http://{}/", self.addr))
+            .expect("mock node address is a valid url")
+    }
+
+    /// Respond to requests with the given method and path with `status` and
+    /// the JSON `body`, replacing any previous response
+    pub fn respond(&self, method: &str, path: &str, status: u16, body: &str) {
+        self.set_route(method, path, MockResponse::Json(status, body.to_string()));
+    }
+
+    /// All requests received so far, in order
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .clone()
+    }
+
+    fn set_route(&self, method: &str, path: &str, response: MockResponse) {
+        self.routes
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .insert((method.to_uppercase(), path.to_string()), response);
+    }
+
+    fn add_default_fixtures(&mut self) {
+        let fixtures = [
+            ("GET", "/info", include_str!("testing/fixtures/info.json")),
+            (
+                "GET",
+                "/wallet/status",
+                include_str!("testing/fixtures/wallet_status.json"),
+            ),
+            (
+                "GET",
+                "/wallet/addresses",
+                include_str!("testing/fixtures/wallet_addresses.json"),
+            ),
+            (
+                "GET",
+                "/wallet/balances",
+                include_str!("testing/fixtures/wallet_balances.json"),
+            ),
+            (
+                "GET",
+                "/wallet/boxes/unspent",
+                include_str!("testing/fixtures/wallet_boxes.json"),
+            ),
+            (
+                "POST",
+                "/scan/register",
+                include_str!("testing/fixtures/scan_register.json"),
+            ),
+            (
+                "GET",
+                "/scan/listAll",
+                include_str!("testing/fixtures/scan_list_all.json"),
+            ),
+            (
+                "GET",
+                "/scan/unspentBoxes/*",
+                include_str!("testing/fixtures/scan_boxes.json"),
+            ),
+        ];
+        for (method, path, body) in fixtures {
+            self.respond(method, path, 200, body);
+        }
+        let tx_id = format!("\"{MOCK_TX_ID}\"");
+        self.respond("POST", "/wallet/transaction/send", 200, &tx_id);
+        self.respond("POST", "/wallet/payment/send", 200, &tx_id);
+        self.set_route("POST", "/transactions", MockResponse::EchoTxId);
+    }
+}
+
+impl Drop for MockNode {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake up the server thread blocked on accepting connections
+        TcpStream::connect(self.addr).ok();
+        if let Some(server) = self.server.take() {
+            server.join().ok();
+        }
+    }
+}
+
+/// Reads a single HTTP request from the stream and writes the mocked response
+fn handle_connection(
+    mut stream: TcpStream,
+    routes: &Routes,
+    requests: &Mutex<Vec<RecordedRequest>>,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    let mut api_key = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            match name.trim().to_lowercase().as_str() {
+                "content-length" => content_length = value.trim().parse().unwrap_or(0),
+                "api_key" => api_key = Some(value.trim().to_string()),
+                _ => (),
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    let body = String::from_utf8_lossy(&body).to_string();
+
+    let route_path = path.split('?').next().unwrap_or_default();
+    let response = find_route(
+        &routes.lock().unwrap_or_else(|p| p.into_inner()),
+        &method,
+        route_path,
+    );
+    let (status, response_body) = match response {
+        Some(MockResponse::Json(status, body)) => (status, body),
+        Some(MockResponse::EchoTxId) => match serde_json::from_str::<serde_json::Value>(&body) {
+            Ok(tx) if tx["id"].is_string() => (200, tx["id"].to_string()),
+            _ => (400, not_found_body("Invalid transaction")),
+        },
+        None => (
+            404,
+            not_found_body(&format!("{method} {route_path} is not mocked")),
+        ),
+    };
+    requests
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .push(RecordedRequest {
+            method,
+            path,
+            api_key,
+            body,
+        });
+
+    write!(
+        stream,
+        "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response_body}",
+        response_body.len()
+    )?;
+    stream.flush()
+}
+
+/// Finds the response for a request, preferring exact paths over prefixes
+fn find_route(
+    routes: &HashMap<(String, String), MockResponse>,
+    method: &str,
+    path: &str,
+) -> Option<MockResponse> {
+    if let Some(response) = routes.get(&(method.to_string(), path.to_string())) {
+        return Some(response.clone());
+    }
+    routes
+        .iter()
+        .filter(|((m, p), _)| m == method && p.ends_with('*'))
+        .filter(|((_, p), _)| path.starts_with(p.trim_end_matches('*')))
+        .max_by_key(|((_, p), _)| p.len())
+        .map(|(_, response)| response.clone())
+}
+
+/// A node style error response body
+fn not_found_body(detail: &str) -> String {
+    serde_json::json!({ "error": 404, "reason": "not-found", "detail": detail }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_node_fixtures() {
+        let mock = MockNode::start().unwrap();
+        let node = mock.node_interface();
+        assert_eq!(node.current_block_height().unwrap(), 1000100);
+        assert_eq!(node.wallet_nano_ergs_balance().unwrap(), 3000000000);
+        assert_eq!(node.unspent_boxes().unwrap().len(), 2);
+        assert_eq!(node.scan_boxes(&"1".to_string()).unwrap().len(), 1);
+        assert!(node.wallet_status().unwrap().unlocked);
+
+        mock.respond("GET", "/info", 200, r#"{"fullHeight": 5}"#);
+        assert_eq!(node.current_block_height().unwrap(), 5);
+        let info: std::collections::HashMap<String, u64> = node.get_typed("/info").unwrap();
+        assert_eq!(info["fullHeight"], 5);
+        assert!(matches!(
+            node.get_json("/peers/all"),
+            Err(NodeError::Api(e)) if e.error == 404
+        ));
+        let requests = mock.requests();
+        assert_eq!(requests[0].path, "/info");
+        assert_eq!(requests[0].api_key.as_deref(), Some(MOCK_API_KEY));
+    }
+
+    #[test]
+    fn test_mock_stop_tracking_box() {
+        let mock = MockNode::start().unwrap();
+        let node = mock.node_interface();
+        let box_id = "ab".repeat(32);
+        let body = serde_json::json!({ "scanId": 1, "boxId": box_id }).to_string();
+        mock.respond("POST", "/scan/stopTracking", 200, &body);
+        node.stop_tracking_box(&"1".to_string(), &box_id).unwrap();
+        let request: serde_json::Value = serde_json::from_str(&mock.requests()[0].body).unwrap();
+        assert_eq!(request["scanId"], 1);
+        assert_eq!(request["boxId"], box_id.as_str());
+        assert!(node.stop_tracking_box(&"x".to_string(), &box_id).is_err());
+    }
+
+    #[test]
+    fn test_mock_deregister_scan() {
+        let mock = MockNode::start().unwrap();
+        let node = mock.node_interface();
+        mock.respond("POST", "/scan/deregister", 200, r#"{"scanId": 1}"#);
+        node.deregister_scan(&"1".to_string()).unwrap();
+        let request: serde_json::Value = serde_json::from_str(&mock.requests()[0].body).unwrap();
+        assert_eq!(request["scanId"], 1);
+    }
+
+    #[test]
+    fn test_mock_ensure_scan() {
+        let mock = MockNode::start().unwrap();
+        let node = mock.node_interface();
+        let registered = &node.list_all_scans().unwrap()[0];
+        let rule = registered.tracking_rule.clone();
+        let scan_id = node.ensure_scan(&registered.scan_name, &rule).unwrap();
+        assert_eq!(scan_id, "1");
+        assert!(mock.requests().iter().all(|r| r.path != "/scan/register"));
+
+        mock.respond("POST", "/scan/register", 200, r#"{"scanId": 2}"#);
+        assert_eq!(node.ensure_scan("Other Scan", &rule).unwrap(), "2");
+        let register = mock.requests().pop().unwrap();
+        assert_eq!(register.path, "/scan/register");
+
+        mock.respond("POST", "/scan/register", 200, "{}");
+        assert!(matches!(
+            node.ensure_scan("Third Scan", &rule),
+            Err(NodeError::FailedParsingNodeResponse(_))
+        ));
+    }
+
+    #[test]
+    fn test_mock_send_idempotent() {
+        let mock = MockNode::start().unwrap();
+        let node = mock.node_interface();
+        let signed_tx = serde_json::json!({ "id": MOCK_TX_ID, "inputs": [], "outputs": [] });
+        mock.respond(
+            "POST",
+            "/wallet/transaction/generate",
+            200,
+            &signed_tx.to_string(),
+        );
+        let tx_id = node
+            .send_transaction_from_request_idempotent(&crate::TxRequestBuilder::new(), 3)
+            .unwrap();
+        assert_eq!(String::from(tx_id), MOCK_TX_ID);
+        let requests = mock.requests();
+        let submitted = requests.iter().find(|r| r.path == "/transactions").unwrap();
+        let submitted: serde_json::Value = serde_json::from_str(&submitted.body).unwrap();
+        assert_eq!(submitted, signed_tx);
+    }
+
+    #[test]
+    fn test_mock_wait_for_unknown_tx() {
+        let mock = MockNode::start().unwrap();
+        let node = mock.node_interface();
+        let tx_id = crate::transactions::parse_tx_id(&serde_json::json!(MOCK_TX_ID)).unwrap();
+        let timeout = std::time::Duration::from_millis(10);
+        assert!(matches!(
+            node.wait_for_tx_confirmation(&tx_id, 0, timeout),
+            Err(NodeError::ConfirmationTimeout(_))
+        ));
+        let endpoint = format!("/transactions/unconfirmed/byTransactionId/{MOCK_TX_ID}");
+        mock.respond("GET", &endpoint, 200, "{}");
+        node.wait_for_tx_confirmation(&tx_id, 0, timeout).unwrap();
+    }
+
+    #[test]
+    fn test_mock_script_helpers_unquoted() {
+        let mock = MockNode::start().unwrap();
+        let node = mock.node_interface();
+        let tree_hex = "0008cd0327e65711a59378c59359c3e1d0f7abe906479eccb76094e50fe79d743ccc15e6";
+        let raw = "0327e65711a59378c59359c3e1d0f7abe906479eccb76094e50fe79d743ccc15e6";
+        let address = crate::test_fixtures::TEST_ADDRESS.to_string();
+        let tree_body = serde_json::json!({ "tree": tree_hex }).to_string();
+        mock.respond("GET", "/script/addressToTree/*", 200, &tree_body);
+        let bytes_body = serde_json::json!({ "bytes": "0e240008cd" }).to_string();
+        mock.respond("GET", "/script/addressToBytes/*", 200, &bytes_body);
+        let raw_body = serde_json::json!({ "raw": raw }).to_string();
+        mock.respond("GET", "/utils/addressToRaw/*", 200, &raw_body);
+        let address_body = serde_json::json!({ "address": address }).to_string();
+        mock.respond("GET", "/utils/rawToAddress/*", 200, &address_body);
+
+        let tree = node.p2s_to_tree(&address).unwrap();
+        assert_eq!(tree.to_base16_bytes().unwrap(), tree_hex);
+        assert_eq!(node.p2s_to_bytes(&address).unwrap(), "0e240008cd");
+        assert_eq!(node.p2pk_to_raw(&address).unwrap(), raw);
+        assert_eq!(node.raw_to_p2pk(raw).unwrap(), address);
+        assert_eq!(
+            node.p2pk_to_raw_for_register(&address).unwrap(),
+            format!("07{raw}")
+        );
+    }
+
+    #[test]
+    fn test_mock_restart_detected_from_info() {
+        let mock = MockNode::start().unwrap();
+        let node = mock.node_interface();
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorded = events.clone();
+        node.on_node_event(move |event| recorded.lock().unwrap().push(event.clone()));
+
+        node.current_block_height().unwrap();
+        let restarted = r#"{"fullHeight": 5, "appVersion": "5.0.21", "launchTime": 1700000001000}"#;
+        mock.respond("GET", "/info", 200, restarted);
+        node.current_block_height().unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![crate::session::NodeEvent::Restarted {
+                previous_app_version: "5.0.20".to_string(),
+                app_version: "5.0.21".to_string(),
+            }]
+        );
+        assert!(mock.requests().iter().all(|r| r.path == "/info"));
+    }
+
+    #[test]
+    fn test_mock_tx_tracker_resume_and_drop() {
+        use crate::journal::{TxJournal, TxStatus};
+        use crate::tx_tracker::{TxState, TxTracker};
+        use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+        use ergo_lib::chain::transaction::Transaction;
+        use ergo_lib::ergotree_interpreter::sigma_protocol::prover::ProofBytes;
+
+        let mock = MockNode::start().unwrap();
+        let path = std::env::temp_dir().join(format!("tx-tracker-{}.jsonl", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        let node = mock.node_interface().with_tx_journal(TxJournal::new(&path));
+        let unsigned_tx: UnsignedTransaction = serde_json::from_value(serde_json::json!({
+            "inputs": [{
+                "boxId": "e56847ed19b3dc6b72828fcfb992fdf7310828cf291221269b7ffc72fd66706e",
+                "extension": {}
+            }],
+            "dataInputs": [],
+            "outputs": [{
+                "value": 1000000,
+                "ergoTree": "0008cd0327e65711a59378c59359c3e1d0f7abe906479eccb76094e50fe79d743ccc15e6",
+                "assets": [],
+                "additionalRegisters": {},
+                "creationHeight": 100
+            }]
+        }))
+        .unwrap();
+        let tx = Transaction::from_unsigned_tx(unsigned_tx, vec![ProofBytes::Empty]).unwrap();
+        let tx_id = String::from(tx.id());
+
+        let journal = TxJournal::new(&path);
+        let statuses = || -> Vec<TxStatus> {
+            let entries = journal.tx_entries(&tx_id).unwrap();
+            entries.iter().map(|entry| entry.status).collect()
+        };
+
+        let mut tracker = node.track_transaction(tx.clone());
+        assert_eq!(tracker.submit().unwrap(), TxState::Submitted);
+        assert_eq!(statuses(), vec![TxStatus::Signed, TxStatus::Submitted]);
+
+        // A failing node is not mistaken for a rejection of the tx
+        let unavailable = r#"{"error": 503, "reason": "service-unavailable"}"#;
+        mock.respond("POST", "/transactions", 503, unavailable);
+        assert!(tracker.poll().is_err());
+        assert_eq!(tracker.state(), TxState::Submitted);
+
+        let mut resumed = TxTracker::resume(&node, &tx_id).unwrap();
+        assert_eq!(resumed.state(), TxState::Submitted);
+        assert_eq!(node.resume_tracked_transactions().unwrap().len(), 1);
+
+        let double_spend =
+            r#"{"error": 400, "reason": "bad.request", "detail": "Double spending attempt"}"#;
+        mock.respond("POST", "/transactions", 400, double_spend);
+        assert_eq!(resumed.poll().unwrap(), TxState::Dropped);
+        assert!(node.resume_tracked_transactions().unwrap().is_empty());
+
+        assert!(node.submit_transaction(&tx).is_err());
+        assert_eq!(statuses().last(), Some(&TxStatus::Failed));
+        let confirmed = serde_json::json!({ "id": tx_id, "numConfirmations": 3 }).to_string();
+        mock.respond("GET", "/wallet/transactionById", 200, &confirmed);
+        let timeout = std::time::Duration::from_millis(10);
+        node.wait_for_tx_confirmation(&tx.id(), 1, timeout).unwrap();
+        assert_eq!(statuses().last(), Some(&TxStatus::Confirmed));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_mock_multisig_request_bodies() {
+        use crate::test_fixtures::{test_address, test_box, test_tree};
+        use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+        use ergo_lib::chain::transaction::Transaction;
+        use ergo_lib::ergotree_interpreter::sigma_protocol::prover::ProofBytes;
+        use ergo_lib::ergotree_ir::chain::address::Address;
+        use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+
+        let mock = MockNode::start().unwrap();
+        let node = mock.node_interface();
+        let pks = match test_address() {
+            Address::P2Pk(pk) => [pk],
+            _ => unreachable!(),
+        };
+        let input = test_box(2000000, &test_tree(), 100, 0);
+        let input_raw = base16::encode_lower(&input.sigma_serialize_bytes().unwrap());
+        let unsigned_tx: UnsignedTransaction = serde_json::from_value(serde_json::json!({
+            "inputs": [{ "boxId": String::from(input.box_id()), "extension": {} }],
+            "dataInputs": [],
+            "outputs": [{
+                "value": 1000000,
+                "ergoTree": test_tree().to_base16_bytes().unwrap(),
+                "assets": [],
+                "additionalRegisters": {},
+                "creationHeight": 100
+            }]
+        }))
+        .unwrap();
+        let signed_tx =
+            Transaction::from_unsigned_tx(unsigned_tx.clone(), vec![ProofBytes::Empty]).unwrap();
+        let hints = serde_json::json!({ "secretHints": {}, "publicHints": {} });
+        mock.respond(
+            "POST",
+            "/wallet/generateCommitments",
+            200,
+            &hints.to_string(),
+        );
+        mock.respond("POST", "/wallet/extractHints", 200, &hints.to_string());
+        let signed_json = serde_json::to_string(&signed_tx).unwrap();
+        mock.respond("POST", "/wallet/transaction/sign", 200, &signed_json);
+
+        let boxes = Some(vec![input]);
+        node.generate_commitments(&unsigned_tx, boxes.clone(), None)
+            .unwrap();
+        node.extract_hints(&signed_tx, &pks, &pks, boxes.clone(), None)
+            .unwrap();
+        let signed = node
+            .sign_transaction_with_hints(&unsigned_tx, boxes, None, Some(&hints))
+            .unwrap();
+        assert_eq!(signed, signed_tx);
+
+        let bodies: HashMap<String, serde_json::Value> = mock
+            .requests()
+            .into_iter()
+            .map(|r| (r.path, serde_json::from_str(&r.body).unwrap()))
+            .collect();
+        // A P2PK tree is the `0008cd` prefix followed by the public key
+        let tree_hex = test_tree().to_base16_bytes().unwrap();
+        let h = tree_hex.trim_start_matches("0008cd");
+        let proposition = serde_json::json!([{ "op": -51, "h": h }]);
+
+        let commitments = &bodies["/wallet/generateCommitments"];
+        assert_eq!(
+            commitments["tx"],
+            serde_json::to_value(&unsigned_tx).unwrap()
+        );
+        assert_eq!(commitments["inputsRaw"], serde_json::json!([input_raw]));
+
+        let extract = &bodies["/wallet/extractHints"];
+        assert_eq!(extract["tx"], serde_json::to_value(&signed_tx).unwrap());
+        assert_eq!(extract["real"], proposition);
+        assert_eq!(extract["simulated"], proposition);
+        assert_eq!(extract["inputsRaw"], serde_json::json!([input_raw]));
+
+        let sign = &bodies["/wallet/transaction/sign"];
+        assert_eq!(sign["tx"], serde_json::to_value(&unsigned_tx).unwrap());
+        assert_eq!(sign["inputsRaw"], serde_json::json!([input_raw]));
+        assert_eq!(sign["hints"], hints);
+    }
+
+    const TOKEN_ID: &str = "08b59b14e4fdd60e5952314adbaa8b4e00bc0f0b676872a5224d3bf8591074cd";
+
+    /// Starts a `MockNode` with the indexer enabled
+    fn indexer_mock() -> MockNode {
+        let mock = MockNode::start().unwrap();
+        let indexed_height = r#"{"indexedHeight": 1000100, "fullHeight": 1000100}"#;
+        mock.respond("GET", "/blockchain/indexedHeight", 200, indexed_height);
+        mock
+    }
+
+    #[test]
+    fn test_mock_token_info_from_index() {
+        let mock = indexer_mock();
+        let node = mock.node_interface();
+        let token = serde_json::json!({
+            "id": TOKEN_ID,
+            "boxId": "45482d60f7372105d2a8091c88ac486d33c8b4b3fddd8d7d0c5df470174f5640",
+            "emissionAmount": 1000,
+            "name": "Test",
+            "description": "From index",
+            "decimals": 2
+        });
+        mock.respond("GET", "/blockchain/token/byId/*", 200, &token.to_string());
+
+        let info = node.token_info(&TOKEN_ID.to_string()).unwrap();
+        assert_eq!(info.name.as_deref(), Some("Test"));
+        assert_eq!(info.description.as_deref(), Some("From index"));
+        assert_eq!(info.decimals, Some(2));
+        let requests = mock.requests();
+        assert!(requests.iter().any(|r| r.path.ends_with(TOKEN_ID)));
+        assert!(!requests.iter().any(|r| r.path.contains("byId/45482d60")));
+    }
+
+    #[test]
+    fn test_mock_token_info_from_issuance_box() {
+        use crate::test_fixtures::{test_box_with, test_tree, TEST_ADDRESS};
+        use ergo_lib::ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+        use ergo_lib::ergotree_ir::mir::constant::Constant;
+        use std::convert::TryFrom;
+
+        let mock = indexer_mock();
+        let node = mock.node_interface();
+        let registers = NonMandatoryRegisters::try_from(vec![
+            Constant::from(b"Test".to_vec()),
+            Constant::from(b"From box".to_vec()),
+            Constant::from(b"2".to_vec()),
+        ])
+        .unwrap();
+        let issuance_box = test_box_with(1000000, &test_tree(), None, registers, 1000000, 0);
+        let box_id = String::from(issuance_box.box_id());
+        let token = serde_json::json!({
+            "id": TOKEN_ID,
+            "boxId": box_id,
+            "emissionAmount": 1000,
+            "name": null,
+            "description": null,
+            "decimals": null
+        });
+        mock.respond("GET", "/blockchain/token/byId/*", 200, &token.to_string());
+        // The issuance box is spent, so it is only found in the index
+        let mut indexed_box = serde_json::to_value(&issuance_box).unwrap();
+        indexed_box["address"] = serde_json::json!(TEST_ADDRESS);
+        indexed_box["inclusionHeight"] = serde_json::json!(1000000);
+        mock.respond(
+            "GET",
+            "/blockchain/box/byId/*",
+            200,
+            &indexed_box.to_string(),
+        );
+
+        let info = node.token_info(&TOKEN_ID.to_string()).unwrap();
+        assert_eq!(info.name.as_deref(), Some("Test"));
+        assert_eq!(info.description.as_deref(), Some("From box"));
+        assert_eq!(info.decimals, Some(2));
+        let requests = mock.requests();
+        assert!(requests
+            .iter()
+            .any(|r| r.path == format!("/utxo/byId/{box_id}")));
+        assert!(requests
+            .iter()
+            .any(|r| r.path == format!("/blockchain/box/byId/{box_id}")));
+    }
+
+    #[test]
+    fn test_mock_lease_unspent_boxes() {
+        let mock = MockNode::start().unwrap();
+        let node = mock.node_interface();
+        let duration = std::time::Duration::from_secs(60);
+        let leased = node
+            .lease_unspent_boxes_with_min_total(1_500_000_000, duration)
+            .unwrap();
+        assert_eq!(leased.len(), 1);
+        assert!(node.box_leases.is_leased(&leased[0].box_id()));
+        // The leased box is skipped by the next selection
+        let next = node
+            .lease_unspent_boxes_with_min_total(1_000_000_000, duration)
+            .unwrap();
+        assert_ne!(next[0].box_id(), leased[0].box_id());
+
+        mock.respond("GET", "/wallet/boxes/unspent", 200, "[]");
+        assert!(matches!(
+            node.lease_unspent_boxes_with_min_total(1, duration),
+            Err(NodeError::InsufficientErgsBalance())
+        ));
+    }
+
+    #[test]
+    fn test_mock_capabilities_after_lost_connection() {
+        let mock = MockNode::start().unwrap();
+        let node = mock.node_interface();
+        // A clone shares the session, so its failed request records the loss
+        let mut offline = node.clone();
+        offline.url = reqwest::Url::parse("http://127.0.0.1:1").unwrap();
+        assert!(matches!(
+            offline.current_block_height(),
+            Err(NodeError::NodeUnreachable)
+        ));
+
+        let capabilities = node.capabilities().unwrap();
+        assert!(!capabilities.indexer);
+        assert_eq!(node.capabilities().unwrap(), capabilities);
+    }
+
+    #[test]
+    fn test_mock_query_backend_falls_back_to_scans() {
+        use crate::query_backend::{QueryBackend, QueryBackendKind};
+        use crate::test_fixtures::TEST_ADDRESS;
+
+        let mock = MockNode::start().unwrap();
+        let node = mock.node_interface();
+        let mut backend = QueryBackend::with_kind(QueryBackendKind::Indexer, &node);
+
+        let boxes = backend.unspent_boxes_by_address(&TEST_ADDRESS.to_string());
+        assert!(boxes.is_ok());
+        assert_eq!(backend.kind, QueryBackendKind::Scans);
+        let requests = mock.requests();
+        assert!(requests
+            .iter()
+            .any(|r| r.path.starts_with("/scan/unspentBoxes/")));
+    }
+
+    #[test]
+    fn test_mock_query_backend_keeps_indexer_on_not_found() {
+        use crate::query_backend::{QueryBackend, QueryBackendKind};
+
+        let mock = indexer_mock();
+        mock.respond(
+            "GET",
+            "/blockchain/box/unspent/byTokenId/*",
+            404,
+            &not_found_body("token not found"),
+        );
+        let node = mock.node_interface();
+        let mut backend = QueryBackend::with_kind(QueryBackendKind::Indexer, &node);
+
+        let boxes = backend.unspent_boxes_by_token(&TOKEN_ID.to_string());
+        assert!(matches!(boxes, Err(e) if e.is_not_found()));
+        assert_eq!(backend.kind, QueryBackendKind::Indexer);
+    }
+
+    #[test]
+    fn test_mock_request_with_change_signs_raw_inputs() {
+        use crate::test_fixtures::{test_box, test_tree, TEST_ADDRESS};
+        use crate::tx_request::TxRequestBuilder;
+        use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+        use ergo_lib::chain::transaction::Transaction;
+        use ergo_lib::ergotree_interpreter::sigma_protocol::prover::ProofBytes;
+        use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+
+        let mock = MockNode::start().unwrap();
+        let node = mock.node_interface();
+        let input = test_box(2000000, &test_tree(), 100, 0);
+        let data_input = test_box(1000000, &test_tree(), 100, 1);
+        let raw = |b: &ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox| {
+            base16::encode_lower(&b.sigma_serialize_bytes().unwrap())
+        };
+        let unsigned_tx: UnsignedTransaction = serde_json::from_value(serde_json::json!({
+            "inputs": [{ "boxId": String::from(input.box_id()), "extension": {} }],
+            "dataInputs": [{ "boxId": String::from(data_input.box_id()) }],
+            "outputs": [{
+                "value": 1000000,
+                "ergoTree": test_tree().to_base16_bytes().unwrap(),
+                "assets": [],
+                "additionalRegisters": {},
+                "creationHeight": 100
+            }]
+        }))
+        .unwrap();
+        let signed_tx =
+            Transaction::from_unsigned_tx(unsigned_tx.clone(), vec![ProofBytes::Empty]).unwrap();
+        let unsigned_json = serde_json::to_string(&unsigned_tx).unwrap();
+        mock.respond(
+            "POST",
+            "/wallet/transaction/generateUnsigned",
+            200,
+            &unsigned_json,
+        );
+        let signed_json = serde_json::to_string(&signed_tx).unwrap();
+        mock.respond("POST", "/wallet/transaction/sign", 200, &signed_json);
+
+        let tx_request = TxRequestBuilder::new()
+            .with_raw_inputs(vec![raw(&input)])
+            .with_raw_data_inputs(vec![raw(&data_input)])
+            .with_change_address(&TEST_ADDRESS.to_string());
+        node.generate_transaction_from_request(&tx_request).unwrap();
+
+        let requests = mock.requests();
+        let sign = requests
+            .iter()
+            .find(|r| r.path == "/wallet/transaction/sign")
+            .unwrap();
+        let sign: serde_json::Value = serde_json::from_str(&sign.body).unwrap();
+        assert_eq!(sign["inputsRaw"], serde_json::json!([raw(&input)]));
+        assert_eq!(sign["dataInputsRaw"], serde_json::json!([raw(&data_input)]));
+    }
+
+    #[test]
+    fn test_mock_check_transaction_errors() {
+        use crate::test_fixtures::{test_box, test_tree};
+        use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+        use ergo_lib::chain::transaction::Transaction;
+        use ergo_lib::ergotree_interpreter::sigma_protocol::prover::ProofBytes;
+
+        let mock = MockNode::start().unwrap();
+        let node = mock.node_interface();
+        let input = test_box(2000000, &test_tree(), 100, 0);
+        let unsigned_tx: UnsignedTransaction = serde_json::from_value(serde_json::json!({
+            "inputs": [{ "boxId": String::from(input.box_id()), "extension": {} }],
+            "dataInputs": [],
+            "outputs": [{
+                "value": 1000000,
+                "ergoTree": test_tree().to_base16_bytes().unwrap(),
+                "assets": [],
+                "additionalRegisters": {},
+                "creationHeight": 100
+            }]
+        }))
+        .unwrap();
+        let tx = Transaction::from_unsigned_tx(unsigned_tx, vec![ProofBytes::Empty]).unwrap();
+
+        let double_spend =
+            r#"{"error": 400, "reason": "bad.request", "detail": "Double spending attempt"}"#;
+        mock.respond("POST", "/transactions/check", 400, double_spend);
+        assert!(matches!(
+            node.check_transaction(&tx),
+            Err(NodeError::InvalidTransaction(_))
+        ));
+
+        let unavailable = r#"{"error": 503, "reason": "service-unavailable"}"#;
+        mock.respond("POST", "/transactions/check", 503, unavailable);
+        assert!(matches!(
+            node.check_transaction(&tx),
+            Err(NodeError::Api(e)) if e.error == 503
+        ));
+    }
+}
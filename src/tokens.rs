@@ -0,0 +1,168 @@
+//! Token-related helpers which aggregate the tokens held within the node wallet
+//! and look up the EIP-4 metadata (name, description, decimals) of any token
+//! on chain using the blockchain index, falling back to the registers of the
+//! issuance box when the index lacks them.
+use crate::node_interface::{encode_param, NodeInterface, Result};
+use crate::TokenID;
+use ergo_lib::ergotree_ir::chain::ergo_box::{BoxId, ErgoBox, NonMandatoryRegisterId};
+use ergo_lib::ergotree_ir::mir::constant::TryExtractInto;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Metadata of a token following EIP-4, as stored in the registers of the
+/// box which issued it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    #[serde(rename = "id")]
+    pub token_id: TokenID,
+    /// Id of the box which issued the token
+    pub box_id: String,
+    /// Total amount of the token issued
+    pub emission_amount: u64,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub decimals: Option<u32>,
+}
+
+impl TokenInfo {
+    /// Fills in any metadata missing from the index by decoding the EIP-4
+    /// registers (R4 name, R5 description, R6 decimals) of the issuance box
+    fn fill_from_issuance_box(&mut self, issuance_box: &ErgoBox) {
+        let register_string = |id| {
+            let bytes: Vec<u8> = issuance_box
+                .additional_registers
+                .get_constant(id)
+                .ok()??
+                .try_extract_into()
+                .ok()?;
+            String::from_utf8(bytes).ok()
+        };
+        if self.name.is_none() {
+            self.name = register_string(NonMandatoryRegisterId::R4);
+        }
+        if self.description.is_none() {
+            self.description = register_string(NonMandatoryRegisterId::R5);
+        }
+        if self.decimals.is_none() {
+            self.decimals =
+                register_string(NonMandatoryRegisterId::R6).and_then(|d| d.parse().ok());
+        }
+    }
+}
+
+/// The total amount of a single token held across all unspent boxes
+/// of the node wallet, together with the boxes holding it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenInventoryEntry {
+    pub token_id: TokenID,
+    pub amount: u64,
+    /// Number of decimals of the token if its metadata could be resolved
+    pub decimals: Option<u32>,
+    /// Name of the token if its metadata could be resolved
+    pub name: Option<String>,
+    pub boxes: Vec<BoxId>,
+}
+
+impl NodeInterface {
+    /// Aggregates all tokens held across the unspent boxes of the node wallet.
+    /// Token names and decimals are resolved via the node when available.
+    pub fn wallet_token_inventory(&self) -> Result<Vec<TokenInventoryEntry>> {
+        let mut inventory: BTreeMap<TokenID, TokenInventoryEntry> = BTreeMap::new();
+        for b in self.unspent_boxes()? {
+            for token in b.tokens.iter().flat_map(|tokens| tokens.iter()) {
+                let token_id: TokenID = String::from(token.token_id);
+                let entry =
+                    inventory
+                        .entry(token_id.clone())
+                        .or_insert_with(|| TokenInventoryEntry {
+                            token_id,
+                            amount: 0,
+                            decimals: None,
+                            name: None,
+                            boxes: vec![],
+                        });
+                entry.amount += u64::from(token.amount);
+                entry.boxes.push(b.box_id());
+            }
+        }
+
+        for entry in inventory.values_mut() {
+            if let Ok((name, decimals)) = self.token_name_and_decimals(&entry.token_id) {
+                entry.name = name;
+                entry.decimals = decimals;
+            }
+        }
+        Ok(inventory.into_values().collect())
+    }
+
+    /// Get the EIP-4 metadata of a token using the node's blockchain index.
+    /// Metadata missing from the index is decoded from the issuance box.
+    pub fn token_info(&self, token_id: &TokenID) -> Result<TokenInfo> {
+        self.require_indexer()?;
+        let endpoint = "/blockchain/token/byId/".to_string() + &encode_param(token_id);
+        let mut info: TokenInfo = self.get_typed(&endpoint)?;
+        if info.name.is_none() || info.description.is_none() || info.decimals.is_none() {
+            if let Ok(issuance_box) = self.box_from_id(&info.box_id) {
+                info.fill_from_issuance_box(&issuance_box);
+            } else if let Ok(indexed) = self.indexed_box_by_id(&info.box_id) {
+                info.fill_from_issuance_box(&indexed.ergo_box);
+            }
+        }
+        Ok(info)
+    }
+
+    /// Resolves the name and decimals of a token using the node's
+    /// blockchain index.
+    pub(crate) fn token_name_and_decimals(
+        &self,
+        token_id: &TokenID,
+    ) -> Result<(Option<String>, Option<u32>)> {
+        let info = self.token_info(token_id)?;
+        Ok((info.name, info.decimals))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_token_info_from_issuance_box() {
+        let token_id = "08b59b14e4fdd60e5952314adbaa8b4e00bc0f0b676872a5224d3bf8591074cd";
+        let box_id = "45482d60f7372105d2a8091c88ac486d33c8b4b3fddd8d7d0c5df470174f5640";
+        let mut info: TokenInfo = serde_json::from_value(json!({
+            "id": token_id,
+            "boxId": box_id,
+            "emissionAmount": 1000,
+            "name": null,
+            "description": "From index",
+            "decimals": null
+        }))
+        .unwrap();
+        let issuance_box: ErgoBox = serde_json::from_value(json!({
+            "boxId": "db9710a0e433ab1cf06a17fe8c65633f6b39cc2763674f108e7296c48c7a4e85",
+            "value": 1000000,
+            "ergoTree": "0008cd02764ea2b0b9b06b5730a4257bba71fd7797eb1ec12bc3ae6025a01d7fba53830e",
+            "assets": [{"tokenId": token_id, "amount": 1000}],
+            "additionalRegisters": {
+                "R4": "0e0454657374",
+                "R5": "0e0444657363",
+                "R6": "0e0132"
+            },
+            "creationHeight": 1000000,
+            "transactionId": "0000000000000000000000000000000000000000000000000000000000000000",
+            "index": 0
+        }))
+        .unwrap();
+        info.fill_from_issuance_box(&issuance_box);
+        assert_eq!(info.name.as_deref(), Some("Test"));
+        assert_eq!(info.description.as_deref(), Some("From index"));
+        assert_eq!(info.decimals, Some(2));
+        assert_eq!(info.emission_amount, 1000);
+    }
+}
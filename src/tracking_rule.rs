@@ -0,0 +1,178 @@
+//! A typed representation of the scanning predicates used by the node to
+//! decide which boxes are tracked by a scan, together with a small builder
+//! so tracking rules no longer have to be written as raw JSON.
+use crate::node_interface::{NodeError, Result};
+use crate::scanning::Scan;
+use crate::TokenID;
+use ergo_lib::ergotree_ir::mir::constant::Constant;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A register of a box which a tracking rule can match against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ScanRegister {
+    R0,
+    /// The register holding the `ErgoTree` of a box
+    #[default]
+    R1,
+    R2,
+    R3,
+    R4,
+    R5,
+    R6,
+    R7,
+    R8,
+    R9,
+}
+
+/// A scanning predicate as understood by `/scan/register`. Values are
+/// hex-encoded serialized constants.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "predicate", rename_all = "camelCase")]
+pub enum TrackingRule {
+    /// Matches boxes holding the given token
+    ContainsAsset {
+        #[serde(rename = "assetId")]
+        asset_id: TokenID,
+    },
+    /// Matches boxes where the register contains the given bytes
+    Contains {
+        #[serde(default)]
+        register: ScanRegister,
+        value: String,
+    },
+    /// Matches boxes where the register equals the given value
+    Equals {
+        #[serde(default)]
+        register: ScanRegister,
+        value: String,
+    },
+    /// Matches boxes which are matched by all of the rules
+    And { args: Vec<TrackingRule> },
+    /// Matches boxes which are matched by any of the rules
+    Or { args: Vec<TrackingRule> },
+}
+
+impl TrackingRule {
+    /// Matches boxes holding the given token
+    pub fn contains_asset(token_id: &TokenID) -> TrackingRule {
+        TrackingRule::ContainsAsset {
+            asset_id: token_id.clone(),
+        }
+    }
+
+    /// Matches boxes where `register` contains the hex-encoded bytes
+    pub fn contains(register: ScanRegister, value: &str) -> TrackingRule {
+        TrackingRule::Contains {
+            register,
+            value: value.to_string(),
+        }
+    }
+
+    /// Matches boxes where `register` equals the hex-encoded serialized constant
+    pub fn equals(register: ScanRegister, value: &str) -> TrackingRule {
+        TrackingRule::Equals {
+            register,
+            value: value.to_string(),
+        }
+    }
+
+    /// Matches boxes where `register` equals the given `Constant`
+    pub fn equals_constant(register: ScanRegister, value: &Constant) -> Result<TrackingRule> {
+        let bytes = value
+            .sigma_serialize_bytes()
+            .map_err(|e| NodeError::Other(e.to_string()))?;
+        Ok(TrackingRule::equals(
+            register,
+            &base16::encode_lower(&bytes),
+        ))
+    }
+
+    /// Matches boxes protected by the `ErgoTree` of the given address
+    /// (P2PK or P2S)
+    pub fn address(address: &str) -> Result<TrackingRule> {
+        Ok(TrackingRule::equals(
+            ScanRegister::R1,
+            &Scan::serialize_address_for_tracking(address)?,
+        ))
+    }
+
+    /// Matches boxes which are matched by all of the rules
+    pub fn all(rules: Vec<TrackingRule>) -> TrackingRule {
+        TrackingRule::And { args: rules }
+    }
+
+    /// Matches boxes which are matched by any of the rules
+    pub fn any(rules: Vec<TrackingRule>) -> TrackingRule {
+        TrackingRule::Or { args: rules }
+    }
+
+    /// Combines the rule with `other`, matching boxes matched by both
+    pub fn and(self, other: TrackingRule) -> TrackingRule {
+        match self {
+            TrackingRule::And { mut args } => {
+                args.push(other);
+                TrackingRule::And { args }
+            }
+            rule => TrackingRule::all(vec![rule, other]),
+        }
+    }
+
+    /// Combines the rule with `other`, matching boxes matched by either
+    pub fn or(self, other: TrackingRule) -> TrackingRule {
+        match self {
+            TrackingRule::Or { mut args } => {
+                args.push(other);
+                TrackingRule::Or { args }
+            }
+            rule => TrackingRule::any(vec![rule, other]),
+        }
+    }
+
+    /// The JSON representation of the rule expected by the node
+    pub fn to_json(&self) -> Result<Value> {
+        serde_json::to_value(self)
+            .map_err(|e| NodeError::Other(format!("Failed serializing tracking rule: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_tracking_rule_json() {
+        let token_id = "08b59b14e4fdd60e5952314adbaa8b4e00bc0f0b676872a5224d3bf8591074cd";
+        let rule = TrackingRule::contains_asset(&token_id.to_string())
+            .and(TrackingRule::equals(ScanRegister::R4, "0e01ab"))
+            .and(TrackingRule::contains(ScanRegister::R1, "cd").or(TrackingRule::any(vec![])));
+        let expected = json!({
+            "predicate": "and",
+            "args": [
+                {"predicate": "containsAsset", "assetId": token_id},
+                {"predicate": "equals", "register": "R4", "value": "0e01ab"},
+                {"predicate": "or", "args": [
+                    {"predicate": "contains", "register": "R1", "value": "cd"},
+                    {"predicate": "or", "args": []}
+                ]}
+            ]
+        });
+        assert_eq!(rule.to_json().unwrap(), expected);
+        assert_eq!(
+            serde_json::from_value::<TrackingRule>(expected).unwrap(),
+            rule
+        );
+
+        let without_register = json!({"predicate": "equals", "value": "0e01ab"});
+        assert_eq!(
+            serde_json::from_value::<TrackingRule>(without_register).unwrap(),
+            TrackingRule::equals(ScanRegister::R1, "0e01ab")
+        );
+        assert_eq!(
+            TrackingRule::equals_constant(ScanRegister::R4, &Constant::from(1i32)).unwrap(),
+            TrackingRule::equals(ScanRegister::R4, "0402")
+        );
+    }
+}
@@ -1,15 +1,31 @@
-use std::convert::TryFrom;
+use std::convert::{TryFrom, TryInto};
 
+use crate::journal::TxStatus;
 use crate::node_interface::{NodeError, NodeInterface, Result};
-use crate::JsonString;
+use crate::{BlockDuration, JsonString, NanoErg};
+use ergo_lib::chain::ergo_state_context::{ErgoStateContext, Headers};
 use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
 use ergo_lib::chain::transaction::{Transaction, TxId};
-use ergo_lib::ergo_chain_types::Digest32;
+use ergo_lib::ergo_chain_types::{Digest32, Header, PreHeader};
+use ergo_lib::ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+use ergo_lib::ergotree_ir::chain::ergo_box::box_value::BoxValue;
 use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+use ergo_lib::ergotree_ir::mir::constant::Constant;
 use ergo_lib::ergotree_ir::serialization::{SigmaSerializable, SigmaSerializationError};
+use ergo_lib::wallet::miner_fee::MINERS_FEE_BASE16_BYTES;
+use ergo_lib::wallet::secret_key::SecretKey;
 use ergo_lib::wallet::signing::TransactionContext;
-use json::JsonValue;
-use serde_json::json;
+use ergo_lib::wallet::Wallet;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+/// Maximum number of times the fee is bumped by `submit_with_deadline`
+const MAX_FEE_BUMPS: usize = 3;
+/// Interval between inclusion checks performed by `submit_with_deadline`
+const DEADLINE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// Interval between checks performed by `wait_for_tx_confirmation`
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(10);
 
 impl NodeInterface {
     /// Submits a Signed Transaction provided as input as JSON
@@ -17,21 +33,40 @@ impl NodeInterface {
     pub fn submit_json_transaction(&self, signed_tx_json: &JsonString) -> Result<TxId> {
         let endpoint = "/transactions";
         let res_json = self.use_json_endpoint_and_check_errors(endpoint, signed_tx_json)?;
-        let tx_id = parse_tx_id_unsafe(res_json);
+        let tx_id = parse_tx_id(&res_json)?;
+        self.journal_tx_status(&String::from(tx_id), TxStatus::Submitted, None);
+        Ok(tx_id)
+    }
+
+    /// Submits a Signed Transaction provided as hex-encoded sigma-serialized
+    /// bytes to the Ergo Blockchain mempool.
+    pub fn submit_serialized_transaction(&self, signed_tx_hex: &str) -> Result<TxId> {
+        let endpoint = "/transactions/bytes";
+        let body = json!(signed_tx_hex).to_string();
+        let res_json = self.use_json_endpoint_and_check_errors(endpoint, &body)?;
+        let tx_id = parse_tx_id(&res_json)?;
+        self.journal_tx_status(&String::from(tx_id), TxStatus::Submitted, None);
         Ok(tx_id)
     }
 
+    /// Submits a Signed Transaction provided as sigma-serialized bytes
+    /// to the Ergo Blockchain mempool.
+    pub fn submit_transaction_bytes(&self, signed_tx_bytes: &[u8]) -> Result<TxId> {
+        self.submit_serialized_transaction(&base16::encode_lower(signed_tx_bytes))
+    }
+
     /// Sign an Unsigned Transaction which is formatted in JSON
-    pub fn sign_json_transaction(&self, unsigned_tx_string: &JsonString) -> Result<JsonValue> {
+    pub fn sign_json_transaction(&self, unsigned_tx_string: &JsonString) -> Result<Value> {
         let endpoint = "/wallet/transaction/sign";
-        let unsigned_tx_json = json::parse(unsigned_tx_string)
+        let unsigned_tx_json: Value = serde_json::from_str(unsigned_tx_string)
             .map_err(|_| NodeError::FailedParsingNodeResponse(unsigned_tx_string.to_string()))?;
 
-        let prepared_body = object! {
-            tx: unsigned_tx_json
-        };
+        let prepared_body = json!({
+            "tx": unsigned_tx_json
+        });
 
-        let res_json = self.use_json_endpoint_and_check_errors(endpoint, &prepared_body.dump())?;
+        let res_json =
+            self.use_json_endpoint_and_check_errors(endpoint, &prepared_body.to_string())?;
 
         Ok(res_json)
     }
@@ -43,7 +78,7 @@ impl NodeInterface {
         unsigned_tx_string: &JsonString,
     ) -> Result<TxId> {
         let signed_tx = self.sign_json_transaction(unsigned_tx_string)?;
-        let signed_tx_json = json::stringify(signed_tx);
+        let signed_tx_json = signed_tx.to_string();
 
         self.submit_json_transaction(&signed_tx_json)
     }
@@ -51,13 +86,47 @@ impl NodeInterface {
     /// Submits a Signed `Transaction` provided as input
     /// to the Ergo Blockchain mempool.
     pub fn submit_transaction(&self, signed_tx: &Transaction) -> Result<TxId> {
+        let result = self.post_transaction(signed_tx);
+        match &result {
+            Ok(tx_id) => self.journal_tx_status(&String::from(*tx_id), TxStatus::Submitted, None),
+            Err(e) => self.journal_failed_submission(&String::from(signed_tx.id()), e),
+        }
+        result
+    }
+
+    /// Submits a Signed `Transaction` to the mempool without recording it
+    /// in the tx journal
+    pub(crate) fn post_transaction(&self, signed_tx: &Transaction) -> Result<TxId> {
         let signed_tx_json = &serde_json::to_string(&signed_tx)
             .map_err(|_| NodeError::Other("Failed Converting `Transaction` to json".to_string()))?;
-        let tx_id = self.submit_json_transaction(signed_tx_json)?;
-        assert_eq!(tx_id, signed_tx.id());
+        let res_json = self.use_json_endpoint_and_check_errors("/transactions", signed_tx_json)?;
+        let tx_id = parse_tx_id(&res_json)?;
+        if tx_id != signed_tx.id() {
+            return Err(NodeError::TxIdMismatch(
+                String::from(signed_tx.id()),
+                String::from(tx_id),
+            ));
+        }
         Ok(tx_id)
     }
 
+    /// Checks whether a Signed `Transaction` is valid against the current
+    /// state of the node (UTXO set and mempool) without broadcasting it.
+    /// Returns the `TxId` if valid, otherwise `NodeError::InvalidTransaction`
+    /// with the reason provided by the node. Other failures of the request
+    /// are returned as is.
+    pub fn check_transaction(&self, signed_tx: &Transaction) -> Result<TxId> {
+        let body = serde_json::to_value(signed_tx)
+            .map_err(|_| NodeError::Other("Failed Converting `Transaction` to json".to_string()))?;
+        let res_json = match self.post_json("/transactions/check", &body) {
+            Err(NodeError::Api(e)) if e.error == 400 => {
+                return Err(NodeError::InvalidTransaction(e.message().to_string()))
+            }
+            res => res?,
+        };
+        parse_tx_id(&res_json)
+    }
+
     /// Sign an `UnsignedTransaction`
     /// unsigned_tx - The unsigned transaction to sign.
     /// boxes_to_spend - optional list of input boxes. If not provided, the node will search for the boxes in UTXO
@@ -67,6 +136,20 @@ impl NodeInterface {
         unsigned_tx: &UnsignedTransaction,
         boxes_to_spend: Option<Vec<ErgoBox>>,
         data_input_boxes: Option<Vec<ErgoBox>>,
+    ) -> Result<Transaction> {
+        self.sign_transaction_with_hints(unsigned_tx, boxes_to_spend, data_input_boxes, None)
+    }
+
+    /// Sign an `UnsignedTransaction` using the node wallet together with the
+    /// `hints` of other signers, i.e. a transaction hints bag in the node's
+    /// JSON format as returned by `generate_commitments` and `extract_hints`.
+    /// Used to take part in a multi-party signing flow.
+    pub fn sign_transaction_with_hints(
+        &self,
+        unsigned_tx: &UnsignedTransaction,
+        boxes_to_spend: Option<Vec<ErgoBox>>,
+        data_input_boxes: Option<Vec<ErgoBox>>,
+        hints: Option<&Value>,
     ) -> Result<Transaction> {
         if let Some(ref boxes_to_spend) = boxes_to_spend {
             // check input boxes against tx's inputs (for every input should be a box)
@@ -81,38 +164,70 @@ impl NodeInterface {
 
         let endpoint = "/wallet/transaction/sign";
 
-        fn encode_boxes(
-            maybe_boxes: Option<Vec<ErgoBox>>,
-        ) -> std::result::Result<Option<Vec<String>>, NodeError> {
-            match maybe_boxes.map(|boxes| {
-                boxes
-                    .iter()
-                    .map(|b| {
-                        b.sigma_serialize_bytes()
-                            .map(|bytes| base16::encode_lower(&bytes))
-                    })
-                    .collect::<std::result::Result<Vec<String>, SigmaSerializationError>>()
-            }) {
-                Some(Ok(base16_boxes)) => Ok(Some(base16_boxes)),
-                Some(Err(e)) => Err(NodeError::Other(e.to_string())),
-                None => Ok(None),
-            }
-        }
-
         let input_boxes_base16 = encode_boxes(boxes_to_spend)?;
         let data_input_boxes_base16 = encode_boxes(data_input_boxes)?;
 
-        let prepared_body = json!({
+        let mut prepared_body = json!({
             "tx": unsigned_tx,
             "inputsRaw": input_boxes_base16,
             "dataInputsRaw": data_input_boxes_base16,
         });
+        if let Some(hints) = hints {
+            prepared_body["hints"] = hints.clone();
+        }
 
         let json_signed_tx =
             self.use_json_endpoint_and_check_errors(endpoint, &prepared_body.to_string())?;
 
-        serde_json::from_str(&json_signed_tx.dump())
-            .map_err(|_| NodeError::Other("Failed Converting `Transaction` to json".to_string()))
+        let signed_tx: Transaction = serde_json::from_value(json_signed_tx)
+            .map_err(|_| NodeError::Other("Failed Converting `Transaction` to json".to_string()))?;
+        self.journal_tx_status(&String::from(signed_tx.id()), TxStatus::Signed, None);
+        Ok(signed_tx)
+    }
+
+    /// Acquires the last 10 block headers from the node and builds the
+    /// `ErgoStateContext` required for signing transactions with ergo-lib.
+    pub fn state_context(&self) -> Result<ErgoStateContext> {
+        let mut headers = self.last_headers(10)?;
+        // The node returns the oldest header first, ergo-lib expects the latest first
+        headers.reverse();
+        let headers: Headers = headers.try_into().map_err(|h: Vec<Header>| {
+            NodeError::FailedParsingNodeResponse(format!(
+                "Expected 10 block headers, found {}",
+                h.len()
+            ))
+        })?;
+        let pre_header = PreHeader::from(headers[0].clone());
+        Ok(ErgoStateContext::new(pre_header, headers))
+    }
+
+    /// Sign an `UnsignedTransaction` client-side using the provided secrets,
+    /// without loading any keys into the node wallet. The input and data input
+    /// boxes (confirmed or in the mempool) and the state context are acquired
+    /// from the node.
+    pub fn sign_locally(
+        &self,
+        unsigned_tx: &UnsignedTransaction,
+        secrets: Vec<SecretKey>,
+    ) -> Result<Transaction> {
+        let mut boxes_to_spend = vec![];
+        for input in unsigned_tx.inputs.iter() {
+            boxes_to_spend.push(self.box_from_id_with_mempool(&input.box_id.into())?);
+        }
+        let mut data_input_boxes = vec![];
+        for data_input in unsigned_tx.data_inputs.iter().flat_map(|d| d.iter()) {
+            data_input_boxes.push(self.box_from_id_with_mempool(&data_input.box_id.into())?);
+        }
+        let tx_context =
+            TransactionContext::new(unsigned_tx.clone(), boxes_to_spend, data_input_boxes)
+                .map_err(|e| NodeError::Other(e.to_string()))?;
+
+        let wallet = Wallet::from_secrets(secrets);
+        let signed_tx = wallet
+            .sign_transaction(tx_context, &self.state_context()?, None)
+            .map_err(|e| NodeError::Other(e.to_string()))?;
+        self.journal_tx_status(&String::from(signed_tx.id()), TxStatus::Signed, None);
+        Ok(signed_tx)
     }
 
     /// Sign an `UnsignedTransaction` and then submit it to the mempool.
@@ -121,6 +236,145 @@ impl NodeInterface {
         self.submit_transaction(&signed_tx)
     }
 
+    /// Signs and submits an `UnsignedTransaction`, then waits for it to be
+    /// included in a block. If the tx is not mined within `max_wait_blocks`
+    /// the fee is doubled (taking the difference from the wallet change output)
+    /// and the tx respending the same inputs is signed and resubmitted.
+    /// Returns the `TxId` of the transaction which got mined. Failed
+    /// resubmissions and failed checks while waiting are retried, keeping
+    /// the previously submitted txs in place.
+    pub fn submit_with_deadline(
+        &self,
+        unsigned_tx: &UnsignedTransaction,
+        max_wait_blocks: BlockDuration,
+    ) -> Result<TxId> {
+        let wallet_trees: Vec<ErgoTree> = self
+            .wallet_address_trees()?
+            .into_iter()
+            .map(|(_, tree)| tree)
+            .collect();
+        let first_input: String = unsigned_tx.inputs.first().box_id.into();
+
+        let mut current_tx = unsigned_tx.clone();
+        let mut submitted_tx_ids = vec![self.sign_and_submit_transaction(&current_tx)?];
+        let mut height = self.current_block_height()?;
+        for attempt in 0..=MAX_FEE_BUMPS {
+            if attempt > 0 {
+                let fee = tx_fee(&current_tx);
+                let bumped = bump_fee(&current_tx, &wallet_trees, fee.max(1)).and_then(|tx| {
+                    let tx_id = self.sign_and_submit_transaction(&tx)?;
+                    Ok((tx, tx_id))
+                });
+                if let Ok((tx, tx_id)) = bumped {
+                    current_tx = tx;
+                    submitted_tx_ids.push(tx_id);
+                }
+            }
+
+            let deadline = height + max_wait_blocks;
+            while height <= deadline {
+                // Once the shared inputs are spent one of the submitted txs was mined
+                if let Ok(true) = self.box_spent(&first_input) {
+                    match self.mined_wallet_transaction(&submitted_tx_ids) {
+                        Ok(Some(tx_id)) => {
+                            self.journal_tx_status(&String::from(tx_id), TxStatus::Confirmed, None);
+                            return Ok(tx_id);
+                        }
+                        Ok(None) => {
+                            return Err(NodeError::Other(
+                                "Transaction inputs were spent by another transaction.".to_string(),
+                            ))
+                        }
+                        Err(_) => (),
+                    }
+                }
+                std::thread::sleep(DEADLINE_POLL_INTERVAL);
+                height = self.current_block_height().unwrap_or(height);
+            }
+        }
+        Err(NodeError::Other(format!(
+            "Transaction was not mined after {MAX_FEE_BUMPS} fee bumps."
+        )))
+    }
+
+    /// Checks whether a box has left the UTXO-set. Only a not found
+    /// response counts as spent, any other error is returned.
+    fn box_spent(&self, box_id: &String) -> Result<bool> {
+        match self.box_from_id(box_id) {
+            Ok(_) => Ok(false),
+            Err(e) if e.is_not_found() => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the most recent of the given txs which the wallet has
+    /// included in a block, if any
+    fn mined_wallet_transaction(&self, tx_ids: &[TxId]) -> Result<Option<TxId>> {
+        for tx_id in tx_ids.iter().rev() {
+            let endpoint = format!("/wallet/transactionById?id={}", String::from(*tx_id));
+            match self.get_json(&endpoint) {
+                Ok(tx) if tx["inclusionHeight"].is_u64() => return Ok(Some(*tx_id)),
+                Ok(_) => (),
+                Err(e) if e.is_not_found() => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get the number of confirmations of a transaction, checking the
+    /// wallet, the mempool and (if enabled) the indexer. Returns `Some(0)`
+    /// for transactions in the mempool and `None` if the tx is not found.
+    pub fn tx_confirmations(&self, tx_id: &TxId) -> Result<Option<u64>> {
+        let id = String::from(*tx_id);
+        if let Ok(tx) = self.get_json(&format!("/wallet/transactionById?id={id}")) {
+            if let Some(confirmations) = tx["numConfirmations"].as_u64() {
+                return Ok(Some(confirmations));
+            }
+        }
+        if self.is_in_mempool(tx_id)? {
+            return Ok(Some(0));
+        }
+        if self.indexer_enabled() {
+            if let Ok(tx) = self.indexed_transaction_by_id(tx_id) {
+                let height = self.current_block_height()?;
+                return Ok(Some((height + 1).saturating_sub(tx.inclusion_height)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Blocks the current thread until the transaction is known to the node
+    /// with at least `confirmations` confirmations (so with `0` it must at
+    /// least be in the mempool), failing with `NodeError::ConfirmationTimeout`
+    /// once `timeout` has passed. Transient errors are retried until then.
+    pub fn wait_for_tx_confirmation(
+        &self,
+        tx_id: &TxId,
+        confirmations: u64,
+        timeout: Duration,
+    ) -> Result<()> {
+        let start = Instant::now();
+        loop {
+            match self.tx_confirmations(tx_id) {
+                Ok(Some(tx_confirmations)) if tx_confirmations >= confirmations => {
+                    if tx_confirmations > 0 {
+                        self.journal_tx_status(&String::from(*tx_id), TxStatus::Confirmed, None);
+                    }
+                    return Ok(());
+                }
+                Ok(_) => (),
+                Err(e) if e.is_transient() => (),
+                Err(e) => return Err(e),
+            }
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(NodeError::ConfirmationTimeout(String::from(*tx_id)));
+            }
+            std::thread::sleep(remaining.min(CONFIRMATION_POLL_INTERVAL));
+        }
+    }
+
     /// Generates and submits a tx using the node endpoints. Input is
     /// a json formatted request with rawInputs (and rawDataInputs)
     /// manually selected or inputs will be automatically selected by wallet.
@@ -128,14 +382,15 @@ impl NodeInterface {
     pub fn generate_and_submit_transaction(&self, tx_request_json: &JsonString) -> Result<TxId> {
         let endpoint = "/wallet/transaction/send";
         let res_json = self.use_json_endpoint_and_check_errors(endpoint, tx_request_json)?;
-        let tx_id = parse_tx_id_unsafe(res_json);
+        let tx_id = parse_tx_id(&res_json)?;
+        self.journal_tx_status(&String::from(tx_id), TxStatus::Submitted, None);
         Ok(tx_id)
     }
 
     /// Generates Json of an Unsigned Transaction.
     /// Input must be a json formatted request with rawInputs (and rawDataInputs)
     /// manually selected or will be automatically selected by wallet.
-    pub fn generate_json_transaction(&self, tx_request_json: &JsonString) -> Result<JsonValue> {
+    pub fn generate_json_transaction(&self, tx_request_json: &JsonString) -> Result<Value> {
         let endpoint = "/wallet/transaction/generate";
         let res_json = self.use_json_endpoint_and_check_errors(endpoint, tx_request_json)?;
 
@@ -151,14 +406,225 @@ impl NodeInterface {
             bytes, wait_time
         );
         let res = self.send_get_req(&endpoint);
-        let res_json = self.parse_response_to_json(res);
-        let fee = res_json?.as_u64().unwrap();
-        Ok(fee)
+        let res_json = self.parse_response_to_json(res)?;
+        res_json
+            .as_u64()
+            .ok_or_else(|| NodeError::FailedParsingNodeResponse(res_json.to_string()))
     }
 }
 
-fn parse_tx_id_unsafe(mut res_json: JsonValue) -> TxId {
-    // If tx is valid and is posted, return just the tx id
-    let tx_id_str = res_json.take_string().unwrap();
-    TxId(Digest32::try_from(tx_id_str).unwrap())
+/// Encodes boxes as hex-encoded serialized bytes, as expected by the
+/// `inputsRaw` and `dataInputsRaw` fields of the wallet signing endpoints
+pub(crate) fn encode_boxes(maybe_boxes: Option<Vec<ErgoBox>>) -> Result<Option<Vec<String>>> {
+    match maybe_boxes.map(|boxes| {
+        boxes
+            .iter()
+            .map(|b| {
+                b.sigma_serialize_bytes()
+                    .map(|bytes| base16::encode_lower(&bytes))
+            })
+            .collect::<std::result::Result<Vec<String>, SigmaSerializationError>>()
+    }) {
+        Some(Ok(base16_boxes)) => Ok(Some(base16_boxes)),
+        Some(Err(e)) => Err(NodeError::Other(e.to_string())),
+        None => Ok(None),
+    }
+}
+
+/// Parses a `TxId` from a JSON string returned by the node, e.g. when a tx
+/// is posted or the `id` field of a transaction
+pub(crate) fn parse_tx_id(res_json: &Value) -> Result<TxId> {
+    res_json
+        .as_str()
+        .and_then(|id| Digest32::try_from(id.to_string()).ok())
+        .map(TxId)
+        .ok_or_else(|| NodeError::FailedParsingTxId(res_json.to_string()))
+}
+
+/// Returns a copy of `unsigned_tx` with the given context extension variables
+/// (`getVar` id and value) set on the input at `input_index`. The extension is
+/// kept when the transaction is signed via `sign_transaction`.
+pub fn with_context_extension(
+    unsigned_tx: &UnsignedTransaction,
+    input_index: usize,
+    values: &[(u8, Constant)],
+) -> Result<UnsignedTransaction> {
+    let mut inputs = unsigned_tx.inputs.as_vec().clone();
+    let input = inputs.get_mut(input_index).ok_or_else(|| {
+        NodeError::Other(format!("Transaction has no input at index {input_index}."))
+    })?;
+    let mut extension = input.extension.clone();
+    for (id, value) in values {
+        extension.values.insert(*id, value.clone());
+    }
+    input.extension = extension;
+
+    UnsignedTransaction::new_from_vec(
+        inputs,
+        unsigned_tx
+            .data_inputs
+            .as_ref()
+            .map(|d| d.as_vec().clone())
+            .unwrap_or_default(),
+        unsigned_tx.output_candidates.as_vec().clone(),
+    )
+    .map_err(|e| NodeError::Other(e.to_string()))
+}
+
+/// Returns a copy of `unsigned_tx` with the context extensions of all inputs cleared
+pub fn without_context_extensions(
+    unsigned_tx: &UnsignedTransaction,
+) -> Result<UnsignedTransaction> {
+    let mut inputs = unsigned_tx.inputs.as_vec().clone();
+    for input in inputs.iter_mut() {
+        input.extension = ContextExtension::empty();
+    }
+    UnsignedTransaction::new_from_vec(
+        inputs,
+        unsigned_tx
+            .data_inputs
+            .as_ref()
+            .map(|d| d.as_vec().clone())
+            .unwrap_or_default(),
+        unsigned_tx.output_candidates.as_vec().clone(),
+    )
+    .map_err(|e| NodeError::Other(e.to_string()))
+}
+
+/// Checks whether an output is protected by the miner fee contract
+pub(crate) fn is_miner_fee_tree(tree: &ErgoTree) -> bool {
+    tree.to_base16_bytes().ok().as_deref() == Some(MINERS_FEE_BASE16_BYTES)
+}
+
+/// Sum of the values of the miner fee outputs of an `UnsignedTransaction`
+fn tx_fee(unsigned_tx: &UnsignedTransaction) -> NanoErg {
+    unsigned_tx
+        .output_candidates
+        .iter()
+        .filter(|o| is_miner_fee_tree(&o.ergo_tree))
+        .map(|o| *o.value.as_u64())
+        .sum()
+}
+
+/// Rebuilds an `UnsignedTransaction` increasing its miner fee output by
+/// `bump` nanoErgs, which are taken from the last output going back to one
+/// of the `wallet_trees` (the change output).
+fn bump_fee(
+    unsigned_tx: &UnsignedTransaction,
+    wallet_trees: &[ErgoTree],
+    bump: NanoErg,
+) -> Result<UnsignedTransaction> {
+    let mut outputs = unsigned_tx.output_candidates.as_vec().clone();
+    let fee_index = outputs
+        .iter()
+        .position(|o| is_miner_fee_tree(&o.ergo_tree))
+        .ok_or_else(|| NodeError::Other("Transaction has no miner fee output.".to_string()))?;
+    let change_index = outputs
+        .iter()
+        .rposition(|o| wallet_trees.contains(&o.ergo_tree))
+        .ok_or_else(|| NodeError::Other("Transaction has no wallet change output.".to_string()))?;
+
+    let change_value = outputs[change_index]
+        .value
+        .as_u64()
+        .checked_sub(bump)
+        .and_then(|v| BoxValue::new(v).ok())
+        .ok_or_else(|| {
+            NodeError::Other("Change output is too small to bump the fee.".to_string())
+        })?;
+    let fee_value = BoxValue::new(outputs[fee_index].value.as_u64() + bump)
+        .map_err(|e| NodeError::Other(e.to_string()))?;
+    outputs[change_index].value = change_value;
+    outputs[fee_index].value = fee_value;
+
+    UnsignedTransaction::new_from_vec(
+        unsigned_tx.inputs.as_vec().clone(),
+        unsigned_tx
+            .data_inputs
+            .as_ref()
+            .map(|d| d.as_vec().clone())
+            .unwrap_or_default(),
+        outputs,
+    )
+    .map_err(|e| NodeError::Other(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unsigned_tx() -> UnsignedTransaction {
+        let tx_json = r#"{
+          "inputs": [
+            {
+              "boxId": "e56847ed19b3dc6b72828fcfb992fdf7310828cf291221269b7ffc72fd66706e",
+              "extension": {}
+            }
+          ],
+          "dataInputs": [],
+          "outputs": [
+            {
+              "value": 1000000,
+              "ergoTree": "0008cd0327e65711a59378c59359c3e1d0f7abe906479eccb76094e50fe79d743ccc15e6",
+              "assets": [],
+              "additionalRegisters": {},
+              "creationHeight": 100
+            }
+          ]
+        }"#;
+        serde_json::from_str(tx_json).unwrap()
+    }
+
+    #[test]
+    fn test_parse_tx_id() {
+        let id = "e56847ed19b3dc6b72828fcfb992fdf7310828cf291221269b7ffc72fd66706e";
+        assert_eq!(String::from(parse_tx_id(&Value::from(id)).unwrap()), id);
+        for res_json in [Value::from("abc"), Value::from(1), Value::Null] {
+            assert!(matches!(
+                parse_tx_id(&res_json),
+                Err(NodeError::FailedParsingTxId(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_bump_fee() {
+        let change_tree =
+            "0008cd0327e65711a59378c59359c3e1d0f7abe906479eccb76094e50fe79d743ccc15e6";
+        let output = |value: u64, tree: &str| {
+            serde_json::json!({
+                "value": value,
+                "ergoTree": tree,
+                "assets": [],
+                "additionalRegisters": {},
+                "creationHeight": 100
+            })
+        };
+        let mut tx_json = serde_json::to_value(unsigned_tx()).unwrap();
+        tx_json["outputs"] = serde_json::json!([
+            output(5_000_000, change_tree),
+            output(1_100_000, MINERS_FEE_BASE16_BYTES),
+        ]);
+        let tx: UnsignedTransaction = serde_json::from_value(tx_json).unwrap();
+        let wallet_trees = vec![tx.output_candidates.first().ergo_tree.clone()];
+        assert_eq!(tx_fee(&tx), 1_100_000);
+
+        let bumped = bump_fee(&tx, &wallet_trees, tx_fee(&tx)).unwrap();
+        assert_eq!(tx_fee(&bumped), 2_200_000);
+        assert_eq!(*bumped.output_candidates.first().value.as_u64(), 3_900_000);
+        assert_eq!(bumped.inputs, tx.inputs);
+
+        assert!(bump_fee(&tx, &wallet_trees, 5_000_000).is_err());
+        assert!(bump_fee(&tx, &[], 1).is_err());
+        assert!(bump_fee(&unsigned_tx(), &wallet_trees, 1).is_err());
+    }
+
+    #[test]
+    fn test_context_extension_survives_signing_json() {
+        let tx = with_context_extension(&unsigned_tx(), 0, &[(1, Constant::from(7i32))]).unwrap();
+        assert_ne!(tx.id(), unsigned_tx().id());
+        let tx_json = serde_json::to_value(&tx).unwrap();
+        assert_eq!(tx_json["inputs"][0]["extension"]["1"], "040e");
+        assert!(with_context_extension(&tx, 1, &[]).is_err());
+    }
 }
@@ -0,0 +1,525 @@
+//! The `TxRequestBuilder` struct is defined here which builds the JSON
+//! transaction requests used by the node wallet generate/send endpoints.
+use crate::journal::TxStatus;
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use crate::transactions::parse_tx_id;
+use crate::{JsonString, NanoErg, P2PKAddressString, P2SAddressString, TokenID};
+use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+use ergo_lib::chain::transaction::{Transaction, TxId};
+use ergo_lib::ergotree_ir::chain::address::AddressEncoder;
+use ergo_lib::ergotree_ir::chain::ergo_box::{BoxId, ErgoBox};
+use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// Delay before checking whether a send which failed ambiguously went out
+const SEND_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// A token id and amount as used within node requests
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenAmount {
+    pub token_id: TokenID,
+    pub amount: u64,
+}
+
+/// A request to send nanoErgs and tokens to an address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentRequest {
+    pub address: P2SAddressString,
+    pub value: NanoErg,
+    #[serde(default)]
+    pub assets: Vec<TokenAmount>,
+    /// Hex-encoded serialized register values keyed by register name (`R4`..`R9`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub registers: BTreeMap<String, String>,
+}
+
+/// A request to issue a new token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetIssueRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<P2SAddressString>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub erg_value: Option<NanoErg>,
+    pub amount: u64,
+    pub name: String,
+    pub description: String,
+    pub decimals: u32,
+    /// Hex-encoded serialized register values keyed by register name (`R4`..`R9`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub registers: BTreeMap<String, String>,
+}
+
+/// A request to burn tokens
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BurnRequest {
+    pub assets_to_burn: Vec<TokenAmount>,
+}
+
+/// A single request within a transaction request, matching the node's
+/// payment/asset-issue/burn request schemas.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TxRequestItem {
+    Burn(BurnRequest),
+    AssetIssue(AssetIssueRequest),
+    Payment(PaymentRequest),
+}
+
+/// A builder for the transaction request JSON accepted by
+/// `generate_json_transaction`, `generate_and_submit_transaction` and
+/// `generate_unsigned_transaction`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxRequestBuilder {
+    pub requests: Vec<TxRequestItem>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<NanoErg>,
+    #[serde(default)]
+    pub inputs_raw: Vec<String>,
+    #[serde(default)]
+    pub data_inputs_raw: Vec<String>,
+    /// Change address overriding the `ChangeAddressPolicy` of the `NodeInterface`
+    #[serde(skip)]
+    pub change_address: Option<P2PKAddressString>,
+}
+
+/// The policy used to choose the wallet change address when a transaction
+/// is generated from a `TxRequestBuilder`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ChangeAddressPolicy {
+    /// Use the change address currently configured in the node wallet
+    #[default]
+    WalletChangeAddress,
+    /// Use the first address of the node wallet
+    FirstWalletAddress,
+    /// Always use the provided address
+    Fixed(P2PKAddressString),
+}
+
+impl TxRequestBuilder {
+    /// Create a new empty `TxRequestBuilder`
+    pub fn new() -> TxRequestBuilder {
+        TxRequestBuilder::default()
+    }
+
+    /// Parse a transaction request in the node's JSON format into a builder
+    pub fn from_json(tx_request_json: &str) -> Result<TxRequestBuilder> {
+        serde_json::from_str(tx_request_json)
+            .map_err(|e| NodeError::Other(format!("Invalid transaction request: {e}")))
+    }
+
+    /// Read a transaction request template in the node's JSON format from a file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<TxRequestBuilder> {
+        let tx_request_json = std::fs::read_to_string(path).map_err(|e| {
+            NodeError::Other(format!("Failed to read transaction request file: {e}"))
+        })?;
+        TxRequestBuilder::from_json(&tx_request_json)
+    }
+
+    /// Add a payment/asset-issue/burn request
+    pub fn with_request(mut self, request: TxRequestItem) -> TxRequestBuilder {
+        self.requests.push(request);
+        self
+    }
+
+    /// Set the miner fee of the transaction
+    pub fn with_fee(mut self, fee: NanoErg) -> TxRequestBuilder {
+        self.fee = Some(fee);
+        self
+    }
+
+    /// Add serialized boxes to be used as inputs
+    pub fn with_raw_inputs(mut self, inputs_raw: Vec<String>) -> TxRequestBuilder {
+        self.inputs_raw.extend(inputs_raw);
+        self
+    }
+
+    /// Add serialized boxes to be used as data inputs
+    pub fn with_raw_data_inputs(mut self, data_inputs_raw: Vec<String>) -> TxRequestBuilder {
+        self.data_inputs_raw.extend(data_inputs_raw);
+        self
+    }
+
+    /// Use the provided change address for this request, overriding the
+    /// `ChangeAddressPolicy` of the `NodeInterface`
+    pub fn with_change_address(mut self, address: &P2PKAddressString) -> TxRequestBuilder {
+        self.change_address = Some(address.clone());
+        self
+    }
+
+    /// Fetch the boxes with the given ids (including boxes which only exist
+    /// in the mempool) and add them serialized as data inputs
+    pub fn with_data_input_ids(
+        self,
+        node: &NodeInterface,
+        box_ids: &[BoxId],
+    ) -> Result<TxRequestBuilder> {
+        let mut data_inputs_raw = vec![];
+        for b in node.data_input_boxes_from_ids(box_ids)? {
+            let bytes = b
+                .sigma_serialize_bytes()
+                .map_err(|e| NodeError::Other(e.to_string()))?;
+            data_inputs_raw.push(base16::encode_lower(&bytes));
+        }
+        Ok(self.with_raw_data_inputs(data_inputs_raw))
+    }
+
+    /// Builds the transaction request as a `JsonString`
+    pub fn build(&self) -> Result<JsonString> {
+        serde_json::to_string(self)
+            .map_err(|e| NodeError::Other(format!("Failed serializing transaction request: {e}")))
+    }
+}
+
+impl NodeInterface {
+    /// Set the policy used to choose the change address of transactions
+    /// generated from a `TxRequestBuilder`
+    pub fn with_change_address_policy(mut self, policy: ChangeAddressPolicy) -> NodeInterface {
+        self.change_address_policy = policy;
+        self
+    }
+
+    /// Updates the change address of the node wallet. This is a persistent
+    /// setting of the node shared by all of its clients, use a
+    /// `ChangeAddressPolicy` to choose the change address of single requests.
+    pub fn wallet_update_change_address(&self, address: &P2PKAddressString) -> Result<()> {
+        self.check_address_network(address)?;
+        let endpoint = "/wallet/updateChangeAddress";
+        let res = self.send_post_req(endpoint, json!(address).to_string());
+        self.parse_response_to_json(res)?;
+        Ok(())
+    }
+
+    /// Generates Json of a signed transaction from a `TxRequestBuilder`,
+    /// sending its change to the change address override or the address
+    /// chosen by the `ChangeAddressPolicy`. The node wallet settings are
+    /// left untouched.
+    pub fn generate_transaction_from_request(
+        &self,
+        tx_request: &TxRequestBuilder,
+    ) -> Result<Value> {
+        match self.request_change_address(&tx_request.change_address)? {
+            Some(change_address) => {
+                let unsigned_tx =
+                    self.generate_unsigned_with_change(tx_request, &change_address)?;
+                // Raw inputs may not be in the UTXO set yet, so they are
+                // passed on for signing just as the node does when generating
+                let signed_tx = self.sign_transaction(
+                    &unsigned_tx,
+                    decode_raw_boxes(&tx_request.inputs_raw)?,
+                    decode_raw_boxes(&tx_request.data_inputs_raw)?,
+                )?;
+                serde_json::to_value(signed_tx).map_err(|e| NodeError::Other(e.to_string()))
+            }
+            None => self.generate_json_transaction(&tx_request.build()?),
+        }
+    }
+
+    /// Generates an `UnsignedTransaction` using
+    /// `/wallet/transaction/generateUnsigned` from a `TxRequestBuilder`,
+    /// sending its change to the change address override or the address
+    /// chosen by the `ChangeAddressPolicy`.
+    pub fn generate_unsigned_transaction(
+        &self,
+        tx_request: &TxRequestBuilder,
+    ) -> Result<UnsignedTransaction> {
+        match self.request_change_address(&tx_request.change_address)? {
+            Some(change_address) => self.generate_unsigned_with_change(tx_request, &change_address),
+            None => self.post_typed("/wallet/transaction/generateUnsigned", tx_request),
+        }
+    }
+
+    /// Generates and submits a transaction from a `TxRequestBuilder`,
+    /// sending its change to the change address override or the address
+    /// chosen by the `ChangeAddressPolicy`.
+    pub fn send_transaction_from_request(&self, tx_request: &TxRequestBuilder) -> Result<TxId> {
+        match self.request_change_address(&tx_request.change_address)? {
+            Some(_) => {
+                let signed_tx = self.generate_transaction_from_request(tx_request)?;
+                self.submit_json_transaction(&signed_tx.to_string())
+            }
+            None => self.generate_and_submit_transaction(&tx_request.build()?),
+        }
+    }
+
+    /// Sends the given payments from the node wallet using
+    /// `/wallet/payment/send`, with inputs, fee and change selected by the
+    /// node. Returns the resulting `TxId`.
+    pub fn send_payment(&self, requests: Vec<PaymentRequest>) -> Result<TxId> {
+        for request in &requests {
+            self.check_address_network(&request.address)?;
+        }
+        let body = serde_json::to_value(&requests).map_err(|e| NodeError::Other(e.to_string()))?;
+        let res_json = self.post_json("/wallet/payment/send", &body)?;
+        let tx_id = parse_tx_id(&res_json)?;
+        self.journal_tx_status(&String::from(tx_id), TxStatus::Submitted, None);
+        Ok(tx_id)
+    }
+
+    /// Generates and submits a transaction from a `TxRequestBuilder`, retrying
+    /// up to `max_retries` times if the node could not be reached. The signed
+    /// transaction is generated once and the same transaction is resubmitted
+    /// on every retry. Before each resubmission the node is checked for the
+    /// `TxId` of said transaction, which is returned if the node already
+    /// knows it, thus a send is never duplicated.
+    pub fn send_transaction_from_request_idempotent(
+        &self,
+        tx_request: &TxRequestBuilder,
+        max_retries: usize,
+    ) -> Result<TxId> {
+        let mut retries = 0;
+        let signed_tx = loop {
+            match self.generate_transaction_from_request(tx_request) {
+                Err(NodeError::NodeUnreachable) if retries < max_retries => {
+                    retries += 1;
+                    std::thread::sleep(SEND_RETRY_DELAY);
+                }
+                res => break res?,
+            }
+        };
+        let tx_id = parse_tx_id(&signed_tx["id"])?;
+        let signed_tx_json = signed_tx.to_string();
+        loop {
+            match self.submit_json_transaction(&signed_tx_json) {
+                Err(NodeError::NodeUnreachable) if retries < max_retries => {
+                    retries += 1;
+                    std::thread::sleep(SEND_RETRY_DELAY);
+                    if self.transaction_known(&tx_id) {
+                        return Ok(tx_id);
+                    }
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Checks whether the node knows the transaction, either in the mempool
+    /// or on chain. Failed lookups are treated as the transaction not being
+    /// found yet.
+    fn transaction_known(&self, tx_id: &TxId) -> bool {
+        self.is_in_mempool(tx_id).unwrap_or(false)
+            || matches!(self.tx_confirmations(tx_id), Ok(Some(_)))
+    }
+
+    /// Resolves the change address of the node wallet according to the
+    /// `ChangeAddressPolicy` of this `NodeInterface`
+    pub fn resolve_change_address(&self) -> Result<P2PKAddressString> {
+        match &self.change_address_policy {
+            ChangeAddressPolicy::WalletChangeAddress => self
+                .wallet_status()?
+                .change_address
+                .ok_or(NodeError::NoAddressesInWallet),
+            ChangeAddressPolicy::FirstWalletAddress => self
+                .wallet_addresses()?
+                .into_iter()
+                .next()
+                .ok_or(NodeError::NoAddressesInWallet),
+            ChangeAddressPolicy::Fixed(address) => Ok(address.clone()),
+        }
+    }
+
+    /// Resolves the change address to be used for a request with the given
+    /// change address override, or `None` if the change address configured
+    /// in the node wallet is used
+    fn request_change_address(
+        &self,
+        change_address: &Option<P2PKAddressString>,
+    ) -> Result<Option<P2PKAddressString>> {
+        match (change_address, &self.change_address_policy) {
+            (Some(address), _) => Ok(Some(address.clone())),
+            (None, ChangeAddressPolicy::WalletChangeAddress) => Ok(None),
+            (None, _) => self.resolve_change_address().map(Some),
+        }
+    }
+
+    /// Generates an `UnsignedTransaction` from `tx_request` and moves
+    /// the change outputs, which the node sends to the wallet change address,
+    /// to `change_address`
+    fn generate_unsigned_with_change(
+        &self,
+        tx_request: &TxRequestBuilder,
+        change_address: &P2PKAddressString,
+    ) -> Result<UnsignedTransaction> {
+        self.check_address_network(change_address)?;
+        let wallet_change_address = self
+            .wallet_status()?
+            .change_address
+            .ok_or(NodeError::NoAddressesInWallet)?;
+        let unsigned_tx = self.post_typed("/wallet/transaction/generateUnsigned", tx_request)?;
+        redirect_change(
+            &unsigned_tx,
+            &tx_request.requests,
+            &address_tree(&wallet_change_address)?,
+            &address_tree(change_address)?,
+        )
+    }
+
+    /// Acquires the boxes with the given ids to be used as data inputs,
+    /// checking the mempool for boxes which are not yet confirmed.
+    pub fn data_input_boxes_from_ids(&self, box_ids: &[BoxId]) -> Result<Vec<ErgoBox>> {
+        box_ids
+            .iter()
+            .map(|id| self.box_from_id_with_mempool(&String::from(*id)))
+            .collect()
+    }
+
+    /// Sign an `UnsignedTransaction`, fetching the data input boxes
+    /// referenced by `data_input_ids` automatically.
+    pub fn sign_transaction_with_data_input_ids(
+        &self,
+        unsigned_tx: &UnsignedTransaction,
+        boxes_to_spend: Option<Vec<ErgoBox>>,
+        data_input_ids: &[BoxId],
+    ) -> Result<Transaction> {
+        let data_input_boxes = self.data_input_boxes_from_ids(data_input_ids)?;
+        self.sign_transaction(unsigned_tx, boxes_to_spend, Some(data_input_boxes))
+    }
+}
+
+/// Decodes hex-encoded sigma-serialized boxes, or `None` if there are none
+fn decode_raw_boxes(boxes_raw: &[String]) -> Result<Option<Vec<ErgoBox>>> {
+    if boxes_raw.is_empty() {
+        return Ok(None);
+    }
+    boxes_raw
+        .iter()
+        .map(|box_raw| {
+            base16::decode(box_raw)
+                .ok()
+                .and_then(|bytes| ErgoBox::sigma_parse_bytes(&bytes).ok())
+                .ok_or_else(|| NodeError::FailedParsingBox(box_raw.clone()))
+        })
+        .collect::<Result<Vec<ErgoBox>>>()
+        .map(Some)
+}
+
+/// Parses an address into the `ErgoTree` protecting its boxes
+fn address_tree(address: &P2SAddressString) -> Result<ErgoTree> {
+    AddressEncoder::unchecked_parse_address_from_str(address)
+        .map_err(|e| NodeError::Other(e.to_string()))?
+        .script()
+        .map_err(|e| NodeError::Other(e.to_string()))
+}
+
+/// Rebuilds an `UnsignedTransaction` generated by the node wallet sending
+/// its change outputs to `change_tree`. The node places the outputs of the
+/// payment and asset issue `requests` first, thus only later outputs
+/// protected by `wallet_change_tree` are considered change.
+fn redirect_change(
+    unsigned_tx: &UnsignedTransaction,
+    requests: &[TxRequestItem],
+    wallet_change_tree: &ErgoTree,
+    change_tree: &ErgoTree,
+) -> Result<UnsignedTransaction> {
+    let requested_outputs = requests
+        .iter()
+        .filter(|r| !matches!(r, TxRequestItem::Burn(_)))
+        .count();
+    let mut outputs = unsigned_tx.output_candidates.as_vec().clone();
+    for output in outputs.iter_mut().skip(requested_outputs) {
+        if output.ergo_tree == *wallet_change_tree {
+            output.ergo_tree = change_tree.clone();
+        }
+    }
+    UnsignedTransaction::new_from_vec(
+        unsigned_tx.inputs.as_vec().clone(),
+        unsigned_tx
+            .data_inputs
+            .as_ref()
+            .map(|d| d.as_vec().clone())
+            .unwrap_or_default(),
+        outputs,
+    )
+    .map_err(|e| NodeError::Other(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{test_tree, TEST_ADDRESS};
+
+    #[test]
+    fn test_tx_request_round_trip() {
+        let tx_request_json = r#"{
+          "requests": [
+            {
+              "address": "3WwbzW6u8hKWBcL1W7kNVMr25s2UHfSBnYtwSHvrRQt7DdPuoXrt",
+              "value": 1000000,
+              "assets": [{"tokenId": "0cd8c9f416e5b1ca9f986a7f10a84191dfb85941619e49e53c0dc30ebf83324b", "amount": 5}],
+              "registers": {"R4": "0e0101"}
+            },
+            {
+              "ergValue": 1000000,
+              "amount": 1000,
+              "name": "Test",
+              "description": "A test token",
+              "decimals": 2
+            },
+            {
+              "assetsToBurn": [{"tokenId": "0cd8c9f416e5b1ca9f986a7f10a84191dfb85941619e49e53c0dc30ebf83324b", "amount": 1}]
+            }
+          ],
+          "fee": 1000000,
+          "inputsRaw": []
+        }"#;
+        let builder = TxRequestBuilder::from_json(tx_request_json).unwrap();
+        assert!(matches!(builder.requests[0], TxRequestItem::Payment(_)));
+        assert!(matches!(builder.requests[1], TxRequestItem::AssetIssue(_)));
+        assert!(matches!(builder.requests[2], TxRequestItem::Burn(_)));
+        assert_eq!(builder.fee, Some(1000000));
+
+        let rebuilt = TxRequestBuilder::from_json(&builder.build().unwrap()).unwrap();
+        assert_eq!(rebuilt, builder);
+
+        let change_address = TEST_ADDRESS.to_string();
+        let with_change = builder.with_change_address(&change_address);
+        assert_eq!(with_change.change_address, Some(change_address));
+        assert_eq!(
+            serde_json::to_value(&with_change).unwrap(),
+            serde_json::from_str::<Value>(&rebuilt.build().unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_redirect_change() {
+        let payment_address = "3WwbzW6u8hKWBcL1W7kNVMr25s2UHfSBnYtwSHvrRQt7DdPuoXrt".to_string();
+        let wallet_change_tree = address_tree(&payment_address).unwrap();
+        let change_tree = test_tree();
+        let output = |value: u64| {
+            json!({
+                "value": value,
+                "ergoTree": wallet_change_tree.to_base16_bytes().unwrap(),
+                "assets": [],
+                "additionalRegisters": {},
+                "creationHeight": 100
+            })
+        };
+        let unsigned_tx: UnsignedTransaction = serde_json::from_value(json!({
+            "inputs": [{"boxId": "ab".repeat(32), "extension": {}}],
+            "dataInputs": [],
+            "outputs": [output(1000000), output(5000000)]
+        }))
+        .unwrap();
+        // The payment goes to the wallet change address too and is kept
+        let requests = vec![TxRequestItem::Payment(PaymentRequest {
+            address: payment_address,
+            value: 1000000,
+            assets: vec![],
+            registers: BTreeMap::new(),
+        })];
+        let redirected =
+            redirect_change(&unsigned_tx, &requests, &wallet_change_tree, &change_tree).unwrap();
+        let outputs = redirected.output_candidates.as_vec();
+        assert_eq!(outputs[0].ergo_tree, wallet_change_tree);
+        assert_eq!(outputs[1].ergo_tree, change_tree);
+        assert_eq!(redirected.inputs, unsigned_tx.inputs);
+    }
+}
@@ -0,0 +1,352 @@
+//! The `TxTracker` struct is defined here which owns a transaction through
+//! its lifecycle from being built to being finalized (or dropped),
+//! rebroadcasting it when it disappears from the mempool, emitting every
+//! state transition to listeners and recording it in the tx journal of the
+//! `NodeInterface`. Tracking can be resumed from the journal after a restart.
+use crate::journal::{JournalEntry, TxStatus};
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use ergo_lib::chain::transaction::Transaction;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default number of confirmations after which a transaction is finalized
+pub const DEFAULT_FINALITY_DEPTH: u64 = 10;
+/// Default number of times a transaction missing from the node is resubmitted
+pub const DEFAULT_MAX_REBROADCASTS: u32 = 3;
+/// Journal detail recorded for every rebroadcast of a tracked transaction
+const REBROADCAST_DETAIL: &str = "rebroadcast";
+
+/// The lifecycle states of a tracked transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxState {
+    /// Signed but not yet submitted
+    Built,
+    /// Submitted (or resubmitted) to the node
+    Submitted,
+    InMempool,
+    /// Included in a block with the given number of confirmations
+    Confirmed(u64),
+    /// Reached the finality depth of the tracker
+    Finalized,
+    /// Disappeared from the node and could not be rebroadcast
+    Dropped,
+}
+
+impl TxState {
+    /// Whether the transaction has left the lifecycle
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, TxState::Finalized | TxState::Dropped)
+    }
+
+    /// The status recorded in the tx journal for this state
+    fn journal_status(&self) -> TxStatus {
+        match self {
+            TxState::Built => TxStatus::Signed,
+            TxState::Submitted | TxState::InMempool | TxState::Confirmed(_) => TxStatus::Submitted,
+            TxState::Finalized => TxStatus::Confirmed,
+            TxState::Dropped => TxStatus::Dropped,
+        }
+    }
+
+    /// The state to resume from given the latest status in the tx journal.
+    /// Submitted transactions resume as `Submitted`, their actual state is
+    /// observed by the next poll.
+    fn from_journal_status(status: TxStatus) -> TxState {
+        match status {
+            TxStatus::Signed => TxState::Built,
+            TxStatus::Submitted => TxState::Submitted,
+            TxStatus::Confirmed => TxState::Finalized,
+            TxStatus::Dropped | TxStatus::Failed => TxState::Dropped,
+        }
+    }
+}
+
+impl fmt::Display for TxState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxState::Built => write!(f, "built"),
+            TxState::Submitted => write!(f, "submitted"),
+            TxState::InMempool => write!(f, "in mempool"),
+            TxState::Confirmed(n) => write!(f, "confirmed ({n} confirmations)"),
+            TxState::Finalized => write!(f, "finalized"),
+            TxState::Dropped => write!(f, "dropped"),
+        }
+    }
+}
+
+/// A change of the state of a tracked transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxTransition {
+    pub tx_id: String,
+    pub from: TxState,
+    pub to: TxState,
+}
+
+type TxTransitionListener = Arc<dyn Fn(&TxTransition) + Send + Sync>;
+
+/// A `TxTracker` drives a signed transaction through its lifecycle
+/// (Built → Submitted → InMempool → Confirmed(n) → Finalized/Dropped).
+/// Confirmations are determined via `tx_confirmations`, thus confirmed
+/// transactions must belong to the node wallet or the blockchain index
+/// must be enabled. If the `NodeInterface` has a tx journal, the signed
+/// transaction and every transition are recorded in it, so the tracker
+/// can be recreated with `TxTracker::resume`.
+#[derive(Clone)]
+pub struct TxTracker {
+    pub node_interface: NodeInterface,
+    pub tx: Transaction,
+    pub finality_depth: u64,
+    pub max_rebroadcasts: u32,
+    state: TxState,
+    rebroadcasts: u32,
+    listeners: Vec<TxTransitionListener>,
+}
+
+impl fmt::Debug for TxTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TxTracker")
+            .field("tx_id", &self.tx_id())
+            .field("state", &self.state)
+            .field("rebroadcasts", &self.rebroadcasts)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TxTracker {
+    /// Create a new `TxTracker` for a signed transaction which has not been
+    /// submitted yet, recording the transaction in the tx journal
+    pub fn new(node_interface: &NodeInterface, tx: Transaction) -> TxTracker {
+        let built = TxState::Built;
+        node_interface.journal_transaction(&tx, built.journal_status(), Some(built.to_string()));
+        TxTracker::with_state(node_interface, tx, built)
+    }
+
+    /// Recreate the `TxTracker` of a transaction from the tx journal of the
+    /// `NodeInterface`, restoring its state and number of rebroadcasts
+    pub fn resume(node_interface: &NodeInterface, tx_id: &str) -> Result<TxTracker> {
+        let journal = node_interface
+            .tx_journal
+            .as_ref()
+            .ok_or_else(|| NodeError::Other("No tx journal is configured.".to_string()))?;
+        TxTracker::from_journal_entries(node_interface, &journal.tx_entries(tx_id)?).ok_or_else(
+            || {
+                NodeError::Other(format!(
+                    "Transaction {tx_id} is not recorded in the tx journal."
+                ))
+            },
+        )
+    }
+
+    /// Recreate a `TxTracker` from the journal entries of its transaction,
+    /// or `None` if the signed transaction was not recorded
+    fn from_journal_entries(
+        node_interface: &NodeInterface,
+        entries: &[JournalEntry],
+    ) -> Option<TxTracker> {
+        let tx = entries.iter().rev().find_map(|entry| entry.tx.clone())?;
+        let state = entries
+            .last()
+            .map(|entry| TxState::from_journal_status(entry.status))
+            .unwrap_or(TxState::Built);
+        let mut tracker = TxTracker::with_state(node_interface, tx, state);
+        tracker.rebroadcasts = entries
+            .iter()
+            .filter(|entry| entry.detail.as_deref() == Some(REBROADCAST_DETAIL))
+            .count() as u32;
+        Some(tracker)
+    }
+
+    /// Create a new `TxTracker` resuming from a previously observed state,
+    /// e.g. for a transaction that was submitted before a restart
+    pub fn with_state(
+        node_interface: &NodeInterface,
+        tx: Transaction,
+        state: TxState,
+    ) -> TxTracker {
+        TxTracker {
+            node_interface: node_interface.clone(),
+            tx,
+            finality_depth: DEFAULT_FINALITY_DEPTH,
+            max_rebroadcasts: DEFAULT_MAX_REBROADCASTS,
+            state,
+            rebroadcasts: 0,
+            listeners: vec![],
+        }
+    }
+
+    /// Set the number of confirmations after which the tx is finalized
+    pub fn with_finality_depth(mut self, finality_depth: u64) -> TxTracker {
+        self.finality_depth = finality_depth;
+        self
+    }
+
+    /// Set how often a tx missing from the node is resubmitted before it
+    /// is considered dropped
+    pub fn with_max_rebroadcasts(mut self, max_rebroadcasts: u32) -> TxTracker {
+        self.max_rebroadcasts = max_rebroadcasts;
+        self
+    }
+
+    /// Register a listener which is called with every state transition
+    pub fn on_transition<F>(mut self, listener: F) -> TxTracker
+    where
+        F: Fn(&TxTransition) + Send + Sync + 'static,
+    {
+        self.listeners.push(Arc::new(listener));
+        self
+    }
+
+    /// The id of the tracked transaction
+    pub fn tx_id(&self) -> String {
+        String::from(self.tx.id())
+    }
+
+    /// The current state of the tracked transaction
+    pub fn state(&self) -> TxState {
+        self.state
+    }
+
+    /// Submits the transaction to the node if it has not been submitted yet
+    pub fn submit(&mut self) -> Result<TxState> {
+        if self.state == TxState::Built {
+            if let Err(e) = self.node_interface.post_transaction(&self.tx) {
+                self.node_interface
+                    .journal_failed_submission(&self.tx_id(), &e);
+                return Err(e);
+            }
+            self.transition(TxState::Submitted);
+        }
+        Ok(self.state)
+    }
+
+    /// Checks the node once and advances the state of the transaction. A
+    /// submitted transaction which is neither in the mempool nor on chain
+    /// (e.g. after being evicted or reorged out) is rebroadcast up to
+    /// `max_rebroadcasts` times, after which it is dropped. It is dropped
+    /// right away if the node rejects it as invalid (e.g. a double spend),
+    /// other failures are returned and the next poll retries.
+    pub fn poll(&mut self) -> Result<TxState> {
+        if self.state == TxState::Built || self.state.is_terminal() {
+            return Ok(self.state);
+        }
+        let confirmations = self.node_interface.tx_confirmations(&self.tx.id())?;
+        match observed_state(confirmations, self.finality_depth) {
+            Some(state) => self.transition(state),
+            None if self.rebroadcasts < self.max_rebroadcasts => {
+                match self.node_interface.post_transaction(&self.tx) {
+                    Ok(_) => {
+                        self.rebroadcasts += 1;
+                        self.record_transition(TxState::Submitted, REBROADCAST_DETAIL);
+                    }
+                    Err(e) if is_rejection(&e) => self.transition(TxState::Dropped),
+                    Err(e) => return Err(e),
+                }
+            }
+            None => self.transition(TxState::Dropped),
+        }
+        Ok(self.state)
+    }
+
+    /// Submits the transaction if needed and blocks the current thread,
+    /// polling the node every `interval` until the transaction is
+    /// finalized or dropped. Returns the final state.
+    pub fn run(&mut self, interval: Duration) -> Result<TxState> {
+        self.submit()?;
+        while !self.poll()?.is_terminal() {
+            std::thread::sleep(self.node_interface.poll_interval(interval));
+        }
+        Ok(self.state)
+    }
+
+    /// Moves to the new state if it differs from the current state
+    fn transition(&mut self, to: TxState) {
+        if to != self.state {
+            self.record_transition(to, &to.to_string());
+        }
+    }
+
+    /// Moves to the new state, recording it with `detail` in the tx journal
+    /// and notifying the listeners if it differs from the current state
+    fn record_transition(&mut self, to: TxState, detail: &str) {
+        let transition = TxTransition {
+            tx_id: self.tx_id(),
+            from: self.state,
+            to,
+        };
+        self.state = to;
+        self.node_interface.journal_tx_status(
+            &transition.tx_id,
+            to.journal_status(),
+            Some(detail.to_string()),
+        );
+        if transition.from != to {
+            for listener in &self.listeners {
+                listener(&transition);
+            }
+        }
+    }
+}
+
+impl NodeInterface {
+    /// Create a `TxTracker` for a signed transaction which has not been
+    /// submitted yet
+    pub fn track_transaction(&self, tx: Transaction) -> TxTracker {
+        TxTracker::new(self, tx)
+    }
+
+    /// Recreate the `TxTracker`s of all transactions in the tx journal which
+    /// were tracked and are not yet confirmed, dropped or failed
+    pub fn resume_tracked_transactions(&self) -> Result<Vec<TxTracker>> {
+        let journal = match &self.tx_journal {
+            Some(journal) => journal,
+            None => return Ok(vec![]),
+        };
+        let entries = journal.entries()?;
+        // Transactions sent without a tracker have no stored tx and are skipped
+        Ok(journal
+            .pending_tx_ids()?
+            .iter()
+            .filter_map(|tx_id| {
+                let tx_entries: Vec<JournalEntry> = entries
+                    .iter()
+                    .filter(|entry| entry.tx_id == *tx_id)
+                    .cloned()
+                    .collect();
+                TxTracker::from_journal_entries(self, &tx_entries)
+            })
+            .collect())
+    }
+}
+
+/// Checks whether a submission failed as the node rejects the transaction
+/// as invalid (e.g. its inputs are already spent), rather than failing to
+/// process it
+fn is_rejection(e: &NodeError) -> bool {
+    matches!(e, NodeError::Api(api_error) if api_error.error == 400)
+}
+
+/// The state of a transaction given its confirmations as reported by the
+/// node, or `None` if the node does not know the transaction
+fn observed_state(confirmations: Option<u64>, finality_depth: u64) -> Option<TxState> {
+    match confirmations? {
+        0 => Some(TxState::InMempool),
+        n if n >= finality_depth => Some(TxState::Finalized),
+        n => Some(TxState::Confirmed(n)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observed_state() {
+        assert_eq!(observed_state(None, 10), None);
+        assert_eq!(observed_state(Some(0), 10), Some(TxState::InMempool));
+        assert_eq!(observed_state(Some(3), 10), Some(TxState::Confirmed(3)));
+        assert_eq!(observed_state(Some(10), 10), Some(TxState::Finalized));
+        assert_eq!(TxState::InMempool.journal_status(), TxStatus::Submitted);
+        assert!(TxState::Dropped.is_terminal());
+    }
+}
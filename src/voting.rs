@@ -0,0 +1,271 @@
+//! Helpers for decoding the parameter votes in block headers and the
+//! protocol parameters stored in block extensions, allowing the status of
+//! ongoing parameter and soft-fork voting to be reported. The votes cast by
+//! a mining node are configured through the `ergo.voting` section of its
+//! config as the node API provides no setters, which `VotingPolicy` renders.
+use crate::node_interface::{encode_param, NodeError, NodeInterface, Result};
+use crate::BlockHeight;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+/// Number of blocks in a voting epoch (on both mainnet and testnet)
+pub const VOTING_EPOCH_LENGTH: BlockHeight = 1024;
+
+/// Id of the vote for the activation of a soft-fork
+pub const SOFT_FORK_PARAMETER_ID: u8 = 120;
+
+/// Maximum number of parameter votes (excluding the soft-fork vote) a miner
+/// can cast within a single block header
+pub const MAX_PARAMETER_VOTES: usize = 2;
+
+/// Number of headers requested from the node at once
+const HEADERS_CHUNK_SIZE: BlockHeight = 1000;
+
+/// Returns the name of a protocol parameter given its id
+pub fn parameter_name(parameter_id: u8) -> &'static str {
+    match parameter_id {
+        1 => "storageFeeFactor",
+        2 => "minValuePerByte",
+        3 => "maxBlockSize",
+        4 => "maxBlockCost",
+        5 => "tokenAccessCost",
+        6 => "inputCost",
+        7 => "dataInputCost",
+        8 => "outputCost",
+        120 => "softFork",
+        121 => "softForkVotesCollected",
+        122 => "softForkStartingHeight",
+        123 => "blockVersion",
+        _ => "unknown",
+    }
+}
+
+/// A single vote cast by a miner within a block header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Vote {
+    pub parameter_id: u8,
+    /// Whether the vote is to increase (or decrease) the parameter.
+    /// Always `true` for soft-fork votes.
+    pub increase: bool,
+}
+
+/// Decodes the votes of a block header, given as the hex-encoded 3 vote
+/// bytes. Empty vote slots are skipped.
+pub fn decode_votes(votes_hex: &str) -> Result<Vec<Vote>> {
+    let bytes = base16::decode(votes_hex).map_err(|e| NodeError::Other(e.to_string()))?;
+    Ok(bytes
+        .iter()
+        .filter(|b| **b != 0)
+        .map(|b| {
+            let signed = *b as i8;
+            Vote {
+                parameter_id: signed.unsigned_abs(),
+                increase: signed > 0 || *b == SOFT_FORK_PARAMETER_ID,
+            }
+        })
+        .collect())
+}
+
+/// Decodes the protocol parameters from the `[key, value]` fields of a block
+/// extension. Parameter fields have a key of `00` followed by the parameter id.
+pub fn decode_parameters(fields: &[(String, String)]) -> BTreeMap<u8, i32> {
+    fields
+        .iter()
+        .filter_map(|(key, value)| {
+            let key = base16::decode(key).ok()?;
+            let value = base16::decode(value).ok()?;
+            match (key.as_slice(), <[u8; 4]>::try_from(value.as_slice())) {
+                ([0, parameter_id], Ok(bytes)) => Some((*parameter_id, i32::from_be_bytes(bytes))),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// The target protocol parameter values a miner votes towards, and whether
+/// it votes for a soft-fork
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VotingPolicy {
+    pub targets: BTreeMap<u8, i32>,
+    pub soft_fork: bool,
+}
+
+impl VotingPolicy {
+    /// Create a new empty `VotingPolicy`
+    pub fn new() -> VotingPolicy {
+        VotingPolicy::default()
+    }
+
+    /// Vote towards the given target value of a parameter
+    pub fn with_target(mut self, parameter_id: u8, value: i32) -> VotingPolicy {
+        self.targets.insert(parameter_id, value);
+        self
+    }
+
+    /// Vote for the activation of a soft-fork
+    pub fn with_soft_fork(mut self) -> VotingPolicy {
+        self.soft_fork = true;
+        self
+    }
+
+    /// The votes a node with this policy casts given the current protocol
+    /// parameters, matching the node's choice of the first parameters (by
+    /// id) which differ from their target.
+    pub fn expected_votes(&self, current: &BTreeMap<u8, i32>) -> Vec<Vote> {
+        let mut votes: Vec<Vote> = self
+            .targets
+            .iter()
+            .filter_map(|(id, target)| {
+                let value = current.get(id)?;
+                (value != target).then_some(Vote {
+                    parameter_id: *id,
+                    increase: target > value,
+                })
+            })
+            .take(MAX_PARAMETER_VOTES)
+            .collect();
+        if self.soft_fork {
+            votes.push(Vote {
+                parameter_id: SOFT_FORK_PARAMETER_ID,
+                increase: true,
+            });
+        }
+        votes
+    }
+
+    /// Renders the policy as the `ergo.voting` section of a node config
+    pub fn to_node_config(&self) -> String {
+        let mut lines = vec!["ergo {".to_string(), "  voting {".to_string()];
+        for (id, target) in &self.targets {
+            lines.push(format!("    # {}", parameter_name(*id)));
+            lines.push(format!("    {id} = {target}"));
+        }
+        if self.soft_fork {
+            lines.push(format!("    {SOFT_FORK_PARAMETER_ID} = 1"));
+        }
+        lines.push("  }".to_string());
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+}
+
+impl NodeInterface {
+    /// The votes a node with the given `VotingPolicy` would cast in the next
+    /// block, based on the parameters currently in effect
+    pub fn expected_votes(&self, policy: &VotingPolicy) -> Result<Vec<Vote>> {
+        let current = self.parameters_at_height(self.current_block_height()?)?;
+        Ok(policy.expected_votes(&current))
+    }
+
+    /// Counts the votes cast in the headers from `from_height` to
+    /// `to_height` (inclusive)
+    pub fn votes_in_range(
+        &self,
+        from_height: BlockHeight,
+        to_height: BlockHeight,
+    ) -> Result<BTreeMap<Vote, u32>> {
+        let mut tally = BTreeMap::new();
+        let mut chunk_start = from_height;
+        while chunk_start <= to_height {
+            let chunk_end = (chunk_start + HEADERS_CHUNK_SIZE - 1).min(to_height);
+            for header in self.chain_slice(chunk_start, chunk_end)? {
+                for vote in decode_votes(&base16::encode_lower(&header.votes.0))? {
+                    *tally.entry(vote).or_insert(0) += 1;
+                }
+            }
+            chunk_start = chunk_end + 1;
+        }
+        Ok(tally)
+    }
+
+    /// Counts the votes cast so far within the current voting epoch
+    pub fn current_epoch_votes(&self) -> Result<BTreeMap<Vote, u32>> {
+        let height = self.current_block_height()?;
+        let epoch_start = height - height % VOTING_EPOCH_LENGTH;
+        self.votes_in_range(epoch_start, height)
+    }
+
+    /// Get the protocol parameters in effect at the given height, read from
+    /// the extension of the first block of its voting epoch
+    pub fn parameters_at_height(&self, height: BlockHeight) -> Result<BTreeMap<u8, i32>> {
+        let epoch_start = height - height % VOTING_EPOCH_LENGTH;
+        let header_id = self.block_id_at_height(epoch_start)?;
+        let block = self.get_json(&format!("/blocks/{}", encode_param(&header_id)))?;
+        let fields: Vec<(String, String)> =
+            serde_json::from_value(block["extension"]["fields"].clone())
+                .map_err(|_| NodeError::FailedParsingNodeResponse(block.to_string()))?;
+        Ok(decode_parameters(&fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_votes() {
+        let votes = decode_votes("03fe78").unwrap();
+        assert_eq!(
+            votes,
+            vec![
+                Vote {
+                    parameter_id: 3,
+                    increase: true
+                },
+                Vote {
+                    parameter_id: 2,
+                    increase: false
+                },
+                Vote {
+                    parameter_id: SOFT_FORK_PARAMETER_ID,
+                    increase: true
+                },
+            ]
+        );
+        assert!(decode_votes("000000").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_decode_parameters() {
+        let fields = vec![
+            ("0001".to_string(), "00080000".to_string()),
+            ("0003".to_string(), "0007a120".to_string()),
+            ("0100".to_string(), "00000001".to_string()),
+        ];
+        let parameters = decode_parameters(&fields);
+        assert_eq!(parameters.len(), 2);
+        assert_eq!(parameters[&1], 524288);
+        assert_eq!(parameters[&3], 500000);
+    }
+
+    #[test]
+    fn test_voting_policy() {
+        let policy = VotingPolicy::new()
+            .with_target(1, 1_250_000)
+            .with_target(3, 1_000_000)
+            .with_target(4, 7_000_000)
+            .with_target(8, 100)
+            .with_soft_fork();
+        let current = BTreeMap::from([(1, 1_250_000), (3, 1_271_009), (4, 8_001_091), (8, 100)]);
+        assert_eq!(
+            policy.expected_votes(&current),
+            vec![
+                Vote {
+                    parameter_id: 3,
+                    increase: false
+                },
+                Vote {
+                    parameter_id: 4,
+                    increase: false
+                },
+                Vote {
+                    parameter_id: SOFT_FORK_PARAMETER_ID,
+                    increase: true
+                },
+            ]
+        );
+        assert!(policy
+            .to_node_config()
+            .contains("    # maxBlockSize\n    3 = 1000000"));
+    }
+}
@@ -0,0 +1,51 @@
+//! A composite snapshot of the node wallet state which is gathered
+//! concurrently in a single call.
+use crate::node_interface::{NodeInterface, Result, WalletStatus};
+use crate::{NanoErg, P2PKAddressString};
+use std::thread;
+
+/// The state of the node wallet at (approximately) a single point in time
+#[derive(Debug, Clone)]
+pub struct WalletSnapshot {
+    pub status: WalletStatus,
+    /// Confirmed nanoErgs balance of the wallet
+    pub balance: NanoErg,
+    pub addresses: Vec<P2PKAddressString>,
+    /// Number of unspent boxes held by the wallet
+    pub unspent_box_count: usize,
+    /// Sum of the nanoErgs held in the unspent boxes of the wallet
+    pub unspent_total: NanoErg,
+}
+
+impl NodeInterface {
+    /// Acquires the wallet status, balance, addresses and unspent boxes
+    /// concurrently and combines them into a `WalletSnapshot`. The first
+    /// error encountered (in the order listed) is returned.
+    pub fn wallet_snapshot(&self) -> Result<WalletSnapshot> {
+        thread::scope(|scope| {
+            let status = scope.spawn(|| self.wallet_status());
+            let balance = scope.spawn(|| self.wallet_nano_ergs_balance());
+            let addresses = scope.spawn(|| self.wallet_addresses());
+            let unspent_boxes = scope.spawn(|| self.unspent_boxes());
+
+            let status = join(status)?;
+            let balance = join(balance)?;
+            let addresses = join(addresses)?;
+            let unspent_boxes = join(unspent_boxes)?;
+            Ok(WalletSnapshot {
+                status,
+                balance,
+                addresses,
+                unspent_box_count: unspent_boxes.len(),
+                unspent_total: unspent_boxes.iter().map(|b| *b.value.as_u64()).sum(),
+            })
+        })
+    }
+}
+
+/// Joins a scoped request thread, resuming the panic if the thread panicked
+fn join<T>(handle: thread::ScopedJoinHandle<'_, Result<T>>) -> Result<T> {
+    handle
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
@@ -0,0 +1,138 @@
+//! Typed access to the transaction history of the node wallet.
+use crate::blockchain::IndexedTransaction;
+use crate::node_interface::{NodeError, NodeInterface, Result};
+use crate::transactions::is_miner_fee_tree;
+use crate::{BlockHeight, NanoErg, ScanID};
+use ergo_lib::chain::transaction::{Input, TxId};
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use serde::Deserialize;
+
+/// A transaction of the node wallet as returned by `/wallet/transactions`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletTransaction {
+    pub id: TxId,
+    pub inputs: Vec<Input>,
+    pub outputs: Vec<ErgoBox>,
+    pub inclusion_height: BlockHeight,
+    #[serde(rename = "numConfirmations")]
+    pub confirmations: u64,
+    /// Ids of the scans (including the wallet, `10`) the transaction belongs to
+    #[serde(rename = "scans", default)]
+    pub scan_ids: Vec<u32>,
+}
+
+impl WalletTransaction {
+    /// Checks whether the transaction belongs to the given scan
+    pub fn belongs_to_scan(&self, scan_id: &ScanID) -> bool {
+        self.scan_ids.iter().any(|id| id.to_string() == *scan_id)
+    }
+}
+
+impl NodeInterface {
+    /// Get the transactions of the node wallet which were included within
+    /// the given height range and have at least `min_confirmations`. Large
+    /// histories can be paged through by querying consecutive height ranges.
+    pub fn wallet_transactions(
+        &self,
+        min_inclusion_height: Option<BlockHeight>,
+        max_inclusion_height: Option<BlockHeight>,
+        min_confirmations: Option<u64>,
+    ) -> Result<Vec<WalletTransaction>> {
+        let params: Vec<String> = [
+            ("minInclusionHeight", min_inclusion_height),
+            ("maxInclusionHeight", max_inclusion_height),
+            ("minConfirmations", min_confirmations),
+        ]
+        .iter()
+        .filter_map(|(key, value)| value.map(|v| format!("{key}={v}")))
+        .collect();
+        let endpoint = format!("/wallet/transactions?{}", params.join("&"));
+        let res_json = self.get_json(&endpoint)?;
+        serde_json::from_value(res_json)
+            .map_err(|e| NodeError::FailedParsingNodeResponse(e.to_string()))
+    }
+
+    /// Get the fee actually paid by a confirmed transaction, i.e. the sum
+    /// of its outputs protected by the miner fee contract. Requires the
+    /// blockchain index.
+    pub fn tx_fee(&self, tx_id: &TxId) -> Result<NanoErg> {
+        Ok(paid_fee(&self.indexed_transaction_by_id(tx_id)?))
+    }
+
+    /// Get the total fees paid by the node wallet for transactions included
+    /// between the given heights (both inclusive). Only transactions which
+    /// spend boxes of the wallet addresses are counted, incoming payments
+    /// are ignored.
+    pub fn fees_paid_between(
+        &self,
+        from_height: BlockHeight,
+        to_height: BlockHeight,
+    ) -> Result<NanoErg> {
+        self.require_indexer()?;
+        let wallet_addresses = self.wallet_addresses()?;
+        let mut total = 0;
+        for tx in self.wallet_transactions(Some(from_height), Some(to_height), None)? {
+            let indexed_tx = self.indexed_transaction_by_id(&tx.id)?;
+            let funded_by_wallet = indexed_tx
+                .inputs
+                .iter()
+                .any(|i| wallet_addresses.contains(&i.address));
+            if funded_by_wallet {
+                total += paid_fee(&indexed_tx);
+            }
+        }
+        Ok(total)
+    }
+}
+
+/// Sum of the values of the miner fee outputs of an indexed transaction
+fn paid_fee(tx: &IndexedTransaction) -> NanoErg {
+    tx.outputs
+        .iter()
+        .filter(|o| is_miner_fee_tree(&o.ergo_box.ergo_tree))
+        .map(|o| *o.ergo_box.value.as_u64())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::test_box;
+    use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+    use ergo_lib::wallet::miner_fee::MINERS_FEE_BASE16_BYTES;
+    use serde_json::{json, Value};
+
+    fn indexed_box_json(value: NanoErg, ergo_tree_hex: &str, index: u16) -> Value {
+        let ergo_tree =
+            ErgoTree::sigma_parse_bytes(&base16::decode(ergo_tree_hex).unwrap()).unwrap();
+        let ergo_box = test_box(value, &ergo_tree, 1000000, index);
+        let mut box_json = serde_json::to_value(ergo_box).unwrap();
+        box_json["inclusionHeight"] = json!(1000001);
+        box_json["address"] = json!("9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA");
+        box_json
+    }
+
+    #[test]
+    fn test_paid_fee() {
+        let p2pk = "0008cd02764ea2b0b9b06b5730a4257bba71fd7797eb1ec12bc3ae6025a01d7fba53830e";
+        // Inputs and outputs hold the same value, the fee is an explicit output
+        let tx: IndexedTransaction = serde_json::from_value(json!({
+            "id": "cd".repeat(32),
+            "blockId": "ef".repeat(32),
+            "inclusionHeight": 1000001,
+            "timestamp": 1700000000000u64,
+            "index": 0,
+            "numConfirmations": 5,
+            "inputs": [indexed_box_json(2_000_000, p2pk, 0), indexed_box_json(1_500_000, p2pk, 1)],
+            "outputs": [
+                indexed_box_json(2_400_000, p2pk, 0),
+                indexed_box_json(1_000_000, MINERS_FEE_BASE16_BYTES, 1),
+                indexed_box_json(100_000, MINERS_FEE_BASE16_BYTES, 2),
+            ],
+        }))
+        .unwrap();
+        assert_eq!(paid_fee(&tx), 1_100_000);
+    }
+}
@@ -0,0 +1,167 @@
+//! The `AddressWatcher` struct is defined here which allows for monitoring
+//! an address for incoming payments using the node's blockchain index.
+use crate::node_interface::{NodeInterface, Result};
+use crate::{BlockHeight, NanoErg, P2PKAddressString, TokenID};
+use ergo_lib::chain::transaction::TxId;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+/// Number of indexed boxes requested from the node per page
+const PAGE_LIMIT: usize = 100;
+
+/// A payment received by a watched address within a single transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncomingPayment {
+    pub tx_id: String,
+    pub value: NanoErg,
+    pub tokens: Vec<(TokenID, u64)>,
+    pub height: BlockHeight,
+}
+
+/// An `AddressWatcher` keeps track of which payments to an address
+/// have already been reported.
+///
+/// The watcher keeps an offset into the indexed boxes of the address, so
+/// each poll only requests the boxes received since the previous one.
+#[derive(Debug, Clone)]
+pub struct AddressWatcher {
+    pub address: P2PKAddressString,
+    pub min_confirmations: u64,
+    pub node_interface: NodeInterface,
+    /// Number of indexed boxes of the address which were already processed
+    offset: usize,
+    /// Transactions reported recently, together with their inclusion height
+    seen_txs: HashMap<String, BlockHeight>,
+}
+
+impl AddressWatcher {
+    /// Create a new `AddressWatcher` for the given address which reports
+    /// payments once they have at least `min_confirmations` confirmations.
+    pub fn new(
+        address: &P2PKAddressString,
+        min_confirmations: u64,
+        node_interface: &NodeInterface,
+    ) -> AddressWatcher {
+        AddressWatcher {
+            address: address.clone(),
+            min_confirmations,
+            node_interface: node_interface.clone(),
+            offset: 0,
+            seen_txs: HashMap::new(),
+        }
+    }
+
+    /// Create an `AddressWatcher` which skips the first `offset` indexed
+    /// boxes of the address, e.g. to resume from a previous `offset()`.
+    pub fn resume_from(mut self, offset: usize) -> AddressWatcher {
+        self.offset = offset;
+        self
+    }
+
+    /// Number of indexed boxes of the address which were already processed
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns all payments to the watched address which have reached the
+    /// required number of confirmations and were not returned by a previous poll.
+    /// Transactions spending boxes of the watched address (i.e. change sent
+    /// back to it) are not reported as payments.
+    pub fn poll(&mut self) -> Result<Vec<IncomingPayment>> {
+        self.node_interface.require_indexer()?;
+        self.node_interface.check_address_network(&self.address)?;
+        let current_height = self.node_interface.current_block_height()?;
+        // Boxes included at or below this height have enough confirmations
+        let horizon = (current_height + 1).saturating_sub(self.min_confirmations);
+        let mut payments: BTreeMap<String, IncomingPayment> = BTreeMap::new();
+        let mut tx_ids: HashMap<String, TxId> = HashMap::new();
+
+        'pages: loop {
+            let items = self
+                .node_interface
+                .boxes_by_address(&self.address, self.offset, PAGE_LIMIT)?
+                .items;
+            for item in &items {
+                let height = item.inclusion_height;
+                if height > horizon {
+                    break 'pages;
+                }
+                self.offset += 1;
+                let tx_id = String::from(item.ergo_box.transaction_id);
+                if self.seen_txs.contains_key(&tx_id) {
+                    continue;
+                }
+                tx_ids.insert(tx_id.clone(), item.ergo_box.transaction_id);
+
+                let payment = payments
+                    .entry(tx_id.clone())
+                    .or_insert_with(|| IncomingPayment {
+                        tx_id,
+                        value: 0,
+                        tokens: vec![],
+                        height,
+                    });
+                payment.value += item.ergo_box.value.as_u64();
+                for token in item.ergo_box.tokens.iter().flatten() {
+                    let token_id = String::from(token.token_id);
+                    let amount = *token.amount.as_u64();
+                    match payment.tokens.iter_mut().find(|(id, _)| *id == token_id) {
+                        Some((_, total)) => *total += amount,
+                        None => payment.tokens.push((token_id, amount)),
+                    }
+                }
+            }
+            if items.len() < PAGE_LIMIT {
+                break;
+            }
+        }
+
+        let mut new_payments = vec![];
+        for payment in payments.into_values() {
+            self.seen_txs.insert(payment.tx_id.clone(), payment.height);
+            if !self.is_change(&tx_ids[&payment.tx_id])? {
+                new_payments.push(payment);
+            }
+        }
+        new_payments.sort_by_key(|p| p.height);
+        // Transactions below the horizon are behind the offset and can no
+        // longer be returned by the indexer
+        self.seen_txs.retain(|_, height| *height >= horizon);
+        Ok(new_payments)
+    }
+
+    /// Checks whether a transaction spends boxes of the watched address
+    fn is_change(&self, tx_id: &TxId) -> Result<bool> {
+        let tx = self.node_interface.indexed_transaction_by_id(tx_id)?;
+        Ok(tx.inputs.iter().any(|input| input.address == self.address))
+    }
+
+    /// Blocks the current thread, polling the node every `interval` and
+    /// passing each new payment to `callback`. Watching stops when the
+    /// callback returns `false` or an error is encountered.
+    pub fn watch<F>(&mut self, interval: Duration, mut callback: F) -> Result<()>
+    where
+        F: FnMut(IncomingPayment) -> bool,
+    {
+        loop {
+            for payment in self.poll()? {
+                if !callback(payment) {
+                    return Ok(());
+                }
+            }
+            std::thread::sleep(self.node_interface.poll_interval(interval));
+        }
+    }
+}
+
+impl NodeInterface {
+    /// Create an `AddressWatcher` which reports incoming payments to
+    /// `address` once they have at least `min_confirmations` confirmations.
+    pub fn watch_address(
+        &self,
+        address: &P2PKAddressString,
+        min_confirmations: u64,
+    ) -> AddressWatcher {
+        AddressWatcher::new(address, min_confirmations, self)
+    }
+}
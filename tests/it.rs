@@ -0,0 +1,142 @@
+//! Integration tests against a real node, run with `cargo test --features it`.
+//! See `ergo_node_interface::harness` for how the node is configured.
+#![cfg(feature = "it")]
+
+use ergo_node_interface::capabilities::NodeFeature;
+use ergo_node_interface::harness::NodeHarness;
+use ergo_node_interface::scanning::ScanRegistrationRequest;
+use ergo_node_interface::TrackingRule;
+use std::sync::{Arc, Mutex, Weak};
+
+/// The harness shared by all tests which are running at the same time, so
+/// only one node container runs. It is dropped, removing the container, once
+/// the last of these tests finishes.
+static HARNESS: Mutex<Option<Weak<NodeHarness>>> = Mutex::new(None);
+
+/// Returns the shared harness, creating it if no running test holds it.
+/// The node wallet is provisioned from `ERGO_IT_MNEMONIC` (if set, otherwise
+/// the wallet must already be initialized) and unlocked with `ERGO_IT_WALLET_PASS`
+fn harness() -> Arc<NodeHarness> {
+    let mut shared = HARNESS.lock().unwrap_or_else(|p| p.into_inner());
+    if let Some(harness) = shared.as_ref().and_then(Weak::upgrade) {
+        return harness;
+    }
+    let harness = NodeHarness::from_env().unwrap();
+    let password = std::env::var("ERGO_IT_WALLET_PASS").unwrap_or_default();
+    match std::env::var("ERGO_IT_MNEMONIC") {
+        Ok(mnemonic) => harness.provision_wallet(&mnemonic, &password).unwrap(),
+        Err(_) => {
+            harness.node.wallet_unlock(&password).unwrap();
+        }
+    }
+    let harness = Arc::new(harness);
+    *shared = Some(Arc::downgrade(&harness));
+    harness
+}
+
+#[test]
+fn test_info_endpoints() {
+    let harness = harness();
+    let node = &harness.node;
+    assert!(node.current_block_height().is_ok());
+    assert!(node.capabilities().is_ok());
+    assert!(node.mempool_size().is_ok());
+    assert!(node.unconfirmed_transactions(10, 0).is_ok());
+    assert!(node.all_peers().is_ok());
+    assert!(node.connected_peers().is_ok());
+    assert!(node.diagnostics().node_info.is_ok());
+}
+
+#[test]
+fn test_block_endpoints() {
+    let harness = harness();
+    let node = &harness.node;
+    let height = node.current_block_height().unwrap();
+    let headers = node.last_headers(10).unwrap();
+    assert!(!headers.is_empty());
+    let header_id = node.block_id_at_height(height).unwrap();
+    assert!(node.block_header(&header_id).is_ok());
+    assert!(node.block_transactions(&header_id).is_ok());
+    assert!(node.full_block(&header_id).is_ok());
+    assert!(node.chain_slice(height.saturating_sub(5), height).is_ok());
+    assert!(node.average_block_time().is_ok());
+    assert!(node.emission_at(height).is_ok());
+    assert!(node.emission_scripts().is_ok());
+    assert!(node.parameters_at_height(height).is_ok());
+    assert!(node.state_context().is_ok());
+}
+
+#[test]
+fn test_wallet_endpoints() {
+    let harness = harness();
+    let node = &harness.node;
+    let status = node.wallet_status().unwrap();
+    assert!(status.initialized && status.unlocked);
+    assert!(!node.wallet_addresses().unwrap().is_empty());
+    assert!(node.wallet_balances().is_ok());
+    assert!(node.wallet_balances_unconfirmed().is_ok());
+    assert!(node.unspent_boxes().is_ok());
+    assert!(node.wallet_unspent_boxes_by_address().is_ok());
+    assert!(node.wallet_token_inventory().is_ok());
+    assert!(node.wallet_transactions(None, None, None).is_ok());
+}
+
+#[test]
+fn test_scan_endpoints() {
+    let harness = harness();
+    let node = &harness.node;
+    let address = node.wallet_addresses().unwrap().remove(0);
+    let tracking_rule = TrackingRule::address(&address).unwrap();
+    let scan_name = format!("it-scan-{}", std::process::id());
+    let scan_id = node
+        .register_scan(&ScanRegistrationRequest::new(&scan_name, tracking_rule))
+        .unwrap();
+    let listed = node
+        .list_all_scans()
+        .unwrap()
+        .iter()
+        .any(|scan| scan.scan_id.to_string() == scan_id);
+    let boxes = node.scan_boxes(&scan_id);
+    // Deregister before asserting so a failure does not leave the scan behind
+    node.deregister_scan(&scan_id).unwrap();
+    assert!(listed);
+    assert!(boxes.is_ok());
+    assert!(node
+        .list_all_scans()
+        .unwrap()
+        .iter()
+        .all(|scan| scan.scan_id.to_string() != scan_id));
+}
+
+#[test]
+fn test_script_endpoints() {
+    let harness = harness();
+    let node = &harness.node;
+    let address = node.wallet_addresses().unwrap().remove(0);
+    let raw = node.p2pk_to_raw(&address).unwrap();
+    assert_eq!(node.raw_to_p2pk(&raw).unwrap(), address);
+    assert!(node.check_address(&address).unwrap().is_valid);
+    assert!(node.blake2b_hash("hello").is_ok());
+    let p2s = node.compile_p2s("sigmaProp(HEIGHT > 100)").unwrap();
+    let tree = node.p2s_to_tree(&p2s).unwrap();
+    let tree_hex = tree.to_base16_bytes().unwrap();
+    assert_eq!(node.tree_to_address(&tree_hex).unwrap(), p2s);
+    assert!(node.p2s_to_bytes(&p2s).is_ok());
+}
+
+#[test]
+fn test_optional_feature_endpoints() {
+    let harness = harness();
+    let node = &harness.node;
+    let capabilities = node.capabilities().unwrap();
+    let address = node.wallet_addresses().unwrap().remove(0);
+    if capabilities.supports(NodeFeature::Indexer) {
+        assert!(node.indexer_lag().is_ok());
+        assert!(node.address_balance(&address).is_ok());
+        assert!(node.unspent_boxes_by_address(&address, 0, 10).is_ok());
+        assert!(node.transactions_by_address(&address, 0, 10).is_ok());
+    }
+    if capabilities.supports(NodeFeature::Mining) {
+        assert!(node.mining_reward_address().is_ok());
+    }
+}